@@ -10,7 +10,7 @@ use once_cell::sync::Lazy;
 
 use reqwest::header::{HeaderName, HeaderValue};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
 use std::sync::{Arc, RwLock};
 
 use wasmtime::component::__internal::async_trait;
@@ -72,9 +72,52 @@ impl Deref for State {
     }
 }
 
+/// The folder plugins can read and write files in.
+pub(crate) const SANDBOX: &str = "./sandbox";
+
+/// Resolve a path a plugin passed to the host inside the sandbox folder. Absolute paths, paths with `..` and symlinks that point
+/// outside of the sandbox are rejected. The path doesn't need to exist yet.
+pub(crate) fn sandboxed_path(path: &str) -> anyhow::Result<PathBuf> {
+    let relative = Path::new(path);
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        anyhow::bail!("The path {path:?} must be relative to the sandbox and can't contain `..`");
+    }
+    let sandbox = Path::new(SANDBOX).canonicalize()?;
+
+    // Resolve the symlinks in the part of the path that exists. The rest of the path is created inside of that part later
+    let mut existing = sandbox.join(relative);
+    let mut missing = Vec::new();
+    while existing.symlink_metadata().is_err() {
+        let Some(name) = existing.file_name() else {
+            break;
+        };
+        missing.push(name.to_owned());
+        existing.pop();
+    }
+    let mut resolved = existing.canonicalize()?;
+    if !resolved.starts_with(&sandbox) {
+        anyhow::bail!("The path {path:?} is outside of the sandbox");
+    }
+    resolved.extend(missing.into_iter().rev());
+    Ok(resolved)
+}
+
+/// Get the path a plugin sees for a path inside the sandbox folder. The sandbox is the root of the plugin's file system, so the
+/// path is relative to the sandbox.
+pub(crate) fn plugin_path(path: &Path) -> anyhow::Result<String> {
+    let sandbox = Path::new(SANDBOX).canonicalize()?;
+    let relative = path
+        .strip_prefix(&sandbox)
+        .map_err(|_| anyhow::anyhow!("The path {path:?} is outside of the sandbox"))?;
+    Ok(relative.to_string_lossy().to_string())
+}
+
 impl State {
     pub fn new(shared: SharedPluginState) -> Self {
-        let sandbox = Path::new("./sandbox");
+        let sandbox = Path::new(SANDBOX);
         std::fs::create_dir_all(sandbox).unwrap();
         let mut ctx = WasiCtxBuilder::new();
         let ctx_builder = ctx
@@ -166,6 +209,73 @@ impl main::types::Host for State {
         self.resources.impl_page_html(self_).await
     }
 
+    async fn wait_for_selector(
+        &mut self,
+        self_: main::types::PageResource,
+        selector: String,
+        timeout_ms: u64,
+    ) -> wasmtime::Result<main::types::NodeResource> {
+        self.resources
+            .impl_wait_for_selector(self_, selector, timeout_ms)
+            .await
+    }
+
+    async fn wait_for_network_idle(
+        &mut self,
+        self_: main::types::PageResource,
+        idle_ms: u64,
+        timeout_ms: u64,
+    ) -> wasmtime::Result<()> {
+        self.resources
+            .impl_wait_for_network_idle(self_, idle_ms, timeout_ms)
+            .await
+    }
+
+    async fn go_back(&mut self, self_: main::types::PageResource) -> wasmtime::Result<()> {
+        self.resources.impl_go_back(self_).await
+    }
+
+    async fn go_forward(&mut self, self_: main::types::PageResource) -> wasmtime::Result<()> {
+        self.resources.impl_go_forward(self_).await
+    }
+
+    async fn scroll_page(
+        &mut self,
+        self_: main::types::PageResource,
+        x: f64,
+        y: f64,
+    ) -> wasmtime::Result<()> {
+        self.resources.impl_scroll_page(self_, x, y).await
+    }
+
+    async fn set_viewport(
+        &mut self,
+        self_: main::types::PageResource,
+        width: u32,
+        height: u32,
+    ) -> wasmtime::Result<()> {
+        self.resources.impl_set_viewport(self_, width, height).await
+    }
+
+    async fn print_pdf(&mut self, self_: main::types::PageResource) -> wasmtime::Result<Vec<u8>> {
+        self.resources.impl_print_pdf(self_).await
+    }
+
+    async fn get_cookies(
+        &mut self,
+        self_: main::types::PageResource,
+    ) -> wasmtime::Result<Vec<main::types::Cookie>> {
+        self.resources.impl_get_cookies(self_).await
+    }
+
+    async fn set_cookie(
+        &mut self,
+        self_: main::types::PageResource,
+        cookie: main::types::Cookie,
+    ) -> wasmtime::Result<()> {
+        self.resources.impl_set_cookie(self_, cookie).await
+    }
+
     async fn drop_node(&mut self, self_: main::types::NodeResource) -> wasmtime::Result<()> {
         self.resources.impl_drop_node(self_)
     }
@@ -217,6 +327,33 @@ impl main::types::Host for State {
             .await
     }
 
+    async fn select_option(
+        &mut self,
+        self_: main::types::NodeResource,
+        option: String,
+    ) -> wasmtime::Result<()> {
+        self.resources.impl_select_option(self_, option).await
+    }
+
+    async fn upload_files(
+        &mut self,
+        self_: main::types::NodeResource,
+        paths: Vec<String>,
+    ) -> wasmtime::Result<()> {
+        self.resources.impl_upload_files(self_, paths).await
+    }
+
+    async fn click_and_download(
+        &mut self,
+        self_: main::types::NodeResource,
+        folder: String,
+        timeout_ms: u64,
+    ) -> wasmtime::Result<String> {
+        self.resources
+            .impl_click_and_download(self_, folder, timeout_ms)
+            .await
+    }
+
     async fn create_embedding_db(
         &mut self,
         embeddings: Vec<main::types::Embedding>,
@@ -1,8 +1,12 @@
-use crate::host::AnyNodeRef;
+use crate::host::{plugin_path, sandboxed_path, AnyNodeRef};
 use crate::resource::{Resource, ResourceStorage};
 
 use crate::plugins::main::types::NodeResource;
 
+use kalosm::language::{Node, Tab};
+use std::sync::Arc;
+use std::time::Duration;
+
 impl ResourceStorage {
     pub fn with_node<O>(
         &self,
@@ -17,7 +21,7 @@ impl ResourceStorage {
         let tab = self.get(tab).ok_or(anyhow::anyhow!(
             "Page not found; may have been already dropped"
         ))?;
-        f(headless_chrome::Element::new(&tab, node.node_id)?)
+        f(headless_chrome::Element::new(&tab.inner(), node.node_id)?)
     }
 
     pub(crate) async fn impl_get_element_text(
@@ -79,7 +83,7 @@ impl ResourceStorage {
         let node_id = {
             let page = Resource::from_index_borrowed(page_id);
             let tab = self.get(page).ok_or(anyhow::anyhow!("Page not found"))?;
-            let node = headless_chrome::Element::new(&tab, node_id)?;
+            let node = headless_chrome::Element::new(&tab.inner(), node_id)?;
             let child = node.find_element(&query)?;
             child.node_id
         };
@@ -90,6 +94,53 @@ impl ResourceStorage {
         })
     }
 
+    pub(crate) async fn impl_select_option(
+        &self,
+        self_: NodeResource,
+        option: String,
+    ) -> wasmtime::Result<()> {
+        self.with_node(self_, |node| Node::from(node).select_option(&option))
+    }
+
+    pub(crate) async fn impl_upload_files(
+        &self,
+        self_: NodeResource,
+        paths: Vec<String>,
+    ) -> wasmtime::Result<()> {
+        let paths = paths
+            .iter()
+            .map(|path| sandboxed_path(path))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.with_node(self_, |node| Node::from(node).upload_files(&paths))
+    }
+
+    pub(crate) async fn impl_click_and_download(
+        &self,
+        self_: NodeResource,
+        folder: String,
+        timeout_ms: u64,
+    ) -> wasmtime::Result<String> {
+        let folder = sandboxed_path(&folder)?;
+        let (node_id, page_id) = {
+            let index = self_.into();
+            let node = self.get(index).ok_or(anyhow::anyhow!("Node not found"))?;
+            (node.node_id, node.page_id)
+        };
+        let tab: Arc<Tab> = {
+            let page = Resource::from_index_borrowed(page_id);
+            let tab = self.get(page).ok_or(anyhow::anyhow!("Page not found"))?;
+            tab.clone()
+        };
+        let path = tokio::task::spawn_blocking(move || {
+            tab.download(folder, Duration::from_millis(timeout_ms), |tab| {
+                headless_chrome::Element::new(&tab.inner(), node_id)?.click()?;
+                Ok(())
+            })
+        })
+        .await??;
+        plugin_path(&path)
+    }
+
     pub(crate) fn impl_drop_node(&self, rep: NodeResource) -> wasmtime::Result<()> {
         let index = rep.into();
         self.drop_key(index);
@@ -1,12 +1,21 @@
 use crate::host::AnyNodeRef;
 use crate::plugins::main;
 
-use crate::plugins::main::types::{NodeResource, PageResource};
-use crate::resource::ResourceStorage;
+use crate::plugins::main::types::{Cookie, NodeResource, PageResource};
+use crate::resource::{Resource, ResourceStorage};
 
 use kalosm::language::Tab;
+use std::sync::Arc;
+use std::time::Duration;
 
 impl ResourceStorage {
+    /// Clone a page out of the storage so it can be used without holding the lock, for example on a blocking thread.
+    pub(crate) fn cloned_page(&self, page: PageResource) -> anyhow::Result<Arc<Tab>> {
+        let index: Resource<Arc<Tab>> = page.into();
+        let page = self.get(index).ok_or(anyhow::anyhow!("Page not found"))?;
+        Ok(page.clone())
+    }
+
     pub(crate) fn impl_create_page(
         &self,
         mode: main::types::BrowserMode,
@@ -16,7 +25,7 @@ impl ResourceStorage {
             url.parse()?,
             matches!(mode, main::types::BrowserMode::Headless),
         )?;
-        let page_id = self.insert(page.inner());
+        let page_id = self.insert(Arc::new(page));
         Ok(PageResource {
             id: page_id.index() as u64,
             owned: true,
@@ -64,6 +73,125 @@ impl ResourceStorage {
         page.html().map(|html| html.html())
     }
 
+    pub(crate) async fn impl_wait_for_selector(
+        &self,
+        self_: PageResource,
+        selector: String,
+        timeout_ms: u64,
+    ) -> wasmtime::Result<NodeResource> {
+        let page = self.cloned_page(self_)?;
+        let node_id = tokio::task::spawn_blocking(move || {
+            let node = page.wait_for(&selector, Duration::from_millis(timeout_ms))?;
+            match node.id() {
+                kalosm::language::NodeRef::Dynamic(node_id) => Ok(node_id),
+                kalosm::language::NodeRef::Static(_) => anyhow::bail!("Static node not supported"),
+            }
+        })
+        .await??;
+        let node = AnyNodeRef {
+            node_id,
+            page_id: self_.id as usize,
+        };
+        let node_id = self.insert(node);
+        Ok(NodeResource {
+            id: node_id.index() as u64,
+            owned: true,
+        })
+    }
+
+    pub(crate) async fn impl_wait_for_network_idle(
+        &self,
+        self_: PageResource,
+        idle_ms: u64,
+        timeout_ms: u64,
+    ) -> wasmtime::Result<()> {
+        let page = self.cloned_page(self_)?;
+        tokio::task::spawn_blocking(move || {
+            page.wait_for_network_idle(
+                Duration::from_millis(idle_ms),
+                Duration::from_millis(timeout_ms),
+            )
+        })
+        .await?
+    }
+
+    pub(crate) async fn impl_go_back(&self, self_: PageResource) -> wasmtime::Result<()> {
+        let page = self.cloned_page(self_)?;
+        tokio::task::spawn_blocking(move || page.back()).await?
+    }
+
+    pub(crate) async fn impl_go_forward(&self, self_: PageResource) -> wasmtime::Result<()> {
+        let page = self.cloned_page(self_)?;
+        tokio::task::spawn_blocking(move || page.forward()).await?
+    }
+
+    pub(crate) async fn impl_scroll_page(
+        &self,
+        self_: PageResource,
+        x: f64,
+        y: f64,
+    ) -> wasmtime::Result<()> {
+        let index = self_.into();
+        let page = self.get(index).ok_or(anyhow::anyhow!("Page not found"))?;
+        page.scroll_by(x, y)
+    }
+
+    pub(crate) async fn impl_set_viewport(
+        &self,
+        self_: PageResource,
+        width: u32,
+        height: u32,
+    ) -> wasmtime::Result<()> {
+        let index = self_.into();
+        let page = self.get(index).ok_or(anyhow::anyhow!("Page not found"))?;
+        page.set_viewport(width, height)
+    }
+
+    pub(crate) async fn impl_print_pdf(&self, self_: PageResource) -> wasmtime::Result<Vec<u8>> {
+        let index = self_.into();
+        let page = self.get(index).ok_or(anyhow::anyhow!("Page not found"))?;
+        page.pdf()
+    }
+
+    pub(crate) async fn impl_get_cookies(
+        &self,
+        self_: PageResource,
+    ) -> wasmtime::Result<Vec<Cookie>> {
+        let index = self_.into();
+        let page = self.get(index).ok_or(anyhow::anyhow!("Page not found"))?;
+        Ok(page
+            .cookies()?
+            .into_iter()
+            .map(|cookie| Cookie {
+                name: cookie.name,
+                value: cookie.value,
+                domain: cookie.domain,
+                path: cookie.path,
+                expires: cookie.expires,
+                http_only: cookie.http_only,
+                secure: cookie.secure,
+            })
+            .collect())
+    }
+
+    pub(crate) async fn impl_set_cookie(
+        &self,
+        self_: PageResource,
+        cookie: Cookie,
+    ) -> wasmtime::Result<()> {
+        let index = self_.into();
+        let page = self.get(index).ok_or(anyhow::anyhow!("Page not found"))?;
+        page.set_cookie(kalosm::language::Cookie {
+            name: cookie.name,
+            value: cookie.value,
+            domain: cookie.domain,
+            path: cookie.path,
+            expires: cookie.expires,
+            http_only: cookie.http_only,
+            secure: cookie.secure,
+        })
+    }
+
     pub(crate) fn impl_drop_page(&self, rep: PageResource) -> wasmtime::Result<()> {
         let index = rep.into();
         self.drop_key(index);
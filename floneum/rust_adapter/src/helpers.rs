@@ -1,6 +1,7 @@
 pub use crate::exports::plugins::main::definitions::Guest;
 pub use crate::plugins::main::imports::log_to_user;
 pub use crate::plugins::main::types::*;
+use std::time::Duration;
 
 pub struct Page {
     page: PageResource,
@@ -26,6 +27,47 @@ impl Page {
     pub fn screenshot(&self) -> Vec<u8> {
         screenshot_browser(self.page)
     }
+
+    pub fn wait_for_selector(&self, selector: &str, timeout: Duration) -> Node {
+        let resource = wait_for_selector(self.page, selector, timeout.as_millis() as u64);
+        Node { node: resource }
+    }
+
+    pub fn wait_for_network_idle(&self, idle: Duration, timeout: Duration) {
+        wait_for_network_idle(
+            self.page,
+            idle.as_millis() as u64,
+            timeout.as_millis() as u64,
+        );
+    }
+
+    pub fn back(&self) {
+        go_back(self.page);
+    }
+
+    pub fn forward(&self) {
+        go_forward(self.page);
+    }
+
+    pub fn scroll_by(&self, x: f64, y: f64) {
+        scroll_page(self.page, x, y);
+    }
+
+    pub fn set_viewport(&self, width: u32, height: u32) {
+        set_viewport(self.page, width, height);
+    }
+
+    pub fn pdf(&self) -> Vec<u8> {
+        print_pdf(self.page)
+    }
+
+    pub fn cookies(&self) -> Vec<Cookie> {
+        get_cookies(self.page)
+    }
+
+    pub fn set_cookie(&self, cookie: &Cookie) {
+        set_cookie(self.page, cookie);
+    }
 }
 
 impl Drop for Page {
@@ -73,6 +115,18 @@ impl Node {
         let resource = find_child_of_element(self.node, selector);
         Node { node: resource }
     }
+
+    pub fn select_option(&self, option: &str) {
+        select_option(self.node, option);
+    }
+
+    pub fn upload_files(&self, paths: &[String]) {
+        upload_files(self.node, paths);
+    }
+
+    pub fn click_and_download(&self, folder: &str, timeout: Duration) -> String {
+        click_and_download(self.node, folder, timeout.as_millis() as u64)
+    }
 }
 
 impl Drop for Node {
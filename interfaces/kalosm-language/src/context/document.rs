@@ -1,3 +1,6 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
 use url::Url;
 pub use whatlang::Lang;
 
@@ -9,6 +12,10 @@ pub struct Document {
     summary: Option<String>,
     created_at: Option<chrono::DateTime<chrono::Utc>>,
     updated_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    metadata: DocumentMetadata,
+    #[serde(default)]
+    spans: Vec<DocumentSpan>,
 }
 
 impl Document {
@@ -30,6 +37,8 @@ impl Document {
             summary: None,
             created_at: None,
             updated_at: None,
+            metadata: DocumentMetadata::default(),
+            spans: Vec::new(),
         }
     }
 
@@ -48,6 +57,21 @@ impl Document {
         self.updated_at = Some(updated_at);
     }
 
+    /// Get the summary of the document.
+    pub fn summary(&self) -> Option<&str> {
+        self.summary.as_deref()
+    }
+
+    /// Get the created at time of the document.
+    pub fn created_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.created_at
+    }
+
+    /// Get the updated at time of the document.
+    pub fn updated_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.updated_at
+    }
+
     /// Get the title of the document.
     pub fn title(&self) -> &str {
         &self.title
@@ -57,6 +81,237 @@ impl Document {
     pub fn body(&self) -> &str {
         &self.body
     }
+
+    /// Get the metadata of the document.
+    pub fn metadata(&self) -> &DocumentMetadata {
+        &self.metadata
+    }
+
+    /// Get a mutable reference to the metadata of the document.
+    pub fn metadata_mut(&mut self) -> &mut DocumentMetadata {
+        &mut self.metadata
+    }
+
+    /// Set the metadata of the document.
+    pub fn set_metadata(&mut self, metadata: DocumentMetadata) {
+        self.metadata = metadata;
+    }
+
+    /// Set the metadata of the document.
+    pub fn with_metadata(mut self, metadata: DocumentMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Get the spans of the body with a known provenance, like the page of a pdf they are on.
+    pub fn spans(&self) -> &[DocumentSpan] {
+        &self.spans
+    }
+
+    /// Record the provenance of a span of the body.
+    pub fn add_span(&mut self, byte_range: Range<usize>, provenance: Provenance) {
+        self.spans.push(DocumentSpan {
+            byte_range,
+            provenance,
+        });
+    }
+
+    /// Get the provenance of every span that overlaps the byte range of the body. Duplicate provenances are only returned once.
+    pub fn provenance(&self, byte_range: Range<usize>) -> Vec<Provenance> {
+        let mut provenance: Vec<Provenance> = Vec::new();
+        for span in &self.spans {
+            let overlaps =
+                span.byte_range.start < byte_range.end && byte_range.start < span.byte_range.end;
+            if overlaps && !provenance.contains(&span.provenance) {
+                provenance.push(span.provenance.clone());
+            }
+        }
+        provenance
+    }
+}
+
+/// Metadata about where a [`Document`] came from.
+///
+/// # Example
+/// ```rust
+/// use kalosm_language::prelude::*;
+///
+/// let document = Document::from_parts("Kalosm", "Kalosm is a library for local AI").with_metadata(
+///     DocumentMetadata::new()
+///         .with_source("https://floneum.com/kalosm")
+///         .with_author("Evan Almloff")
+///         .with_mime_type("text/html")
+///         .with("project", "floneum"),
+/// );
+/// assert_eq!(document.metadata().get("project"), Some("floneum"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DocumentMetadata {
+    source: Option<String>,
+    #[serde(default)]
+    authors: Vec<String>,
+    mime_type: Option<String>,
+    language: Option<String>,
+    #[serde(default)]
+    extra: BTreeMap<String, String>,
+}
+
+impl DocumentMetadata {
+    /// Create new empty metadata.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the URI of the source of the document, like the url of a web page or a `file://` url.
+    pub fn with_source(mut self, source: impl ToString) -> Self {
+        self.source = Some(source.to_string());
+        self
+    }
+
+    /// Add an author of the document.
+    pub fn with_author(mut self, author: impl ToString) -> Self {
+        self.authors.push(author.to_string());
+        self
+    }
+
+    /// Set the MIME type of the source of the document.
+    pub fn with_mime_type(mut self, mime_type: impl ToString) -> Self {
+        self.mime_type = Some(mime_type.to_string());
+        self
+    }
+
+    /// Set the language of the document, like `en` or `eng`.
+    pub fn with_language(mut self, language: impl ToString) -> Self {
+        self.language = Some(language.to_string());
+        self
+    }
+
+    /// Set an arbitrary key-value pair.
+    pub fn with(mut self, key: impl ToString, value: impl ToString) -> Self {
+        self.extra.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Set the URI of the source of the document.
+    pub fn set_source(&mut self, source: impl ToString) {
+        self.source = Some(source.to_string());
+    }
+
+    /// Add an author of the document.
+    pub fn add_author(&mut self, author: impl ToString) {
+        self.authors.push(author.to_string());
+    }
+
+    /// Set the MIME type of the source of the document.
+    pub fn set_mime_type(&mut self, mime_type: impl ToString) {
+        self.mime_type = Some(mime_type.to_string());
+    }
+
+    /// Set the language of the document.
+    pub fn set_language(&mut self, language: impl ToString) {
+        self.language = Some(language.to_string());
+    }
+
+    /// Set an arbitrary key-value pair.
+    pub fn insert(&mut self, key: impl ToString, value: impl ToString) {
+        self.extra.insert(key.to_string(), value.to_string());
+    }
+
+    /// Get the URI of the source of the document.
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Get the authors of the document.
+    pub fn authors(&self) -> &[String] {
+        &self.authors
+    }
+
+    /// Get the MIME type of the source of the document.
+    pub fn mime_type(&self) -> Option<&str> {
+        self.mime_type.as_deref()
+    }
+
+    /// Get the language of the document.
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
+    /// Get the value of an arbitrary key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.extra.get(key).map(|value| value.as_str())
+    }
+
+    /// Get all of the arbitrary key-value pairs.
+    pub fn extra(&self) -> &BTreeMap<String, String> {
+        &self.extra
+    }
+}
+
+/// A span of the body of a [`Document`] with a known [`Provenance`].
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DocumentSpan {
+    /// The byte range of the span in the body of the document.
+    pub byte_range: Range<usize>,
+    /// Where the span came from in the source of the document.
+    pub provenance: Provenance,
+}
+
+/// Where some text came from in the source of a [`Document`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Provenance {
+    /// The page of the source the text is on, starting at 1. This is set for pdf documents.
+    pub page: Option<usize>,
+    /// The headings the text is under, from the top level heading to the closest heading. This is set for markdown documents, and
+    /// holds the enclosing items (like `impl Parser > fn parse`) for chunks of source code.
+    #[serde(default)]
+    pub heading_path: Vec<String>,
+    /// The row of the table the text came from, starting at 1 for the first row after the header. This is set for csv and spreadsheet documents.
+    #[serde(default)]
+    pub row: Option<usize>,
+}
+
+impl Provenance {
+    /// Create the provenance of text on a page, starting at 1.
+    pub fn page(page: usize) -> Self {
+        Self {
+            page: Some(page),
+            ..Default::default()
+        }
+    }
+
+    /// Create the provenance of text under a list of headings.
+    pub fn heading_path(heading_path: impl IntoIterator<Item = impl ToString>) -> Self {
+        Self {
+            heading_path: heading_path
+                .into_iter()
+                .map(|heading| heading.to_string())
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the row of the table the text came from, starting at 1.
+    pub fn with_row(mut self, row: usize) -> Self {
+        self.row = Some(row);
+        self
+    }
+}
+
+impl std::fmt::Display for Provenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(page) = self.page {
+            parts.push(format!("page {page}"));
+        }
+        if !self.heading_path.is_empty() {
+            parts.push(self.heading_path.join(" > "));
+        }
+        if let Some(row) = self.row {
+            parts.push(format!("row {row}"));
+        }
+        write!(f, "{}", parts.join(", "))
+    }
 }
 
 impl From<String> for Document {
@@ -121,7 +376,11 @@ impl IntoDocument for Document {
 #[async_trait::async_trait]
 impl IntoDocument for Url {
     async fn into_document(self) -> anyhow::Result<Document> {
-        super::page::get_article(self).await
+        let mut document = super::page::get_article(self.clone()).await?;
+        let metadata = document.metadata_mut();
+        metadata.set_source(self);
+        metadata.set_mime_type("text/html");
+        Ok(document)
     }
 }
 
@@ -0,0 +1,730 @@
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use heed::types::{SerdeBincode, Str};
+use heed::{Database, EnvOpenOptions};
+use once_cell::sync::OnceCell;
+use reqwest::header::{
+    HeaderMap, CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+    RETRY_AFTER,
+};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::time::{Duration, Instant};
+use url::Url;
+
+/// The user agent the [`Fetcher`] sends by default.
+pub const DEFAULT_USER_AGENT: &str = concat!("kalosm/", env!("CARGO_PKG_VERSION"));
+
+static GLOBAL_FETCHER: OnceCell<Fetcher> = OnceCell::new();
+
+/// A response from a [`Fetcher`].
+#[derive(Debug, Clone)]
+pub struct FetchResponse {
+    url: Url,
+    status: u16,
+    content_type: Option<String>,
+    body: Vec<u8>,
+    from_cache: bool,
+}
+
+impl FetchResponse {
+    /// Get the URL of the response after any redirects.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Get the HTTP status code of the response.
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// Get the content type of the response.
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// Check if the body of the response was read from the [`HttpCache`].
+    pub fn is_from_cache(&self) -> bool {
+        self.from_cache
+    }
+
+    /// Get the body of the response.
+    pub fn bytes(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Get the body of the response as text. Invalid UTF-8 is replaced with the replacement character.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// Take the body of the response.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.body
+    }
+}
+
+/// A response stored in the [`HttpCache`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedResponse {
+    url: String,
+    status: u16,
+    content_type: Option<String>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    max_age: Option<u64>,
+    stored_at: DateTime<Utc>,
+    body: Vec<u8>,
+}
+
+impl CachedResponse {
+    /// Check if the response can be used without asking the server if it changed.
+    fn is_fresh(&self) -> bool {
+        match self.max_age {
+            Some(max_age) => (Utc::now() - self.stored_at).num_seconds() < max_age as i64,
+            None => false,
+        }
+    }
+
+    fn to_response(&self) -> anyhow::Result<FetchResponse> {
+        Ok(FetchResponse {
+            url: Url::parse(&self.url)?,
+            status: self.status,
+            content_type: self.content_type.clone(),
+            body: self.body.clone(),
+            from_cache: true,
+        })
+    }
+}
+
+/// The caching directives of a response.
+struct CacheControl {
+    no_store: bool,
+    max_age: Option<u64>,
+}
+
+impl CacheControl {
+    fn new(headers: &HeaderMap) -> Self {
+        let mut cache_control = Self {
+            no_store: false,
+            max_age: None,
+        };
+        let directives = headers
+            .get_all(CACHE_CONTROL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|directive| directive.trim().to_lowercase());
+        for directive in directives {
+            if directive == "no-store" {
+                cache_control.no_store = true;
+            } else if directive == "no-cache" {
+                cache_control.max_age = Some(0);
+            } else if let Some(max_age) = directive.strip_prefix("max-age=") {
+                if cache_control.max_age.is_none() {
+                    cache_control.max_age = max_age.trim_matches('"').parse().ok();
+                }
+            }
+        }
+        cache_control
+    }
+}
+
+fn header(headers: &HeaderMap, name: reqwest::header::HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+/// An on-disk cache of HTTP responses for a [`Fetcher`].
+///
+/// Responses are revalidated with a conditional request (`If-None-Match` or `If-Modified-Since`) unless the server allowed
+/// the response to be reused with a `Cache-Control: max-age` header. Responses with `Cache-Control: no-store` are never stored.
+#[derive(Clone)]
+pub struct HttpCache {
+    env: heed::Env,
+    responses: Database<Str, SerdeBincode<CachedResponse>>,
+}
+
+impl HttpCache {
+    /// Create a new cache at the given path. If a cache already exists at the path, it will be reused.
+    pub fn new_at(path: impl AsRef<std::path::Path>) -> anyhow::Result<Self> {
+        const ONE_GIB: usize = 1024 * 1024 * 1024;
+
+        std::fs::create_dir_all(&path)?;
+
+        let env = unsafe {
+            EnvOpenOptions::new()
+                .map_size(ONE_GIB)
+                .max_dbs(1)
+                .open(path)
+        }?;
+
+        let mut wtxn = env.write_txn()?;
+        let responses = env.create_database(&mut wtxn, Some("http-responses"))?;
+        wtxn.commit()?;
+
+        Ok(Self { env, responses })
+    }
+
+    fn get(&self, url: &Url) -> anyhow::Result<Option<CachedResponse>> {
+        let rtxn = self.env.read_txn()?;
+        Ok(self.responses.get(&rtxn, url.as_str())?)
+    }
+
+    fn put(&self, url: &Url, response: &CachedResponse) -> anyhow::Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+        self.responses.put(&mut wtxn, url.as_str(), response)?;
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    /// Remove the cached response for a URL.
+    pub fn remove(&self, url: &Url) -> anyhow::Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+        self.responses.delete(&mut wtxn, url.as_str())?;
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    /// Remove every cached response.
+    pub fn clear(&self) -> anyhow::Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+        self.responses.clear(&mut wtxn)?;
+        wtxn.commit()?;
+        Ok(())
+    }
+}
+
+/// A builder for a [`Fetcher`].
+pub struct FetcherBuilder {
+    user_agent: String,
+    cache: Option<HttpCache>,
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    host_delay: Duration,
+    timeout: Option<Duration>,
+}
+
+impl Default for FetcherBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FetcherBuilder {
+    /// Create a new builder with the default settings.
+    pub fn new() -> Self {
+        Self {
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            cache: None,
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            host_delay: Duration::ZERO,
+            timeout: Some(Duration::from_secs(30)),
+        }
+    }
+
+    /// Set the user agent sent with every request. (default: [`DEFAULT_USER_AGENT`])
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Store responses in an [`HttpCache`] and revalidate them with conditional requests. (default: no cache)
+    pub fn with_cache(mut self, cache: HttpCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Set the number of times a request is retried after a network error, a `429 Too Many Requests` response, or a server error. (default: 3)
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the time to wait before the first retry. The time doubles after every retry up to the maximum backoff. (default: 500ms)
+    pub fn with_initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Set the longest time to wait before a retry. (default: 30s)
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Set the minimum time between the start of two requests to the same host. (default: no limit)
+    pub fn with_host_delay(mut self, host_delay: Duration) -> Self {
+        self.host_delay = host_delay;
+        self
+    }
+
+    /// Set the timeout for each request, or `None` to wait forever. (default: 30s)
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Build the [`Fetcher`].
+    pub fn build(self) -> anyhow::Result<Fetcher> {
+        let mut client = reqwest::Client::builder().user_agent(self.user_agent);
+        if let Some(timeout) = self.timeout {
+            client = client.timeout(timeout);
+        }
+        Ok(Fetcher {
+            inner: Arc::new(FetcherInner {
+                client: client.build()?,
+                cache: self.cache,
+                max_retries: self.max_retries,
+                initial_backoff: self.initial_backoff,
+                max_backoff: self.max_backoff,
+                host_delay: self.host_delay,
+                hosts: DashMap::new(),
+            }),
+        })
+    }
+}
+
+struct FetcherInner {
+    client: reqwest::Client,
+    cache: Option<HttpCache>,
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    host_delay: Duration,
+    // The time the next request to each host may start
+    hosts: DashMap<String, Arc<tokio::sync::Mutex<Instant>>>,
+}
+
+/// The HTTP client kalosm uses to fetch web pages, feeds, robots.txt files and sitemaps.
+///
+/// The fetcher sends a configurable user agent, retries failed requests with exponential backoff, limits the rate of requests to
+/// each host, and can store responses in an on-disk [`HttpCache`].
+///
+/// [`StaticPage`](crate::context::StaticPage), [`RssFeed`](crate::context::RssFeed) and the crawler use the
+/// [global fetcher](Fetcher::global). It can be configured with [`Fetcher::set_global`] before the first request is sent.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use kalosm_language::prelude::*;
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let fetcher = Fetcher::builder()
+///         .with_user_agent("my-crawler/1.0")
+///         .with_cache(HttpCache::new_at("./http-cache").unwrap())
+///         .with_host_delay(Duration::from_secs(1))
+///         .build()
+///         .unwrap();
+///     fetcher.set_global().unwrap();
+///
+///     let page = Page::new(Url::parse("https://floneum.com").unwrap(), BrowserMode::Static).unwrap();
+///     println!("{}", page.article().await.unwrap().body());
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Fetcher {
+    inner: Arc<FetcherInner>,
+}
+
+impl Fetcher {
+    /// Create a [`FetcherBuilder`] to configure a fetcher.
+    pub fn builder() -> FetcherBuilder {
+        FetcherBuilder::new()
+    }
+
+    /// Get the fetcher that is shared by every page, feed and crawler.
+    pub fn global() -> &'static Fetcher {
+        GLOBAL_FETCHER.get_or_init(|| {
+            FetcherBuilder::new()
+                .build()
+                .expect("Failed to create the HTTP client")
+        })
+    }
+
+    /// Make this fetcher the [global fetcher](Fetcher::global). This fails and returns the fetcher if the global fetcher was already used or set.
+    pub fn set_global(self) -> Result<(), Self> {
+        GLOBAL_FETCHER.set(self)
+    }
+
+    /// Get the cache the fetcher stores responses in.
+    pub fn cache(&self) -> Option<&HttpCache> {
+        self.inner.cache.as_ref()
+    }
+
+    /// Fetch a URL. Responses that do not have a success status are returned as errors.
+    pub async fn get(&self, url: Url) -> anyhow::Result<FetchResponse> {
+        let inner = &self.inner;
+        // The cache only saves requests, so errors reading or writing it never fail the fetch
+        let cached = match &inner.cache {
+            Some(cache) => cache.get(&url).unwrap_or_else(|err| {
+                tracing::warn!("Failed to read the cached response for {url}: {err}");
+                None
+            }),
+            None => None,
+        };
+        if let Some(cached) = &cached {
+            if cached.is_fresh() {
+                return cached.to_response();
+            }
+        }
+
+        let mut attempt = 0;
+        loop {
+            self.wait_for_host(&url).await;
+
+            let mut request = inner.client.get(url.clone());
+            if let Some(cached) = &cached {
+                if let Some(etag) = &cached.etag {
+                    request = request.header(IF_NONE_MATCH, etag);
+                }
+                if let Some(last_modified) = &cached.last_modified {
+                    request = request.header(IF_MODIFIED_SINCE, last_modified);
+                }
+            }
+
+            let retry_after = match request.send().await {
+                Ok(response) if response.status() == StatusCode::NOT_MODIFIED => {
+                    let Some(mut cached) = cached else {
+                        return Err(anyhow::anyhow!(
+                            "Request to {url} returned 304 Not Modified without a cached response"
+                        ));
+                    };
+                    // The server confirmed the cached response is still valid
+                    cached.stored_at = Utc::now();
+                    cached.max_age = CacheControl::new(response.headers()).max_age;
+                    if let Some(cache) = &inner.cache {
+                        if let Err(err) = cache.put(&url, &cached) {
+                            tracing::warn!("Failed to update the cached response for {url}: {err}");
+                        }
+                    }
+                    return cached.to_response();
+                }
+                Ok(response) if response.status().is_success() => {
+                    return self.read_response(&url, response).await;
+                }
+                Ok(response) if attempt < inner.max_retries && is_retryable(response.status()) => {
+                    retry_after(&response)
+                }
+                Ok(response) => {
+                    return Err(anyhow::anyhow!(
+                        "Request to {url} failed with status {}",
+                        response.status()
+                    ));
+                }
+                Err(err) if attempt < inner.max_retries => {
+                    tracing::warn!("Request to {url} failed, retrying: {err}");
+                    None
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            self.back_off(attempt, retry_after).await;
+            attempt += 1;
+        }
+    }
+
+    /// Send a request that is built with the HTTP client of the fetcher, like a call to a search API. The request is rate limited and
+    /// retried like [`Fetcher::get`], but the response is never cached. Responses that do not have a success status are returned as errors.
+    pub(crate) async fn send(
+        &self,
+        request: impl Fn(&reqwest::Client) -> reqwest::RequestBuilder,
+    ) -> anyhow::Result<FetchResponse> {
+        let inner = &self.inner;
+        let mut attempt = 0;
+        loop {
+            let request = request(&inner.client).build()?;
+            let url = request.url().clone();
+            self.wait_for_host(&url).await;
+
+            let retry_after = match inner.client.execute(request).await {
+                Ok(response) if response.status().is_success() => {
+                    let url = response.url().clone();
+                    let status = response.status().as_u16();
+                    let content_type = header(response.headers(), CONTENT_TYPE);
+                    let body = response.bytes().await?.to_vec();
+                    return Ok(FetchResponse {
+                        url,
+                        status,
+                        content_type,
+                        body,
+                        from_cache: false,
+                    });
+                }
+                Ok(response) if attempt < inner.max_retries && is_retryable(response.status()) => {
+                    retry_after(&response)
+                }
+                Ok(response) => {
+                    return Err(anyhow::anyhow!(
+                        "Request to {url} failed with status {}",
+                        response.status()
+                    ));
+                }
+                Err(err) if attempt < inner.max_retries => {
+                    tracing::warn!("Request to {url} failed, retrying: {err}");
+                    None
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            self.back_off(attempt, retry_after).await;
+            attempt += 1;
+        }
+    }
+
+    /// Wait before retrying a request. The server can ask for a delay with the `Retry-After` header, otherwise the delay doubles after every attempt.
+    async fn back_off(&self, attempt: u32, retry_after: Option<Duration>) {
+        let inner = &self.inner;
+        let backoff = inner.initial_backoff.saturating_mul(1 << attempt.min(16));
+        let delay = retry_after.unwrap_or(backoff).min(inner.max_backoff);
+        tokio::time::sleep(delay).await;
+    }
+
+    /// Read the body of a successful response and store it in the cache.
+    async fn read_response(
+        &self,
+        url: &Url,
+        response: reqwest::Response,
+    ) -> anyhow::Result<FetchResponse> {
+        let headers = response.headers().clone();
+        let final_url = response.url().clone();
+        let status = response.status().as_u16();
+        let body = response.bytes().await?.to_vec();
+        let content_type = header(&headers, CONTENT_TYPE);
+
+        if let Some(cache) = &self.inner.cache {
+            let cache_control = CacheControl::new(&headers);
+            if !cache_control.no_store {
+                let cached = CachedResponse {
+                    url: final_url.to_string(),
+                    status,
+                    content_type: content_type.clone(),
+                    etag: header(&headers, ETAG),
+                    last_modified: header(&headers, LAST_MODIFIED),
+                    max_age: cache_control.max_age,
+                    stored_at: Utc::now(),
+                    body: body.clone(),
+                };
+                if let Err(err) = cache.put(url, &cached) {
+                    tracing::warn!("Failed to cache the response for {url}: {err}");
+                }
+            }
+        }
+
+        Ok(FetchResponse {
+            url: final_url,
+            status,
+            content_type,
+            body,
+            from_cache: false,
+        })
+    }
+
+    /// Wait until the next request to the host of a URL may start.
+    async fn wait_for_host(&self, url: &Url) {
+        let host_delay = self.inner.host_delay;
+        if host_delay.is_zero() {
+            return;
+        }
+        let host = url.host_str().unwrap_or_default().to_string();
+        let next = self
+            .inner
+            .hosts
+            .entry(host)
+            .or_insert_with(|| Arc::new(tokio::sync::Mutex::new(Instant::now())))
+            .clone();
+        let mut next = next.lock().await;
+        tokio::time::sleep_until(*next).await;
+        *next = Instant::now() + host_delay;
+    }
+}
+
+/// Read the number of seconds to wait before retrying from the `Retry-After` header of a response.
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .map(Duration::from_secs)
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+#[cfg(test)]
+pub(crate) async fn test_server(router: axum::Router) -> Url {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    Url::parse(&format!("http://{address}")).unwrap()
+}
+
+#[tokio::test]
+async fn conditional_requests_reuse_the_cache() {
+    use axum::http::{header, HeaderMap, StatusCode};
+    use axum::response::IntoResponse;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let requests = Arc::new(AtomicUsize::new(0));
+    let router = axum::Router::new().route(
+        "/page",
+        axum::routing::get({
+            let requests = requests.clone();
+            move |headers: HeaderMap| async move {
+                requests.fetch_add(1, Ordering::SeqCst);
+                if headers
+                    .get(header::IF_NONE_MATCH)
+                    .is_some_and(|etag| etag == "\"v1\"")
+                {
+                    return StatusCode::NOT_MODIFIED.into_response();
+                }
+                ([(header::ETAG, "\"v1\"")], "hello").into_response()
+            }
+        }),
+    );
+    let url = test_server(router).await.join("/page").unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let fetcher = Fetcher::builder()
+        .with_cache(HttpCache::new_at(dir.path()).unwrap())
+        .build()
+        .unwrap();
+
+    let first = fetcher.get(url.clone()).await.unwrap();
+    assert_eq!(first.text(), "hello");
+    assert!(!first.is_from_cache());
+
+    let second = fetcher.get(url.clone()).await.unwrap();
+    assert_eq!(second.text(), "hello");
+    assert!(second.is_from_cache());
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn fresh_responses_are_not_requested_again() {
+    use axum::http::header;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let requests = Arc::new(AtomicUsize::new(0));
+    let router = axum::Router::new().route(
+        "/page",
+        axum::routing::get({
+            let requests = requests.clone();
+            move || async move {
+                requests.fetch_add(1, Ordering::SeqCst);
+                ([(header::CACHE_CONTROL, "public, max-age=3600")], "cached")
+            }
+        }),
+    );
+    let url = test_server(router).await.join("/page").unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let fetcher = Fetcher::builder()
+        .with_cache(HttpCache::new_at(dir.path()).unwrap())
+        .build()
+        .unwrap();
+    for _ in 0..3 {
+        assert_eq!(fetcher.get(url.clone()).await.unwrap().text(), "cached");
+    }
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn cache_errors_do_not_fail_requests() {
+    let router = axum::Router::new().route("/page", axum::routing::get(|| async { "uncached" }));
+    let mut url = test_server(router).await.join("/page").unwrap();
+    // The cache can't store keys longer than 511 bytes
+    url.set_query(Some(&"a".repeat(1024)));
+
+    let dir = tempfile::tempdir().unwrap();
+    let fetcher = Fetcher::builder()
+        .with_cache(HttpCache::new_at(dir.path()).unwrap())
+        .build()
+        .unwrap();
+    for _ in 0..2 {
+        let response = fetcher.get(url.clone()).await.unwrap();
+        assert_eq!(response.text(), "uncached");
+        assert!(!response.is_from_cache());
+    }
+}
+
+#[tokio::test]
+async fn failed_requests_are_retried_with_the_user_agent() {
+    use axum::http::{header, HeaderMap, StatusCode};
+    use axum::response::IntoResponse;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let requests = Arc::new(AtomicUsize::new(0));
+    let router = axum::Router::new()
+        .route(
+            "/flaky",
+            axum::routing::get({
+                let requests = requests.clone();
+                move || async move {
+                    if requests.fetch_add(1, Ordering::SeqCst) < 2 {
+                        return StatusCode::SERVICE_UNAVAILABLE.into_response();
+                    }
+                    "finally".into_response()
+                }
+            }),
+        )
+        .route(
+            "/user-agent",
+            axum::routing::get(|headers: HeaderMap| async move {
+                headers
+                    .get(header::USER_AGENT)
+                    .and_then(|value| value.to_str().ok())
+                    .unwrap_or_default()
+                    .to_string()
+            }),
+        )
+        .route(
+            "/missing",
+            axum::routing::get(|| async { StatusCode::NOT_FOUND }),
+        );
+    let server = test_server(router).await;
+
+    let fetcher = Fetcher::builder()
+        .with_user_agent("test-agent/1.0")
+        .with_initial_backoff(Duration::from_millis(10))
+        .build()
+        .unwrap();
+
+    let response = fetcher.get(server.join("/flaky").unwrap()).await.unwrap();
+    assert_eq!(response.text(), "finally");
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+    let response = fetcher
+        .get(server.join("/user-agent").unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.text(), "test-agent/1.0");
+
+    assert!(fetcher.get(server.join("/missing").unwrap()).await.is_err());
+}
+
+#[tokio::test]
+async fn requests_to_a_host_are_rate_limited() {
+    let router = axum::Router::new().route("/", axum::routing::get(|| async { "ok" }));
+    let url = test_server(router).await;
+
+    let fetcher = Fetcher::builder()
+        .with_host_delay(Duration::from_millis(200))
+        .build()
+        .unwrap();
+    let start = Instant::now();
+    for _ in 0..3 {
+        fetcher.get(url.clone()).await.unwrap();
+    }
+    assert!(start.elapsed() >= Duration::from_millis(400));
+}
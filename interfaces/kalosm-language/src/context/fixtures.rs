@@ -0,0 +1,262 @@
+//! Fixture websites served from an in-process HTTP server, so the page, crawler and feed code can be tested without the live web.
+
+use axum::http::{header, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use url::Url;
+
+use super::fetch::test_server;
+
+/// A response the fixture server sends for a path.
+#[derive(Debug, Clone)]
+enum Fixture {
+    /// A file with a content type.
+    File {
+        content_type: &'static str,
+        body: String,
+    },
+    /// A permanent redirect to another path.
+    Redirect(String),
+    /// `429 Too Many Requests` for the first requests, then another fixture.
+    RateLimited { failures: usize, then: Box<Fixture> },
+    /// An empty response with a status code.
+    Status(StatusCode),
+}
+
+/// The origins a served site can be reached at. They replace the `{origin}` and `{alias}` placeholders in the fixture files.
+#[derive(Debug)]
+struct Origins {
+    origin: String,
+    alias: String,
+}
+
+/// A website made of fixture files. Paths without a fixture respond with `404 Not Found`.
+///
+/// The bodies of the fixtures may contain `{origin}`, which is replaced with the origin of the server (`http://127.0.0.1:port`),
+/// and `{alias}`, which is replaced with the same server under another host name (`http://localhost:port`). Links to the alias
+/// can be used to test link filters without leaving the machine.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FixtureSite {
+    fixtures: HashMap<String, Fixture>,
+}
+
+impl FixtureSite {
+    /// Create a new site without any pages.
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// A small blog with the things real websites throw at a crawler:
+    /// - `/robots.txt` disallows `/private/`, sets a crawl delay of 0 and lists `/sitemap.xml`
+    /// - `/` links to every post, a disallowed page, a missing page, a page on the alias host and an email address
+    /// - `/posts/first` is an article with navigation and a footer around it
+    /// - `/posts/second` is an article with malformed HTML
+    /// - `/old-post` redirects to `/posts/third`, which has a relative link to `/posts/fourth`, which links back to `/posts/third`
+    /// - `/busy` is rate limited once before it responds
+    /// - `/posts/from-sitemap` is only listed in the sitemap
+    /// - `/feed.xml` is an RSS feed of the blog
+    pub(crate) fn blog() -> Self {
+        Self::new()
+            .with_robots_txt(include_str!("fixtures/blog/robots.txt"))
+            .with_sitemap("/sitemap.xml", include_str!("fixtures/blog/sitemap.xml"))
+            .with_file(
+                "/feed.xml",
+                "application/rss+xml",
+                include_str!("fixtures/blog/feed.xml"),
+            )
+            .with_html("/", include_str!("fixtures/blog/index.html"))
+            .with_html("/posts/first", include_str!("fixtures/blog/first.html"))
+            .with_html("/posts/second", include_str!("fixtures/blog/second.html"))
+            .with_redirect("/old-post", "/posts/third")
+            .with_html("/posts/third", include_str!("fixtures/blog/third.html"))
+            .with_html("/posts/fourth", include_str!("fixtures/blog/fourth.html"))
+            .with_html("/busy", include_str!("fixtures/blog/busy.html"))
+            .with_rate_limit("/busy", 1)
+            .with_html("/private/drafts", include_str!("fixtures/blog/drafts.html"))
+            .with_html("/posts/mirror", include_str!("fixtures/blog/mirror.html"))
+            .with_html(
+                "/posts/from-sitemap",
+                include_str!("fixtures/blog/from-sitemap.html"),
+            )
+    }
+
+    /// Serve a file with a content type at a path.
+    pub(crate) fn with_file(
+        mut self,
+        path: &str,
+        content_type: &'static str,
+        body: impl Into<String>,
+    ) -> Self {
+        self.fixtures.insert(
+            path.to_string(),
+            Fixture::File {
+                content_type,
+                body: body.into(),
+            },
+        );
+        self
+    }
+
+    /// Serve an HTML page at a path.
+    pub(crate) fn with_html(self, path: &str, html: impl Into<String>) -> Self {
+        self.with_file(path, "text/html; charset=utf-8", html)
+    }
+
+    /// Serve a robots.txt file.
+    pub(crate) fn with_robots_txt(self, robots_txt: impl Into<String>) -> Self {
+        self.with_file("/robots.txt", "text/plain", robots_txt)
+    }
+
+    /// Serve an XML sitemap at a path.
+    pub(crate) fn with_sitemap(self, path: &str, sitemap: impl Into<String>) -> Self {
+        self.with_file(path, "application/xml", sitemap)
+    }
+
+    /// Permanently redirect a path to another path.
+    pub(crate) fn with_redirect(mut self, from: &str, to: &str) -> Self {
+        self.fixtures
+            .insert(from.to_string(), Fixture::Redirect(to.to_string()));
+        self
+    }
+
+    /// Respond to the first requests for a path with `429 Too Many Requests` before serving the fixture at the path.
+    pub(crate) fn with_rate_limit(mut self, path: &str, failures: usize) -> Self {
+        let then = self
+            .fixtures
+            .remove(path)
+            .unwrap_or(Fixture::Status(StatusCode::NOT_FOUND));
+        self.fixtures.insert(
+            path.to_string(),
+            Fixture::RateLimited {
+                failures,
+                then: Box::new(then),
+            },
+        );
+        self
+    }
+
+    /// Respond to a path with an empty response with a status code.
+    pub(crate) fn with_status(mut self, path: &str, status: StatusCode) -> Self {
+        self.fixtures
+            .insert(path.to_string(), Fixture::Status(status));
+        self
+    }
+
+    /// Start serving the site on a random local port.
+    pub(crate) async fn serve(self) -> ServedSite {
+        let requests = Arc::new(Mutex::new(Vec::<String>::new()));
+        let origins = Arc::new(OnceLock::<Origins>::new());
+        let fixtures = Arc::new(self.fixtures);
+        let router = axum::Router::new().fallback({
+            let requests = requests.clone();
+            let origins = origins.clone();
+            move |uri: Uri| {
+                let fixtures = fixtures.clone();
+                let requests = requests.clone();
+                let origins = origins.clone();
+                async move {
+                    let path = uri.path().to_string();
+                    let previous = {
+                        let mut requests = requests.lock().unwrap();
+                        let previous = requests.iter().filter(|request| **request == path).count();
+                        requests.push(path.clone());
+                        previous
+                    };
+                    let mut fixture = fixtures.get(&path);
+                    while let Some(Fixture::RateLimited { failures, then }) = fixture {
+                        if previous < *failures {
+                            return (StatusCode::TOO_MANY_REQUESTS, [(header::RETRY_AFTER, "0")])
+                                .into_response();
+                        }
+                        fixture = Some(then.as_ref());
+                    }
+                    respond(fixture, origins.get().unwrap())
+                }
+            }
+        });
+        let origin = test_server(router).await;
+        let mut alias = origin.clone();
+        alias.set_host(Some("localhost")).unwrap();
+        origins
+            .set(Origins {
+                origin: origin.as_str().trim_end_matches('/').to_string(),
+                alias: alias.as_str().trim_end_matches('/').to_string(),
+            })
+            .unwrap();
+        ServedSite { origin, requests }
+    }
+}
+
+/// Create the response for a fixture.
+fn respond(fixture: Option<&Fixture>, origins: &Origins) -> Response {
+    match fixture {
+        Some(Fixture::File { content_type, body }) => {
+            let body = body
+                .replace("{origin}", &origins.origin)
+                .replace("{alias}", &origins.alias);
+            ([(header::CONTENT_TYPE, *content_type)], body).into_response()
+        }
+        Some(Fixture::Redirect(to)) => (
+            StatusCode::MOVED_PERMANENTLY,
+            [(header::LOCATION, to.clone())],
+        )
+            .into_response(),
+        Some(Fixture::Status(status)) => (*status).into_response(),
+        Some(Fixture::RateLimited { .. }) | None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// A [`FixtureSite`] that is being served.
+#[derive(Debug, Clone)]
+pub(crate) struct ServedSite {
+    origin: Url,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl ServedSite {
+    /// Get the URL of a path on the site.
+    pub(crate) fn url(&self, path: &str) -> Url {
+        self.origin.join(path).unwrap()
+    }
+
+    /// Get the number of times a path was requested.
+    pub(crate) fn requests(&self, path: &str) -> usize {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|request| *request == path)
+            .count()
+    }
+}
+
+#[tokio::test]
+async fn fixture_sites_are_served() {
+    let site = FixtureSite::blog()
+        .with_status("/teapot", StatusCode::IM_A_TEAPOT)
+        .serve()
+        .await;
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let get = |path: &str| client.get(site.url(path)).send();
+
+    // Placeholders are replaced with the origin of the server
+    let robots = get("/robots.txt").await.unwrap().text().await.unwrap();
+    assert!(robots.contains(&format!("Sitemap: {}sitemap.xml", site.url("/"))));
+    let index = get("/").await.unwrap().text().await.unwrap();
+    assert!(index.contains("http://localhost:"));
+
+    let redirect = get("/old-post").await.unwrap();
+    assert_eq!(redirect.status().as_u16(), 301);
+    assert_eq!(redirect.headers()["location"], "/posts/third");
+
+    assert_eq!(get("/busy").await.unwrap().status().as_u16(), 429);
+    assert_eq!(get("/busy").await.unwrap().status().as_u16(), 200);
+    assert_eq!(site.requests("/busy"), 2);
+
+    assert_eq!(get("/teapot").await.unwrap().status().as_u16(), 418);
+    assert_eq!(get("/nothing-here").await.unwrap().status().as_u16(), 404);
+}
@@ -0,0 +1,128 @@
+use std::path::PathBuf;
+
+use crate::context::document::{Document, DocumentMetadata, IntoDocument};
+
+use super::file_metadata;
+
+/// The metadata key that [`CodeDocument`] stores the [`CodeLanguage`] of the source code under.
+pub const CODE_LANGUAGE_KEY: &str = "code_language";
+
+/// A programming language that [`CodeDocument`] can read and [`CodeChunker`](crate::search::CodeChunker) can split along item boundaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CodeLanguage {
+    /// Rust source code (`.rs`).
+    Rust,
+    /// Python source code (`.py`).
+    Python,
+    /// TypeScript source code (`.ts`, `.tsx`, `.mts`, `.cts`).
+    TypeScript,
+    /// JavaScript source code (`.js`, `.jsx`, `.mjs`, `.cjs`).
+    JavaScript,
+}
+
+impl CodeLanguage {
+    /// Get the language of a file from its extension.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "rs" => Some(Self::Rust),
+            "py" | "pyi" => Some(Self::Python),
+            "ts" | "tsx" | "mts" | "cts" => Some(Self::TypeScript),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Self::JavaScript),
+            _ => None,
+        }
+    }
+
+    /// Get the language of a document from the metadata [`CodeDocument`] stores.
+    pub fn from_metadata(metadata: &DocumentMetadata) -> Option<Self> {
+        metadata.get(CODE_LANGUAGE_KEY)?.parse().ok()
+    }
+
+    /// Get the lowercase name of the language, like `rust`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Rust => "rust",
+            Self::Python => "python",
+            Self::TypeScript => "typescript",
+            Self::JavaScript => "javascript",
+        }
+    }
+
+    /// Get the MIME type of source files in the language.
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            Self::Rust => "text/x-rust",
+            Self::Python => "text/x-python",
+            Self::TypeScript => "text/typescript",
+            Self::JavaScript => "text/javascript",
+        }
+    }
+}
+
+impl std::fmt::Display for CodeLanguage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for CodeLanguage {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "rust" => Ok(Self::Rust),
+            "python" => Ok(Self::Python),
+            "typescript" => Ok(Self::TypeScript),
+            "javascript" => Ok(Self::JavaScript),
+            _ => Err(anyhow::anyhow!("Unknown code language: {s}")),
+        }
+    }
+}
+
+/// A source code file that can be read from the file system.
+///
+/// The title of the document is the name of the file and the body is the source code. The language of the code is stored in the
+/// metadata of the document under [`CODE_LANGUAGE_KEY`] so [`CodeChunker`](crate::search::CodeChunker) can split it along function,
+/// impl and class boundaries.
+#[derive(Debug, Clone)]
+pub struct CodeDocument {
+    path: PathBuf,
+    language: CodeLanguage,
+}
+
+impl TryFrom<PathBuf> for CodeDocument {
+    type Error = anyhow::Error;
+
+    fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
+        if !path.is_file() {
+            return Err(anyhow::anyhow!("Path is not a file"));
+        }
+        let language = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(CodeLanguage::from_extension)
+            .ok_or_else(|| anyhow::anyhow!("Path is not a supported source code file"))?;
+        Ok(Self { path, language })
+    }
+}
+
+impl CodeDocument {
+    /// Get the language of the source code.
+    pub fn language(&self) -> CodeLanguage {
+        self.language
+    }
+}
+
+#[async_trait::async_trait]
+impl IntoDocument for CodeDocument {
+    async fn into_document(self) -> anyhow::Result<Document> {
+        let source = tokio::fs::read_to_string(&self.path).await?;
+        let title = self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let metadata = file_metadata(&self.path, self.language.mime_type())
+            .with(CODE_LANGUAGE_KEY, self.language);
+        Ok(Document::from_parts(title, source).with_metadata(metadata))
+    }
+}
@@ -0,0 +1,130 @@
+use std::path::PathBuf;
+
+use crate::context::document::{Document, IntoDocument, Provenance};
+
+use super::{file_metadata, file_title};
+
+/// A csv or tsv document that can be read from the file system.
+///
+/// The first row is read as the header. Every other row is rendered as a record with one `header: value` line per column, so each
+/// chunk of the document keeps the context of the columns. The row number of each record is recorded in the [`Provenance`] of the spans of the document.
+#[derive(Debug, Clone)]
+pub struct CsvDocument {
+    path: PathBuf,
+    delimiter: u8,
+}
+
+impl TryFrom<PathBuf> for CsvDocument {
+    type Error = anyhow::Error;
+
+    fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
+        if !path.is_file() {
+            return Err(anyhow::anyhow!("Path is not a file"));
+        }
+        let delimiter = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => b',',
+            Some("tsv") => b'\t',
+            _ => return Err(anyhow::anyhow!("Path is not a csv or tsv file")),
+        };
+        Ok(Self { path, delimiter })
+    }
+}
+
+impl CsvDocument {
+    /// Read the rows of the file into a table.
+    fn read(&self) -> anyhow::Result<RenderedTable> {
+        let mut reader = ::csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .flexible(true)
+            .from_path(&self.path)?;
+        let headers = reader
+            .headers()?
+            .iter()
+            .map(|header| header.trim().to_string())
+            .collect::<Vec<_>>();
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            let record = record?;
+            rows.push(record.iter().map(|cell| cell.to_string()).collect());
+        }
+        Ok(render_table(&headers, rows))
+    }
+}
+
+#[async_trait::async_trait]
+impl IntoDocument for CsvDocument {
+    async fn into_document(self) -> anyhow::Result<Document> {
+        let table = {
+            let this = self.clone();
+            tokio::task::spawn_blocking(move || this.read()).await??
+        };
+
+        let mime_type = match self.delimiter {
+            b'\t' => "text/tab-separated-values",
+            _ => "text/csv",
+        };
+        let mut document = Document::from_parts(file_title(&self.path), table.text)
+            .with_metadata(file_metadata(&self.path, mime_type));
+        for (byte_range, row) in table.rows {
+            document.add_span(byte_range, Provenance::default().with_row(row));
+        }
+        Ok(document)
+    }
+}
+
+/// The text of a table along with the byte range of each row.
+pub(crate) struct RenderedTable {
+    pub(crate) text: String,
+    pub(crate) rows: Vec<(std::ops::Range<usize>, usize)>,
+}
+
+/// Render the rows of a table as records with one `header: value` line per column. Empty cells are skipped.
+pub(crate) fn render_table(headers: &[String], rows: Vec<Vec<String>>) -> RenderedTable {
+    let mut text = String::new();
+    let mut ranges = Vec::new();
+    for (index, row) in rows.into_iter().enumerate() {
+        let start = text.len();
+        for (column, cell) in row.iter().enumerate() {
+            let cell = cell.trim();
+            if cell.is_empty() {
+                continue;
+            }
+            match headers.get(column).filter(|header| !header.is_empty()) {
+                Some(header) => text.push_str(&format!("{header}: {cell}\n")),
+                None => text.push_str(&format!("Column {}: {cell}\n", column + 1)),
+            }
+        }
+        if text.len() > start {
+            ranges.push((start..text.len(), index + 1));
+            text.push('\n');
+        }
+    }
+    RenderedTable { text, rows: ranges }
+}
+
+#[test]
+fn rows_are_rendered_with_their_headers() {
+    let headers = vec!["name".to_string(), "language".to_string()];
+    let rows = vec![
+        vec!["Kalosm".to_string(), "Rust".to_string()],
+        vec![String::new(), String::new()],
+        vec!["Floneum".to_string(), String::new(), "extra".to_string()],
+    ];
+    let table = render_table(&headers, rows);
+    assert_eq!(
+        table.text,
+        "name: Kalosm\nlanguage: Rust\n\nname: Floneum\nColumn 3: extra\n\n"
+    );
+    let rows = table
+        .rows
+        .iter()
+        .map(|(range, row)| (&table.text[range.clone()], *row))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rows,
+        [
+            ("name: Kalosm\nlanguage: Rust\n", 1),
+            ("name: Floneum\nColumn 3: extra\n", 3)
+        ]
+    );
+}
@@ -5,6 +5,8 @@ use std::fs::File;
 
 use crate::context::document::{Document, IntoDocument};
 
+use super::file_metadata;
+
 /// A docx document that can be read from the file system.
 #[derive(Debug, Clone)]
 pub struct DocxDocument {
@@ -28,7 +30,7 @@ impl TryFrom<PathBuf> for DocxDocument {
 #[async_trait::async_trait]
 impl IntoDocument for DocxDocument {
     async fn into_document(self) -> anyhow::Result<Document> {
-        let file = File::open(self.path)?;
+        let file = File::open(&self.path)?;
         let reader = std::io::BufReader::new(file);
         let docx = DocxFile::from_xml(reader)?;
         let mut text = String::new();
@@ -78,6 +80,10 @@ impl IntoDocument for DocxDocument {
                 docx_rs::DocumentChild::TableOfContents(_) => {}
             }
         }
-        Ok(Document::from_parts("", text))
+        let metadata = file_metadata(
+            &self.path,
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        );
+        Ok(Document::from_parts("", text).with_metadata(metadata))
     }
 }
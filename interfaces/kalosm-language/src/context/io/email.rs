@@ -0,0 +1,177 @@
+use mail_parser::{Message, MessageParser};
+use std::path::{Path, PathBuf};
+
+use crate::context::document::{Document, IntoDocument, IntoDocuments, Provenance};
+
+use super::file_metadata;
+
+/// An email (`.eml`) that can be read from the file system.
+///
+/// The title of the document is the subject of the email and the body is the plain text body of the email. The sender and date are stored
+/// in the metadata of the document.
+#[derive(Debug, Clone)]
+pub struct EmailDocument {
+    path: PathBuf,
+}
+
+impl TryFrom<PathBuf> for EmailDocument {
+    type Error = anyhow::Error;
+
+    fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
+        if !path.is_file() {
+            return Err(anyhow::anyhow!("Path is not a file"));
+        }
+        if path.extension().unwrap() != "eml" {
+            return Err(anyhow::anyhow!("Path is not a eml file"));
+        }
+        Ok(Self { path })
+    }
+}
+
+#[async_trait::async_trait]
+impl IntoDocument for EmailDocument {
+    async fn into_document(self) -> anyhow::Result<Document> {
+        let bytes = tokio::fs::read(&self.path).await?;
+        let message = MessageParser::default()
+            .parse(&bytes)
+            .ok_or_else(|| anyhow::anyhow!("Failed to parse email"))?;
+        Ok(message_document(&message, &self.path, "message/rfc822"))
+    }
+}
+
+/// A mailbox (`.mbox`) of emails that can be read from the file system.
+///
+/// As a single document, the emails are joined in order with the subject of each email recorded in the [`Provenance`] of the spans of the
+/// document. With [`IntoDocuments`], every email is a separate document.
+#[derive(Debug, Clone)]
+pub struct MboxDocument {
+    path: PathBuf,
+}
+
+impl TryFrom<PathBuf> for MboxDocument {
+    type Error = anyhow::Error;
+
+    fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
+        if !path.is_file() {
+            return Err(anyhow::anyhow!("Path is not a file"));
+        }
+        if path.extension().unwrap() != "mbox" {
+            return Err(anyhow::anyhow!("Path is not a mbox file"));
+        }
+        Ok(Self { path })
+    }
+}
+
+impl MboxDocument {
+    async fn messages(&self) -> anyhow::Result<Vec<Document>> {
+        let contents = tokio::fs::read(&self.path).await?;
+        let contents = String::from_utf8_lossy(&contents);
+        let parser = MessageParser::default();
+        Ok(split_mbox(&contents)
+            .iter()
+            .filter_map(|message| parser.parse(message.as_bytes()))
+            .map(|message| message_document(&message, &self.path, "application/mbox"))
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl IntoDocument for MboxDocument {
+    async fn into_document(self) -> anyhow::Result<Document> {
+        let messages = self.messages().await?;
+        let mut text = String::new();
+        let mut spans = Vec::new();
+        for message in &messages {
+            let start = text.len();
+            text.push_str(&format!("{}\n\n{}", message.title(), message.body().trim()));
+            spans.push((start..text.len(), message.title().to_string()));
+            text.push_str("\n\n");
+        }
+
+        let title = self
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut document = Document::from_parts(title, text)
+            .with_metadata(file_metadata(&self.path, "application/mbox"));
+        for (byte_range, subject) in spans {
+            document.add_span(byte_range, Provenance::heading_path([subject]));
+        }
+        Ok(document)
+    }
+}
+
+#[async_trait::async_trait]
+impl IntoDocuments for MboxDocument {
+    async fn into_documents(self) -> anyhow::Result<Vec<Document>> {
+        self.messages().await
+    }
+}
+
+/// Convert a parsed email into a document.
+fn message_document(message: &Message, path: &Path, mime_type: &str) -> Document {
+    let subject = message.subject().unwrap_or_default();
+    let body = message.body_text(0).unwrap_or_default();
+    let mut metadata = file_metadata(path, mime_type);
+    if let Some(sender) = message.from().and_then(|from| from.first()) {
+        let sender = match (sender.name(), sender.address()) {
+            (Some(name), Some(address)) => format!("{name} <{address}>"),
+            (Some(name), None) => name.to_string(),
+            (None, Some(address)) => address.to_string(),
+            (None, None) => String::new(),
+        };
+        if !sender.is_empty() {
+            metadata.add_author(sender);
+        }
+    }
+    let mut document = Document::from_parts(subject, body).with_metadata(metadata);
+    if let Some(date) = message
+        .date()
+        .and_then(|date| chrono::DateTime::from_timestamp(date.to_timestamp(), 0))
+    {
+        document.set_created_at(date);
+    }
+    document
+}
+
+/// Split the contents of a mailbox into the raw emails. Every email starts with a `From ` line after an empty line.
+fn split_mbox(contents: &str) -> Vec<String> {
+    let mut messages = Vec::new();
+    let mut current: Option<String> = None;
+    let mut previous_line_empty = true;
+    for line in contents.split_inclusive('\n') {
+        if previous_line_empty && line.starts_with("From ") {
+            messages.extend(current.take());
+            current = Some(String::new());
+        } else if let Some(message) = &mut current {
+            // Lines starting with "From " in the body are escaped with a ">"
+            match line.strip_prefix('>') {
+                Some(unescaped) if unescaped.trim_start_matches('>').starts_with("From ") => {
+                    message.push_str(unescaped)
+                }
+                _ => message.push_str(line),
+            }
+        }
+        previous_line_empty = line.trim_end_matches(['\r', '\n']).is_empty();
+    }
+    messages.extend(current);
+    messages
+}
+
+#[test]
+fn mbox_is_split_into_emails() {
+    let mbox = "From alice@example.com Mon Jan  1 00:00:00 2024\nSubject: First\n\nHello\n>From the start\n\nFrom bob@example.com Tue Jan  2 00:00:00 2024\nSubject: Second\n\nGoodbye\n";
+    let messages = split_mbox(mbox);
+    assert_eq!(
+        messages,
+        [
+            "Subject: First\n\nHello\nFrom the start\n\n",
+            "Subject: Second\n\nGoodbye\n"
+        ]
+    );
+
+    let parser = MessageParser::default();
+    let first = parser.parse(messages[0].as_bytes()).unwrap();
+    assert_eq!(first.subject(), Some("First"));
+}
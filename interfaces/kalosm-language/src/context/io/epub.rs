@@ -0,0 +1,360 @@
+use quick_xml::events::{BytesStart, Event};
+use scraper::{Html, Node};
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::path::PathBuf;
+
+use crate::context::document::{Document, IntoDocument, IntoDocuments, Provenance};
+
+use super::{file_metadata, file_title};
+
+/// An epub book that can be read from the file system.
+///
+/// The chapters are read in the reading order of the book. As a single document, the title of each chapter is recorded in the
+/// [`Provenance`] of the spans of the document. With [`IntoDocuments`], every chapter is a separate document.
+#[derive(Debug, Clone)]
+pub struct EpubDocument {
+    path: PathBuf,
+}
+
+impl TryFrom<PathBuf> for EpubDocument {
+    type Error = anyhow::Error;
+
+    fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
+        if !path.is_file() {
+            return Err(anyhow::anyhow!("Path is not a file"));
+        }
+        if path.extension().unwrap() != "epub" {
+            return Err(anyhow::anyhow!("Path is not a epub file"));
+        }
+        Ok(Self { path })
+    }
+}
+
+impl EpubDocument {
+    async fn read(&self) -> anyhow::Result<Epub> {
+        let bytes = tokio::fs::read(&self.path).await?;
+        tokio::task::spawn_blocking(move || Epub::new(bytes)).await?
+    }
+}
+
+#[async_trait::async_trait]
+impl IntoDocument for EpubDocument {
+    async fn into_document(self) -> anyhow::Result<Document> {
+        let epub = self.read().await?;
+        let mut text = String::new();
+        let mut spans = Vec::new();
+        for chapter in &epub.chapters {
+            let start = text.len();
+            text.push_str(&chapter.text);
+            spans.push((start..text.len(), chapter.title.clone()));
+            text.push_str("\n\n");
+        }
+
+        let title = epub.title.unwrap_or_else(|| file_title(&self.path));
+        let mut metadata = file_metadata(&self.path, "application/epub+zip");
+        for author in epub.authors {
+            metadata.add_author(author);
+        }
+        let mut document = Document::from_parts(title, text).with_metadata(metadata);
+        for (byte_range, chapter) in spans {
+            document.add_span(byte_range, Provenance::heading_path([chapter]));
+        }
+        Ok(document)
+    }
+}
+
+#[async_trait::async_trait]
+impl IntoDocuments for EpubDocument {
+    async fn into_documents(self) -> anyhow::Result<Vec<Document>> {
+        let epub = self.read().await?;
+        let mut metadata = file_metadata(&self.path, "application/epub+zip");
+        if let Some(title) = &epub.title {
+            metadata.insert("book", title);
+        }
+        for author in epub.authors {
+            metadata.add_author(author);
+        }
+        Ok(epub
+            .chapters
+            .into_iter()
+            .map(|chapter| {
+                Document::from_parts(chapter.title, chapter.text).with_metadata(metadata.clone())
+            })
+            .collect())
+    }
+}
+
+/// The contents of an epub book.
+struct Epub {
+    title: Option<String>,
+    authors: Vec<String>,
+    chapters: Vec<Chapter>,
+}
+
+struct Chapter {
+    title: String,
+    text: String,
+}
+
+impl Epub {
+    fn new(bytes: Vec<u8>) -> anyhow::Result<Self> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+        let container = read_entry(&mut archive, "META-INF/container.xml")?;
+        let package_path = container_rootfile(&container)?;
+        let package = Package::new(&read_entry(&mut archive, &package_path)?)?;
+        // Paths in the package are relative to the folder of the package
+        let base = match package_path.rsplit_once('/') {
+            Some((folder, _)) => format!("{folder}/"),
+            None => String::new(),
+        };
+
+        let mut chapters = Vec::new();
+        for idref in &package.spine {
+            let Some(href) = package.manifest.get(idref) else {
+                continue;
+            };
+            let path = format!("{base}{}", href.split('#').next().unwrap_or_default());
+            let Ok(xhtml) = read_entry(&mut archive, &path) else {
+                continue;
+            };
+            let (heading, text) = xhtml_text(&xhtml);
+            if text.is_empty() {
+                continue;
+            }
+            let title = heading.unwrap_or_else(|| format!("Chapter {}", chapters.len() + 1));
+            chapters.push(Chapter { title, text });
+        }
+
+        Ok(Self {
+            title: package.title,
+            authors: package.authors,
+            chapters,
+        })
+    }
+}
+
+fn read_entry(
+    archive: &mut zip::ZipArchive<Cursor<Vec<u8>>>,
+    name: &str,
+) -> anyhow::Result<String> {
+    let mut entry = archive.by_name(name)?;
+    let mut contents = String::new();
+    entry.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+fn attribute(element: &BytesStart, name: &str) -> anyhow::Result<Option<String>> {
+    for attribute in element.attributes() {
+        let attribute = attribute?;
+        if attribute.key.local_name().as_ref() == name.as_bytes() {
+            return Ok(Some(attribute.unescape_value()?.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// Find the path of the package document in `META-INF/container.xml`.
+fn container_rootfile(container: &str) -> anyhow::Result<String> {
+    let mut reader = quick_xml::Reader::from_str(container);
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) | Event::Empty(element)
+                if element.local_name().as_ref() == b"rootfile" =>
+            {
+                if let Some(path) = attribute(&element, "full-path")? {
+                    return Ok(path);
+                }
+            }
+            Event::Eof => return Err(anyhow::anyhow!("Epub is missing a rootfile")),
+            _ => {}
+        }
+    }
+}
+
+/// The metadata, manifest and reading order of an epub book.
+struct Package {
+    title: Option<String>,
+    authors: Vec<String>,
+    manifest: HashMap<String, String>,
+    spine: Vec<String>,
+}
+
+impl Package {
+    fn new(package: &str) -> anyhow::Result<Self> {
+        let mut title = None;
+        let mut authors = Vec::new();
+        let mut manifest = HashMap::new();
+        let mut spine = Vec::new();
+        // The metadata element we are currently reading the text of
+        let mut current: Option<(&'static str, String)> = None;
+
+        let mut reader = quick_xml::Reader::from_str(package);
+        loop {
+            let event = reader.read_event()?;
+            match &event {
+                Event::Start(element) | Event::Empty(element) => {
+                    let start = matches!(event, Event::Start(_));
+                    match element.local_name().as_ref() {
+                        b"title" if start && title.is_none() => {
+                            current = Some(("title", String::new()))
+                        }
+                        b"creator" if start => current = Some(("creator", String::new())),
+                        b"item" => {
+                            if let (Some(id), Some(href)) =
+                                (attribute(element, "id")?, attribute(element, "href")?)
+                            {
+                                manifest.insert(id, href);
+                            }
+                        }
+                        b"itemref" => spine.extend(attribute(element, "idref")?),
+                        _ => {}
+                    }
+                }
+                Event::Text(text) => {
+                    if let Some((_, contents)) = &mut current {
+                        contents.push_str(&text.unescape()?);
+                    }
+                }
+                Event::End(_) => match current.take() {
+                    Some(("title", contents)) => title = Some(contents.trim().to_string()),
+                    Some((_, contents)) => authors.push(contents.trim().to_string()),
+                    None => {}
+                },
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            title: title.filter(|title| !title.is_empty()),
+            authors: authors
+                .into_iter()
+                .filter(|author| !author.is_empty())
+                .collect(),
+            manifest,
+            spine,
+        })
+    }
+}
+
+/// Get the first heading and the plain text of a chapter. Paragraphs are separated by an empty line.
+fn xhtml_text(xhtml: &str) -> (Option<String>, String) {
+    const BLOCKS: &[&str] = &[
+        "p",
+        "div",
+        "section",
+        "article",
+        "h1",
+        "h2",
+        "h3",
+        "h4",
+        "h5",
+        "h6",
+        "li",
+        "blockquote",
+        "pre",
+        "tr",
+        "br",
+        "hr",
+        "figcaption",
+    ];
+    let html = Html::parse_document(xhtml);
+    let mut heading = None;
+    let mut current_heading: Option<String> = None;
+    let mut skipped = 0;
+    let mut text = String::new();
+    for edge in html.root_element().traverse() {
+        match edge {
+            ego_tree::iter::Edge::Open(node) => match node.value() {
+                Node::Element(element) => match element.name() {
+                    "head" | "script" | "style" => skipped += 1,
+                    "h1" | "h2" | "h3" if heading.is_none() && skipped == 0 => {
+                        current_heading = Some(String::new())
+                    }
+                    _ => {}
+                },
+                Node::Text(contents) if skipped == 0 => {
+                    if let Some(heading) = &mut current_heading {
+                        heading.push_str(contents);
+                    }
+                    text.push_str(contents);
+                }
+                _ => {}
+            },
+            ego_tree::iter::Edge::Close(node) => {
+                if let Node::Element(element) = node.value() {
+                    let name = element.name();
+                    if matches!(name, "head" | "script" | "style") {
+                        skipped -= 1;
+                    }
+                    if matches!(name, "h1" | "h2" | "h3") {
+                        if let Some(contents) = current_heading.take() {
+                            let contents =
+                                contents.split_whitespace().collect::<Vec<_>>().join(" ");
+                            heading = (!contents.is_empty()).then_some(contents);
+                        }
+                    }
+                    if BLOCKS.contains(&name) {
+                        text.push_str("\n\n");
+                    }
+                }
+            }
+        }
+    }
+
+    let text = text
+        .split("\n\n")
+        .map(|paragraph| paragraph.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|paragraph| !paragraph.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    (heading, text)
+}
+
+#[test]
+fn chapters_are_read_in_spine_order() {
+    use std::io::Write;
+
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let files = [
+        (
+            "META-INF/container.xml",
+            r#"<?xml version="1.0"?><container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container"><rootfiles><rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/></rootfiles></container>"#,
+        ),
+        (
+            "OEBPS/content.opf",
+            r#"<?xml version="1.0"?><package xmlns="http://www.idpf.org/2007/opf" xmlns:dc="http://purl.org/dc/elements/1.1/" version="3.0"><metadata><dc:title>The Book</dc:title><dc:creator>Ada Lovelace</dc:creator></metadata><manifest><item id="one" href="text/one.xhtml" media-type="application/xhtml+xml"/><item id="two" href="text/two.xhtml" media-type="application/xhtml+xml"/></manifest><spine><itemref idref="two"/><itemref idref="one"/></spine></package>"#,
+        ),
+        (
+            "OEBPS/text/one.xhtml",
+            "<html><head><title>ignored</title></head><body><h1>Ending</h1><p>The  end.</p></body></html>",
+        ),
+        (
+            "OEBPS/text/two.xhtml",
+            "<html><body><h2>Beginning</h2><p>Once upon\na time.</p><p>Then</p></body></html>",
+        ),
+    ];
+    for (name, contents) in files {
+        zip.start_file(name, zip::write::FileOptions::default())
+            .unwrap();
+        zip.write_all(contents.as_bytes()).unwrap();
+    }
+    let bytes = zip.finish().unwrap().into_inner();
+
+    let epub = Epub::new(bytes).unwrap();
+    assert_eq!(epub.title.as_deref(), Some("The Book"));
+    assert_eq!(epub.authors, ["Ada Lovelace"]);
+    let chapters = epub
+        .chapters
+        .iter()
+        .map(|chapter| (chapter.title.as_str(), chapter.text.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        chapters,
+        [
+            ("Beginning", "Beginning\n\nOnce upon a time.\n\nThen"),
+            ("Ending", "Ending\n\nThe end.")
+        ]
+    );
+}
@@ -1,16 +1,28 @@
 use std::path::PathBuf;
+use url::Url;
 
 use tokio::{fs::File, io::AsyncReadExt};
 
 use crate::context::{
     document::{Document, IntoDocument},
-    page::extract_article,
+    page::{extract_article_as, ArticleFormat},
 };
 
+use super::file_metadata;
+
 /// An html document that can be read from the file system.
 #[derive(Debug, Clone)]
 pub struct HtmlDocument {
     path: PathBuf,
+    format: ArticleFormat,
+}
+
+impl HtmlDocument {
+    /// Set the format of the body of the document. (default: [`ArticleFormat::PlainText`])
+    pub fn with_format(mut self, format: ArticleFormat) -> Self {
+        self.format = format;
+        self
+    }
 }
 
 impl TryFrom<PathBuf> for HtmlDocument {
@@ -23,18 +35,26 @@ impl TryFrom<PathBuf> for HtmlDocument {
         if path.extension().unwrap() != "html" {
             return Err(anyhow::anyhow!("Path is not a html file"));
         }
-        Ok(Self { path })
+        Ok(Self {
+            path,
+            format: ArticleFormat::default(),
+        })
     }
 }
 
 #[async_trait::async_trait]
 impl IntoDocument for HtmlDocument {
     async fn into_document(self) -> anyhow::Result<Document> {
-        let file = File::open(self.path).await?;
+        let file = File::open(&self.path).await?;
         let mut html = String::new();
         tokio::io::BufReader::new(file)
             .read_to_string(&mut html)
             .await?;
-        extract_article(&html)
+        // Relative links are resolved against the location of the file
+        let path = self.path.canonicalize()?;
+        let url = Url::from_file_path(&path)
+            .map_err(|_| anyhow::anyhow!("Failed to create a file URL for {}", path.display()))?;
+        let document = extract_article_as(&html, &url, self.format)?;
+        Ok(document.with_metadata(file_metadata(&self.path, "text/html")))
     }
 }
@@ -1,16 +1,32 @@
-use std::path::PathBuf;
+use pulldown_cmark::{Event, HeadingLevel, Tag};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
 use tokio::{fs::File, io::AsyncReadExt};
 
-use crate::context::{
-    document::{Document, IntoDocument},
-    page::extract_article,
-};
+use crate::context::document::{Document, DocumentMetadata, IntoDocument, Provenance};
+use crate::context::page::ArticleFormat;
+use crate::search::MarkdownSection;
+
+use super::{file_metadata, file_title};
 
 /// A markdown document that can be read from the file system.
+///
+/// By default, the body of the document is the text of the markdown without any formatting. With [`ArticleFormat::Markdown`], the body is
+/// the markdown source so [`MarkdownChunker`](crate::search::MarkdownChunker) can split it along headings, tables and code blocks. The
+/// heading path of each section is recorded in the [`Provenance`] of the spans of the document.
 #[derive(Debug, Clone)]
 pub struct MdDocument {
     path: PathBuf,
+    format: ArticleFormat,
+}
+
+impl MdDocument {
+    /// Set the format of the body of the document. (default: [`ArticleFormat::PlainText`])
+    pub fn with_format(mut self, format: ArticleFormat) -> Self {
+        self.format = format;
+        self
+    }
 }
 
 impl TryFrom<PathBuf> for MdDocument {
@@ -23,22 +39,209 @@ impl TryFrom<PathBuf> for MdDocument {
         if path.extension().unwrap() != "md" {
             return Err(anyhow::anyhow!("Path is not a md file"));
         }
-        Ok(Self { path })
+        Ok(Self {
+            path,
+            format: ArticleFormat::default(),
+        })
     }
 }
 
 #[async_trait::async_trait]
 impl IntoDocument for MdDocument {
     async fn into_document(self) -> anyhow::Result<Document> {
-        let file = File::open(self.path).await?;
+        let file = File::open(&self.path).await?;
         let mut md = String::new();
         tokio::io::BufReader::new(file)
             .read_to_string(&mut md)
             .await?;
-        let parser = pulldown_cmark::Parser::new(&md);
 
-        let mut html_output = String::new();
-        pulldown_cmark::html::push_html(&mut html_output, parser);
-        extract_article(&html_output)
+        let metadata = file_metadata(&self.path, "text/markdown");
+        Ok(markdown_document(md, &self.path, metadata, self.format))
+    }
+}
+
+/// Create a document from markdown in the format with the heading path of each section. If the markdown has no top level heading, the
+/// title is the name of the file.
+pub(crate) fn markdown_document(
+    markdown: String,
+    path: &Path,
+    metadata: DocumentMetadata,
+    format: ArticleFormat,
+) -> Document {
+    if format == ArticleFormat::PlainText {
+        return RenderedMarkdown::new(&markdown).into_document(path, metadata);
+    }
+
+    let sections = MarkdownSection::parse(&markdown);
+    let title = sections
+        .iter()
+        .find(|section| section.heading_level == Some(1))
+        .and_then(|section| section.heading_path.last().cloned())
+        .unwrap_or_else(|| file_title(path));
+    let mut document = Document::from_parts(title, markdown).with_metadata(metadata);
+    for section in sections {
+        if !section.heading_path.is_empty() {
+            document.add_span(
+                section.byte_range,
+                Provenance::heading_path(section.heading_path),
+            );
+        }
+    }
+    document
+}
+
+/// The plain text of a markdown document along with the heading path of each section.
+pub(crate) struct RenderedMarkdown {
+    pub(crate) title: Option<String>,
+    pub(crate) text: String,
+    pub(crate) sections: Vec<(Range<usize>, Vec<String>)>,
+}
+
+impl RenderedMarkdown {
+    pub(crate) fn new(markdown: &str) -> Self {
+        let mut title = None;
+        let mut text = String::new();
+        let mut sections = Vec::new();
+        // The headings above the current position and their level
+        let mut headings: Vec<(HeadingLevel, String)> = Vec::new();
+        // The text of the heading we are currently inside of
+        let mut current_heading: Option<String> = None;
+        let mut section_start = 0;
+
+        let close_section =
+            |sections: &mut Vec<_>, headings: &[(HeadingLevel, String)], range: Range<usize>| {
+                if !headings.is_empty() && !range.is_empty() {
+                    let path = headings
+                        .iter()
+                        .map(|(_, heading)| heading.clone())
+                        .collect();
+                    sections.push((range, path));
+                }
+            };
+
+        for event in pulldown_cmark::Parser::new(markdown) {
+            match event {
+                Event::Start(Tag::Heading(..)) => {
+                    close_section(&mut sections, &headings, section_start..text.len());
+                    section_start = text.len();
+                    current_heading = Some(String::new());
+                }
+                Event::End(Tag::Heading(level, ..)) => {
+                    let heading = current_heading.take().unwrap_or_default();
+                    let heading = heading.trim().to_string();
+                    if level == HeadingLevel::H1 && title.is_none() {
+                        title = Some(heading.clone());
+                    }
+                    headings.retain(|(parent, _)| (*parent as usize) < (level as usize));
+                    headings.push((level, heading));
+                    text.push_str("\n\n");
+                }
+                Event::Text(content) | Event::Code(content) => {
+                    if let Some(heading) = &mut current_heading {
+                        heading.push_str(&content);
+                    }
+                    text.push_str(&content);
+                }
+                Event::SoftBreak | Event::HardBreak => text.push('\n'),
+                Event::End(
+                    Tag::Paragraph | Tag::CodeBlock(_) | Tag::BlockQuote | Tag::Table(_),
+                ) => text.push_str("\n\n"),
+                Event::End(Tag::Item | Tag::TableRow | Tag::TableHead) => text.push('\n'),
+                Event::End(Tag::TableCell) => text.push('\t'),
+                _ => {}
+            }
+        }
+        close_section(&mut sections, &headings, section_start..text.len());
+
+        Self {
+            title,
+            text,
+            sections,
+        }
+    }
+
+    /// Create a document with the heading path of each section. If the markdown has no top level heading, the title is the name of the file.
+    pub(crate) fn into_document(self, path: &Path, metadata: DocumentMetadata) -> Document {
+        let title = self.title.unwrap_or_else(|| file_title(path));
+        let mut document = Document::from_parts(title, self.text).with_metadata(metadata);
+        for (byte_range, heading_path) in self.sections {
+            document.add_span(byte_range, Provenance::heading_path(heading_path));
+        }
+        document
     }
 }
+
+#[test]
+fn markdown_source_sections_have_heading_paths() {
+    let markdown = "# Kalosm\n\nIntro text\n\n## Install\n\nRun `cargo add kalosm`\n\n### Features\n\n- language\n- sound\n\n## Usage\n\nCall the model\n";
+    let document = markdown_document(
+        markdown.to_string(),
+        Path::new("readme.md"),
+        DocumentMetadata::new(),
+        ArticleFormat::Markdown,
+    );
+    assert_eq!(document.title(), "Kalosm");
+    assert_eq!(document.body(), markdown);
+
+    let sections = document
+        .spans()
+        .iter()
+        .map(|span| {
+            (
+                document.body()[span.byte_range.clone()].trim(),
+                span.provenance.heading_path.join(" > "),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sections,
+        [
+            ("# Kalosm\n\nIntro text", "Kalosm".to_string()),
+            (
+                "## Install\n\nRun `cargo add kalosm`",
+                "Kalosm > Install".to_string()
+            ),
+            (
+                "### Features\n\n- language\n- sound",
+                "Kalosm > Install > Features".to_string()
+            ),
+            ("## Usage\n\nCall the model", "Kalosm > Usage".to_string()),
+        ]
+    );
+
+    let untitled = markdown_document(
+        "Some text".to_string(),
+        Path::new("release-notes.md"),
+        DocumentMetadata::new(),
+        ArticleFormat::Markdown,
+    );
+    assert_eq!(untitled.title(), "Release Notes");
+}
+
+#[test]
+fn markdown_sections_have_heading_paths() {
+    let markdown = "# Kalosm\n\nIntro text\n\n## Install\n\nRun `cargo add kalosm`\n\n### Features\n\n- language\n- sound\n\n## Usage\n\nCall the model\n";
+    let rendered = RenderedMarkdown::new(markdown);
+    assert_eq!(rendered.title.as_deref(), Some("Kalosm"));
+
+    let sections = rendered
+        .sections
+        .iter()
+        .map(|(range, path)| (rendered.text[range.clone()].trim(), path.join(" > ")))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sections,
+        [
+            ("Kalosm\n\nIntro text", "Kalosm".to_string()),
+            (
+                "Install\n\nRun cargo add kalosm",
+                "Kalosm > Install".to_string()
+            ),
+            (
+                "Features\n\nlanguage\nsound",
+                "Kalosm > Install > Features".to_string()
+            ),
+            ("Usage\n\nCall the model", "Kalosm > Usage".to_string()),
+        ]
+    );
+}
@@ -1,18 +1,52 @@
 use crate::context::document::Document;
+use crate::context::document::DocumentMetadata;
 use crate::context::document::IntoDocument;
 use crate::context::document::IntoDocuments;
-use std::path::PathBuf;
+use convert_case::{Case, Casing};
+use std::path::{Path, PathBuf};
 use tokio::task::JoinSet;
+mod code;
+pub use code::*;
+mod csv;
+pub use self::csv::*;
 mod docx;
 pub use docx::*;
+mod email;
+pub use email::*;
+mod epub;
+pub use epub::*;
 mod html;
 pub use html::*;
 mod md;
 pub use md::*;
+mod notebook;
+pub use notebook::*;
 mod pdf;
 pub use self::pdf::*;
 mod txt;
 pub use txt::*;
+mod xlsx;
+pub use xlsx::*;
+
+/// Create the metadata of a document read from a file. The source is set to the `file://` url of the file.
+pub(crate) fn file_metadata(path: &Path, mime_type: &str) -> DocumentMetadata {
+    let metadata = DocumentMetadata::new().with_mime_type(mime_type);
+    let url = path
+        .canonicalize()
+        .ok()
+        .and_then(|path| url::Url::from_file_path(path).ok());
+    match url {
+        Some(url) => metadata.with_source(url),
+        None => metadata.with_source(path.display()),
+    }
+}
+
+/// Get the title of a document from the name of the file.
+pub(crate) fn file_title(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_case(Case::Title))
+        .unwrap_or_default()
+}
 
 /// A document that can be read from the file system.
 ///
@@ -29,16 +63,30 @@ pub use txt::*;
 /// ```
 #[derive(Debug, Clone)]
 pub enum FsDocument {
+    /// A source code file.
+    Code(CodeDocument),
+    /// A csv or tsv document.
+    Csv(CsvDocument),
     /// A docx document.
     Docx(DocxDocument),
+    /// An email.
+    Email(EmailDocument),
+    /// An epub book.
+    Epub(EpubDocument),
     /// An html document.
     Html(HtmlDocument),
+    /// A mailbox of emails.
+    Mbox(MboxDocument),
     /// A markdown document.
     Md(MdDocument),
+    /// A Jupyter notebook.
+    Notebook(NotebookDocument),
     /// A pdf document.
     Pdf(PdfDocument),
     /// A text document.
     Txt(TextDocument),
+    /// An xlsx spreadsheet.
+    Xlsx(XlsxDocument),
 }
 
 impl TryFrom<PathBuf> for FsDocument {
@@ -49,11 +97,20 @@ impl TryFrom<PathBuf> for FsDocument {
             return Err(anyhow::anyhow!("Path is not a file"));
         }
         match path.extension().and_then(|ext| ext.to_str()) {
+            Some("csv" | "tsv") => Ok(Self::Csv(CsvDocument::try_from(path)?)),
             Some("docx") => Ok(Self::Docx(DocxDocument::try_from(path)?)),
+            Some("eml") => Ok(Self::Email(EmailDocument::try_from(path)?)),
+            Some("epub") => Ok(Self::Epub(EpubDocument::try_from(path)?)),
             Some("html") => Ok(Self::Html(HtmlDocument::try_from(path)?)),
+            Some("mbox") => Ok(Self::Mbox(MboxDocument::try_from(path)?)),
             Some("md") => Ok(Self::Md(MdDocument::try_from(path)?)),
+            Some("ipynb") => Ok(Self::Notebook(NotebookDocument::try_from(path)?)),
             Some("pdf") => Ok(Self::Pdf(PdfDocument::try_from(path)?)),
             Some("txt") => Ok(Self::Txt(TextDocument::try_from(path)?)),
+            Some("xlsx") => Ok(Self::Xlsx(XlsxDocument::try_from(path)?)),
+            Some(ext) if CodeLanguage::from_extension(ext).is_some() => {
+                Ok(Self::Code(CodeDocument::try_from(path)?))
+            }
             _ => Err(anyhow::anyhow!("Path is not a supported file type")),
         }
     }
@@ -63,17 +120,37 @@ impl TryFrom<PathBuf> for FsDocument {
 impl IntoDocument for FsDocument {
     async fn into_document(self) -> anyhow::Result<Document> {
         match self {
+            Self::Code(code) => code.into_document().await,
+            Self::Csv(csv) => csv.into_document().await,
             Self::Docx(docx) => docx.into_document().await,
+            Self::Email(email) => email.into_document().await,
+            Self::Epub(epub) => epub.into_document().await,
             Self::Html(html) => html.into_document().await,
+            Self::Mbox(mbox) => mbox.into_document().await,
             Self::Md(md) => md.into_document().await,
+            Self::Notebook(notebook) => notebook.into_document().await,
             Self::Pdf(pdf) => pdf.into_document().await,
             Self::Txt(txt) => txt.into_document().await,
+            Self::Xlsx(xlsx) => xlsx.into_document().await,
+        }
+    }
+}
+
+/// Mailboxes are read as one document per email. Every other file type is read as a single document.
+#[async_trait::async_trait]
+impl IntoDocuments for FsDocument {
+    async fn into_documents(self) -> anyhow::Result<Vec<Document>> {
+        match self {
+            Self::Mbox(mbox) => mbox.into_documents().await,
+            document => Ok(vec![document.into_document().await?]),
         }
     }
 }
 
 /// A folder full of documents.
 ///
+/// Every file with a supported file type is read with [`FsDocument`]. Mailboxes are split into one document per email.
+///
 /// # Example
 /// ```rust, no_run
 /// use kalosm_language::prelude::*;
@@ -133,7 +210,7 @@ impl IntoDocuments for DocumentFolder {
         self.start_into_documents(&mut set).await?;
         let mut documents = Vec::new();
         while let Some(join) = set.join_next().await {
-            documents.push(join??);
+            documents.extend(join??);
         }
         Ok(documents)
     }
@@ -152,9 +229,32 @@ impl DocumentFolder {
         Self::try_from(path.into())
     }
 
+    /// Get the path of the folder.
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// Get the paths of every document with a supported file type in the folder and its subfolders.
+    pub async fn files(&self) -> anyhow::Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let mut folders = vec![self.path.clone()];
+        while let Some(folder) = folders.pop() {
+            let mut read_dir = tokio::fs::read_dir(&folder).await?;
+            while let Some(entry) = read_dir.next_entry().await? {
+                let path = entry.path();
+                if path.is_dir() {
+                    folders.push(path);
+                } else if FsDocument::try_from(path.clone()).is_ok() {
+                    files.push(path);
+                }
+            }
+        }
+        Ok(files)
+    }
+
     fn start_into_documents<'a>(
         &'a self,
-        set: &'a mut JoinSet<anyhow::Result<Document>>,
+        set: &'a mut JoinSet<anyhow::Result<Vec<Document>>>,
     ) -> std::pin::Pin<Box<dyn std::future::Future<Output = anyhow::Result<()>> + Send + Sync + 'a>>
     {
         Box::pin(async move {
@@ -166,7 +266,7 @@ impl DocumentFolder {
                         folder.start_into_documents(set).await?;
                     }
                 } else if let Ok(document) = FsDocument::try_from(path) {
-                    set.spawn(document.into_document());
+                    set.spawn(document.into_documents());
                 }
             }
             Ok(())
@@ -0,0 +1,167 @@
+use serde::Deserialize;
+use std::path::PathBuf;
+
+use crate::context::document::{Document, IntoDocument};
+use crate::context::page::ArticleFormat;
+
+use super::file_metadata;
+use super::md::markdown_document;
+
+/// A Jupyter notebook (`.ipynb`) that can be read from the file system.
+///
+/// The markdown cells and code cells are read in order. Code cells are kept as code blocks in the language of the notebook, and the
+/// heading path of each section is recorded in the [`Provenance`](crate::context::Provenance) of the spans of the document. Outputs are
+/// skipped. Like [`MdDocument`](super::MdDocument), the body is plain text unless the format is set to [`ArticleFormat::Markdown`].
+#[derive(Debug, Clone)]
+pub struct NotebookDocument {
+    path: PathBuf,
+    format: ArticleFormat,
+}
+
+impl NotebookDocument {
+    /// Set the format of the body of the document. (default: [`ArticleFormat::PlainText`])
+    pub fn with_format(mut self, format: ArticleFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+impl TryFrom<PathBuf> for NotebookDocument {
+    type Error = anyhow::Error;
+
+    fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
+        if !path.is_file() {
+            return Err(anyhow::anyhow!("Path is not a file"));
+        }
+        if path.extension().unwrap() != "ipynb" {
+            return Err(anyhow::anyhow!("Path is not a ipynb file"));
+        }
+        Ok(Self {
+            path,
+            format: ArticleFormat::default(),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct Notebook {
+    #[serde(default)]
+    cells: Vec<Cell>,
+    #[serde(default)]
+    metadata: NotebookMetadata,
+}
+
+#[derive(Deserialize, Default)]
+struct NotebookMetadata {
+    language_info: Option<LanguageInfo>,
+}
+
+#[derive(Deserialize)]
+struct LanguageInfo {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Cell {
+    cell_type: String,
+    source: CellSource,
+}
+
+/// The source of a cell is either one string or a list of lines.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CellSource {
+    Text(String),
+    Lines(Vec<String>),
+}
+
+impl CellSource {
+    fn text(&self) -> String {
+        match self {
+            Self::Text(text) => text.clone(),
+            Self::Lines(lines) => lines.concat(),
+        }
+    }
+}
+
+impl Notebook {
+    /// Convert the cells of the notebook into one markdown document.
+    fn to_markdown(&self) -> String {
+        let language = self
+            .metadata
+            .language_info
+            .as_ref()
+            .map(|info| info.name.as_str())
+            .unwrap_or_default();
+        let mut markdown = String::new();
+        for cell in &self.cells {
+            let source = cell.source.text();
+            if source.trim().is_empty() {
+                continue;
+            }
+            match cell.cell_type.as_str() {
+                "markdown" => markdown.push_str(source.trim_end()),
+                "code" => markdown.push_str(&format!("```{language}\n{}\n```", source.trim_end())),
+                _ => continue,
+            }
+            markdown.push_str("\n\n");
+        }
+        markdown
+    }
+}
+
+#[async_trait::async_trait]
+impl IntoDocument for NotebookDocument {
+    async fn into_document(self) -> anyhow::Result<Document> {
+        let json = tokio::fs::read_to_string(&self.path).await?;
+        let notebook: Notebook = serde_json::from_str(&json)?;
+        let markdown = notebook.to_markdown();
+        let metadata = file_metadata(&self.path, "application/x-ipynb+json");
+        Ok(markdown_document(
+            markdown,
+            &self.path,
+            metadata,
+            self.format,
+        ))
+    }
+}
+
+#[test]
+fn notebook_cells_are_read_in_order() {
+    let notebook: Notebook = serde_json::from_str(
+        r##"{
+            "cells": [
+                {"cell_type": "markdown", "metadata": {}, "source": ["# Analysis\n", "\n", "Load the data"]},
+                {"cell_type": "code", "metadata": {}, "outputs": [], "source": "import pandas as pd\ndf = pd.read_csv('data.csv')"},
+                {"cell_type": "raw", "metadata": {}, "source": "skipped"}
+            ],
+            "metadata": {"language_info": {"name": "python"}},
+            "nbformat": 4,
+            "nbformat_minor": 5
+        }"##,
+    )
+    .unwrap();
+    assert_eq!(
+        notebook.to_markdown(),
+        "# Analysis\n\nLoad the data\n\n```python\nimport pandas as pd\ndf = pd.read_csv('data.csv')\n```\n\n"
+    );
+
+    let document = markdown_document(
+        notebook.to_markdown(),
+        std::path::Path::new("analysis.ipynb"),
+        Default::default(),
+        ArticleFormat::Markdown,
+    );
+    assert_eq!(document.title(), "Analysis");
+    assert_eq!(document.spans()[0].provenance.heading_path, ["Analysis"]);
+
+    let document = markdown_document(
+        notebook.to_markdown(),
+        std::path::Path::new("analysis.ipynb"),
+        Default::default(),
+        ArticleFormat::PlainText,
+    );
+    assert_eq!(document.title(), "Analysis");
+    assert!(document.body().contains("df = pd.read_csv('data.csv')"));
+    assert!(!document.body().contains("```"));
+}
@@ -1,11 +1,14 @@
 use crate::context::document::Document;
 use crate::context::document::IntoDocument;
+use crate::context::document::Provenance;
 use itertools::Itertools;
 use std::fmt::Write;
 use std::path::PathBuf;
 
 use pdf::file::FileOptions;
 
+use super::file_metadata;
+
 /// A pdf document that can be read from the file system.
 #[derive(Debug, Clone)]
 pub struct PdfDocument {
@@ -29,18 +32,28 @@ impl TryFrom<PathBuf> for PdfDocument {
 #[async_trait::async_trait]
 impl IntoDocument for PdfDocument {
     async fn into_document(self) -> anyhow::Result<Document> {
-        let file = FileOptions::cached().open(self.path).unwrap();
+        let file = FileOptions::cached().open(&self.path).unwrap();
         let resolver = file.resolver();
         let mut title = String::new();
         let mut text = String::new();
+        let mut metadata = file_metadata(&self.path, "application/pdf");
 
         if let Some(info) = &file.trailer.info_dict {
             if let Some(pdf_title) = info.title.as_ref().map(|p| p.to_string_lossy()) {
                 title = pdf_title;
             }
+            if let Some(author) = info.author.as_ref().map(|p| p.to_string_lossy()) {
+                metadata.add_author(author);
+            }
         }
 
-        for page in file.pages().flatten() {
+        // Remember which page each span of the text came from
+        let mut pages = Vec::new();
+        for (index, page) in file.pages().enumerate() {
+            let Ok(page) = page else {
+                continue;
+            };
+            let start = text.len();
             if let Ok(flow) = pdf_text::run(&file, &page, &resolver) {
                 for run in flow.runs {
                     for line in run.lines {
@@ -52,8 +65,15 @@ impl IntoDocument for PdfDocument {
                     }
                 }
             }
+            if text.len() > start {
+                pages.push((start..text.len(), Provenance::page(index + 1)));
+            }
         }
 
-        Ok(Document::from_parts(title, text))
+        let mut document = Document::from_parts(title, text).with_metadata(metadata);
+        for (byte_range, provenance) in pages {
+            document.add_span(byte_range, provenance);
+        }
+        Ok(document)
     }
 }
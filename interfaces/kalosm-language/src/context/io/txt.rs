@@ -5,6 +5,8 @@ use tokio::{fs::File, io::AsyncReadExt};
 
 use crate::context::document::{Document, IntoDocument};
 
+use super::file_metadata;
+
 /// A text document that can be read from the file system.
 #[derive(Debug, Clone)]
 pub struct TextDocument {
@@ -34,11 +36,12 @@ impl IntoDocument for TextDocument {
             .to_string_lossy()
             .to_string()
             .to_case(Case::Title);
-        let file = File::open(self.path).await?;
+        let file = File::open(&self.path).await?;
         let mut text = String::new();
         tokio::io::BufReader::new(file)
             .read_to_string(&mut text)
             .await?;
-        Ok(Document::from_parts(title, text))
+        let metadata = file_metadata(&self.path, "text/plain");
+        Ok(Document::from_parts(title, text).with_metadata(metadata))
     }
 }
@@ -0,0 +1,139 @@
+use calamine::{open_workbook, Reader, Xlsx};
+use std::ops::Range;
+use std::path::PathBuf;
+
+use crate::context::document::{Document, IntoDocument, Provenance};
+
+use super::csv::render_table;
+use super::{file_metadata, file_title};
+
+/// An xlsx spreadsheet that can be read from the file system.
+///
+/// Every sheet is read as a table with the first row as the header, and every other row is rendered as a record with one
+/// `header: value` line per column. The sheet name and row number of each record is recorded in the [`Provenance`] of the spans of the document.
+#[derive(Debug, Clone)]
+pub struct XlsxDocument {
+    path: PathBuf,
+}
+
+impl TryFrom<PathBuf> for XlsxDocument {
+    type Error = anyhow::Error;
+
+    fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
+        if !path.is_file() {
+            return Err(anyhow::anyhow!("Path is not a file"));
+        }
+        if path.extension().unwrap() != "xlsx" {
+            return Err(anyhow::anyhow!("Path is not a xlsx file"));
+        }
+        Ok(Self { path })
+    }
+}
+
+impl XlsxDocument {
+    /// Read every sheet of the workbook into the text of the document along with the span of each row.
+    fn read(&self) -> anyhow::Result<(String, Vec<(Range<usize>, Provenance)>)> {
+        let mut workbook: Xlsx<_> = open_workbook(&self.path)?;
+        let mut text = String::new();
+        let mut spans = Vec::new();
+        for sheet in workbook.sheet_names().to_owned() {
+            let range = workbook.worksheet_range(&sheet)?;
+            let mut rows = range
+                .rows()
+                .map(|row| row.iter().map(|cell| cell.to_string()).collect::<Vec<_>>());
+            let Some(headers) = rows.next() else {
+                continue;
+            };
+            let table = render_table(&headers, rows.collect());
+            if table.text.is_empty() {
+                continue;
+            }
+
+            text.push_str(&format!("{sheet}\n\n"));
+            let offset = text.len();
+            text.push_str(&table.text);
+            for (byte_range, row) in table.rows {
+                let provenance = Provenance::heading_path([&sheet]).with_row(row);
+                spans.push((
+                    byte_range.start + offset..byte_range.end + offset,
+                    provenance,
+                ));
+            }
+        }
+        Ok((text, spans))
+    }
+}
+
+#[async_trait::async_trait]
+impl IntoDocument for XlsxDocument {
+    async fn into_document(self) -> anyhow::Result<Document> {
+        let (text, spans) = {
+            let this = self.clone();
+            tokio::task::spawn_blocking(move || this.read()).await??
+        };
+
+        let metadata = file_metadata(
+            &self.path,
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        );
+        let mut document =
+            Document::from_parts(file_title(&self.path), text).with_metadata(metadata);
+        for (byte_range, provenance) in spans {
+            document.add_span(byte_range, provenance);
+        }
+        Ok(document)
+    }
+}
+
+#[tokio::test]
+async fn sheets_are_read_as_tables() {
+    use std::io::Write;
+
+    let files = [
+        (
+            "[Content_Types].xml",
+            r#"<?xml version="1.0" encoding="UTF-8"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/></Types>"#,
+        ),
+        (
+            "_rels/.rels",
+            r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#,
+        ),
+        (
+            "xl/workbook.xml",
+            r#"<?xml version="1.0" encoding="UTF-8"?><workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="Projects" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+        ),
+        (
+            "xl/_rels/workbook.xml.rels",
+            r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/></Relationships>"#,
+        ),
+        (
+            "xl/worksheets/sheet1.xml",
+            r#"<?xml version="1.0" encoding="UTF-8"?><worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData><row r="1"><c r="A1" t="inlineStr"><is><t>name</t></is></c><c r="B1" t="inlineStr"><is><t>language</t></is></c></row><row r="2"><c r="A2" t="inlineStr"><is><t>Kalosm</t></is></c><c r="B2" t="inlineStr"><is><t>Rust</t></is></c></row><row r="3"><c r="A3" t="inlineStr"><is><t>Floneum</t></is></c></row></sheetData></worksheet>"#,
+        ),
+    ];
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("projects.xlsx");
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+    for (name, contents) in files {
+        zip.start_file(name, zip::write::FileOptions::default())
+            .unwrap();
+        zip.write_all(contents.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+
+    let document = XlsxDocument::try_from(path)
+        .unwrap()
+        .into_document()
+        .await
+        .unwrap();
+    assert_eq!(document.title(), "Projects");
+    assert_eq!(
+        document.body(),
+        "Projects\n\nname: Kalosm\nlanguage: Rust\n\nname: Floneum\n\n"
+    );
+    let row = document.body().find("name: Floneum").unwrap();
+    assert_eq!(
+        document.provenance(row..row + 1),
+        [Provenance::heading_path(["Projects"]).with_row(2)]
+    );
+}
@@ -2,6 +2,10 @@
 
 mod document;
 pub use document::*;
+mod fetch;
+pub use fetch::*;
+#[cfg(test)]
+mod fixtures;
 mod io;
 pub use io::*;
 mod page;
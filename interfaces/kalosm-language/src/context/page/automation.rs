@@ -0,0 +1,463 @@
+use headless_chrome::protocol::cdp::types::Method;
+use headless_chrome::protocol::cdp::DOM::NodeId;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::{Node, Tab};
+
+/// How often the state of the page is checked while waiting.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long navigating through the history waits for the page to load.
+const NAVIGATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Counts the resources the page finished loading. The resource timing buffer stops growing once it is full,
+/// so finished resources are counted with an observer instead.
+const COUNT_FINISHED_RESOURCES: &str = r#"(() => {
+    if (window.__kalosmFinishedResources === undefined) {
+        window.__kalosmFinishedResources = performance.getEntriesByType("resource").length;
+        new PerformanceObserver((list) => {
+            window.__kalosmFinishedResources += list.getEntries().length;
+        }).observe({ type: "resource" });
+    }
+    return [document.readyState === "complete", window.__kalosmFinishedResources];
+})()"#;
+
+/// A cookie in a [`Tab`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cookie {
+    /// The name of the cookie.
+    pub name: String,
+    /// The value of the cookie.
+    pub value: String,
+    /// The domain of the cookie. If this is empty when the cookie is set, the domain of the current page is used.
+    #[serde(default)]
+    pub domain: String,
+    /// The path of the cookie. If this is empty when the cookie is set, the path of the current page is used.
+    #[serde(default)]
+    pub path: String,
+    /// The time the cookie expires as seconds since the UNIX epoch, or `None` for a session cookie.
+    #[serde(default)]
+    pub expires: Option<f64>,
+    /// If the cookie is hidden from JavaScript.
+    #[serde(default)]
+    pub http_only: bool,
+    /// If the cookie is only sent over HTTPS.
+    #[serde(default)]
+    pub secure: bool,
+}
+
+impl Cookie {
+    /// Create a new session cookie for the current page.
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            domain: String::new(),
+            path: String::new(),
+            expires: None,
+            http_only: false,
+            secure: false,
+        }
+    }
+}
+
+impl Tab {
+    /// Wait for an element matching the selector to appear, up to a timeout.
+    #[tracing::instrument]
+    pub fn wait_for(&self, selector: &str, timeout: Duration) -> anyhow::Result<Node<'_>> {
+        let element = self
+            .inner
+            .wait_for_element_with_custom_timeout(selector, timeout)?;
+        Ok(element.into())
+    }
+
+    /// Wait until the page is loaded and no network requests finished for the `idle` duration, up to a timeout.
+    ///
+    /// This blocks the current thread while waiting. Call it with [`tokio::task::spawn_blocking`] from async code.
+    #[tracing::instrument]
+    pub fn wait_for_network_idle(&self, idle: Duration, timeout: Duration) -> anyhow::Result<()> {
+        let start = Instant::now();
+        let mut last_requests = None;
+        let mut last_change = Instant::now();
+        loop {
+            let (loaded, requests): (bool, usize) = self.evaluate(COUNT_FINISHED_RESOURCES)?;
+            if !loaded || last_requests != Some(requests) {
+                last_requests = Some(requests);
+                last_change = Instant::now();
+            } else if last_change.elapsed() >= idle {
+                return Ok(());
+            }
+            if start.elapsed() >= timeout {
+                anyhow::bail!("The network was not idle after {timeout:?}");
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Evaluate a JavaScript expression in the page and deserialize the result.
+    pub fn evaluate<R: DeserializeOwned>(&self, expression: &str) -> anyhow::Result<R> {
+        // Objects are returned by reference, so serialize the result in the page
+        let result = self
+            .inner
+            .evaluate(&format!("JSON.stringify({expression})"), false)?;
+        match result.value {
+            Some(serde_json::Value::String(json)) => Ok(serde_json::from_str(&json)?),
+            _ => Ok(serde_json::from_value(serde_json::Value::Null)?),
+        }
+    }
+
+    /// Go back to the previous page in the history of the tab. This blocks the current thread until the page is loaded.
+    #[tracing::instrument]
+    pub fn back(&self) -> anyhow::Result<()> {
+        self.navigate_history(-1)
+    }
+
+    /// Go forward to the next page in the history of the tab. This blocks the current thread until the page is loaded.
+    #[tracing::instrument]
+    pub fn forward(&self) -> anyhow::Result<()> {
+        self.navigate_history(1)
+    }
+
+    fn navigate_history(&self, offset: isize) -> anyhow::Result<()> {
+        let history = self.inner.call_method(GetNavigationHistory {})?;
+        let entry = history
+            .current_index
+            .checked_add_signed(offset)
+            .and_then(|index| history.entries.get(index))
+            .ok_or_else(|| anyhow::anyhow!("There is no page to navigate to in the history"))?;
+        self.inner
+            .call_method(NavigateToHistoryEntry { entry_id: entry.id })?;
+
+        let start = Instant::now();
+        loop {
+            let loaded: bool = self.evaluate(r#"document.readyState === "complete""#)?;
+            if loaded && self.inner.get_url() == entry.url {
+                return Ok(());
+            }
+            if start.elapsed() >= NAVIGATION_TIMEOUT {
+                anyhow::bail!("{} did not load after {NAVIGATION_TIMEOUT:?}", entry.url);
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Reload the current page.
+    #[tracing::instrument]
+    pub fn reload(&self) -> anyhow::Result<()> {
+        self.inner.reload(false, None)?.wait_until_navigated()?;
+        Ok(())
+    }
+
+    /// Scroll the page by a number of pixels. Positive values scroll right and down.
+    #[tracing::instrument]
+    pub fn scroll_by(&self, x: f64, y: f64) -> anyhow::Result<()> {
+        self.inner
+            .evaluate(&format!("window.scrollBy({x}, {y})"), false)?;
+        Ok(())
+    }
+
+    /// Get the cookies of the current page.
+    #[tracing::instrument]
+    pub fn cookies(&self) -> anyhow::Result<Vec<Cookie>> {
+        let cookies = self.inner.call_method(GetCookies {})?.cookies;
+        Ok(cookies
+            .into_iter()
+            .map(|cookie| Cookie {
+                // Session cookies expire at -1
+                expires: cookie.expires.filter(|expires| *expires >= 0.),
+                ..cookie
+            })
+            .collect())
+    }
+
+    /// Set a cookie. Cookies without a domain are set for the current page.
+    #[tracing::instrument(skip(cookie))]
+    pub fn set_cookie(&self, cookie: Cookie) -> anyhow::Result<()> {
+        let url = cookie.domain.is_empty().then(|| self.inner.get_url());
+        let set = self.inner.call_method(SetCookie {
+            url,
+            domain: (!cookie.domain.is_empty()).then_some(cookie.domain),
+            path: (!cookie.path.is_empty()).then_some(cookie.path),
+            name: cookie.name,
+            value: cookie.value,
+            expires: cookie.expires,
+            http_only: cookie.http_only,
+            secure: cookie.secure,
+        })?;
+        if !set.success {
+            anyhow::bail!("The browser rejected the cookie");
+        }
+        Ok(())
+    }
+
+    /// Set the size of the viewport of the tab in CSS pixels.
+    #[tracing::instrument]
+    pub fn set_viewport(&self, width: u32, height: u32) -> anyhow::Result<()> {
+        self.inner.call_method(SetDeviceMetricsOverride {
+            width,
+            height,
+            // A scale factor of 0 keeps the scale of the screen
+            device_scale_factor: 0.,
+            mobile: false,
+        })?;
+        Ok(())
+    }
+
+    /// Print the current page to a PDF. This only works in headless tabs.
+    #[tracing::instrument]
+    pub fn pdf(&self) -> anyhow::Result<Vec<u8>> {
+        self.inner.print_to_pdf(None)
+    }
+
+    /// Download a file into a directory. The download is started by calling `start`, for example by clicking a link in the tab.
+    /// Returns the path of the downloaded file once it is complete, or an error if no download finished before the timeout.
+    /// Downloads are only allowed while this is running, and it blocks the current thread until the download is complete.
+    ///
+    /// # Example
+    /// ```rust, no_run
+    /// use kalosm_language::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// fn main() -> anyhow::Result<()> {
+    ///     let tab = Tab::new(Url::parse("https://www.rust-lang.org/tools/install")?, true)?;
+    ///     let path = tab.download("downloads", Duration::from_secs(60), |tab| {
+    ///         tab.find("a[href$='rustup-init.exe']")?.click()
+    ///     })?;
+    ///     println!("Downloaded {}", path.display());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn download(
+        &self,
+        directory: impl AsRef<Path>,
+        timeout: Duration,
+        start: impl FnOnce(&Self) -> anyhow::Result<()>,
+    ) -> anyhow::Result<PathBuf> {
+        let directory = directory.as_ref();
+        std::fs::create_dir_all(directory)?;
+        let directory = directory.canonicalize()?;
+        let existing = directory_entries(&directory)?;
+        self.inner.call_method(SetDownloadBehavior {
+            behavior: "allow",
+            download_path: Some(directory.to_string_lossy().to_string()),
+        })?;
+
+        let result = self.wait_for_download(&directory, &existing, timeout, start);
+
+        // Go back to the default behavior so later navigation does not download into the directory
+        let reset = self.inner.call_method(SetDownloadBehavior {
+            behavior: "default",
+            download_path: None,
+        });
+        let path = result?;
+        reset?;
+        Ok(path)
+    }
+
+    fn wait_for_download(
+        &self,
+        directory: &Path,
+        existing: &HashSet<PathBuf>,
+        timeout: Duration,
+        start: impl FnOnce(&Self) -> anyhow::Result<()>,
+    ) -> anyhow::Result<PathBuf> {
+        start(self)?;
+
+        let started = Instant::now();
+        loop {
+            // Chrome writes to a .crdownload file and renames it once the download is complete
+            let finished = directory_entries(directory)?.into_iter().find(|path| {
+                !existing.contains(path) && !path.extension().is_some_and(|ext| ext == "crdownload")
+            });
+            if let Some(path) = finished {
+                return Ok(path);
+            }
+            if started.elapsed() >= timeout {
+                anyhow::bail!("No download finished after {timeout:?}");
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+fn directory_entries(directory: &Path) -> anyhow::Result<HashSet<PathBuf>> {
+    let mut entries = HashSet::new();
+    for entry in std::fs::read_dir(directory)? {
+        entries.insert(entry?.path());
+    }
+    Ok(entries)
+}
+
+impl Node<'_> {
+    /// Set the files of a file input element.
+    #[tracing::instrument(skip(paths))]
+    pub fn upload_files(&self, paths: &[impl AsRef<Path>]) -> anyhow::Result<()> {
+        let files = paths
+            .iter()
+            .map(|path| Ok(path.as_ref().canonicalize()?.to_string_lossy().to_string()))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.inner.parent.call_method(SetFileInputFiles {
+            files,
+            node_id: self.inner.node_id,
+        })?;
+        Ok(())
+    }
+}
+
+/// The `Page.getNavigationHistory` DevTools method.
+#[derive(Debug, Serialize)]
+struct GetNavigationHistory {}
+
+impl Method for GetNavigationHistory {
+    const NAME: &'static str = "Page.getNavigationHistory";
+    type ReturnObject = NavigationHistory;
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NavigationHistory {
+    current_index: usize,
+    entries: Vec<NavigationEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NavigationEntry {
+    id: u32,
+    url: String,
+}
+
+/// The `Page.navigateToHistoryEntry` DevTools method.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NavigateToHistoryEntry {
+    entry_id: u32,
+}
+
+impl Method for NavigateToHistoryEntry {
+    const NAME: &'static str = "Page.navigateToHistoryEntry";
+    type ReturnObject = serde_json::Value;
+}
+
+/// The `Network.getCookies` DevTools method.
+#[derive(Debug, Serialize)]
+struct GetCookies {}
+
+impl Method for GetCookies {
+    const NAME: &'static str = "Network.getCookies";
+    type ReturnObject = Cookies;
+}
+
+#[derive(Debug, Deserialize)]
+struct Cookies {
+    cookies: Vec<Cookie>,
+}
+
+/// The `Network.setCookie` DevTools method.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SetCookie {
+    name: String,
+    value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires: Option<f64>,
+    http_only: bool,
+    secure: bool,
+}
+
+impl Method for SetCookie {
+    const NAME: &'static str = "Network.setCookie";
+    type ReturnObject = SetCookieResult;
+}
+
+#[derive(Debug, Deserialize)]
+struct SetCookieResult {
+    #[serde(default = "default_success")]
+    success: bool,
+}
+
+fn default_success() -> bool {
+    true
+}
+
+/// The `Emulation.setDeviceMetricsOverride` DevTools method.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SetDeviceMetricsOverride {
+    width: u32,
+    height: u32,
+    device_scale_factor: f64,
+    mobile: bool,
+}
+
+impl Method for SetDeviceMetricsOverride {
+    const NAME: &'static str = "Emulation.setDeviceMetricsOverride";
+    type ReturnObject = serde_json::Value;
+}
+
+/// The `Page.setDownloadBehavior` DevTools method.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SetDownloadBehavior {
+    behavior: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    download_path: Option<String>,
+}
+
+impl Method for SetDownloadBehavior {
+    const NAME: &'static str = "Page.setDownloadBehavior";
+    type ReturnObject = serde_json::Value;
+}
+
+/// The `DOM.setFileInputFiles` DevTools method.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SetFileInputFiles {
+    files: Vec<String>,
+    node_id: NodeId,
+}
+
+impl Method for SetFileInputFiles {
+    const NAME: &'static str = "DOM.setFileInputFiles";
+    type ReturnObject = serde_json::Value;
+}
+
+#[test]
+fn cookies_are_read_from_devtools() {
+    let cookies: Cookies = serde_json::from_value(serde_json::json!({
+        "cookies": [
+            {"name": "session", "value": "abc", "domain": "example.com", "path": "/", "expires": -1, "size": 10, "httpOnly": true, "secure": true, "session": true, "priority": "Medium"},
+            {"name": "theme", "value": "dark", "domain": ".example.com", "path": "/", "expires": 1767225600.5, "httpOnly": false, "secure": false}
+        ]
+    }))
+    .unwrap();
+    assert_eq!(cookies.cookies[0].name, "session");
+    assert!(cookies.cookies[0].http_only);
+    assert_eq!(cookies.cookies[1].expires, Some(1767225600.5));
+
+    let set = serde_json::to_value(SetCookie {
+        name: "theme".to_string(),
+        value: "light".to_string(),
+        url: Some("https://example.com/".to_string()),
+        domain: None,
+        path: None,
+        expires: None,
+        http_only: false,
+        secure: true,
+    })
+    .unwrap();
+    assert_eq!(
+        set,
+        serde_json::json!({"name": "theme", "value": "light", "url": "https://example.com/", "httpOnly": false, "secure": true})
+    );
+}
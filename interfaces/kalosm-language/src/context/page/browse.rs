@@ -7,7 +7,7 @@ use serde::de::DeserializeOwned;
 use std::sync::Arc;
 use url::Url;
 
-use super::{extract_article, NodeRef};
+use super::{extract_article, extract_article_as, ArticleFormat, NodeRef};
 use crate::context::document::Document;
 
 static BROWSER: Browser = Browser::new();
@@ -127,7 +127,13 @@ impl Tab {
     /// Extract the article from the current page.
     pub fn article(&self) -> anyhow::Result<Document> {
         let html = self.inner.get_content()?;
-        extract_article(&html)
+        extract_article(&html, &self.url())
+    }
+
+    /// Extract the article from the current page with the body in a format.
+    pub fn article_with_format(&self, format: ArticleFormat) -> anyhow::Result<Document> {
+        let html = self.inner.get_content()?;
+        extract_article_as(&html, &self.url(), format)
     }
 
     /// Get the title of the current page.
@@ -166,7 +172,7 @@ impl std::fmt::Debug for Tab {
 /// A node in a [`Tab`]
 #[derive(Debug)]
 pub struct Node<'a> {
-    inner: Element<'a>,
+    pub(crate) inner: Element<'a>,
 }
 
 impl<'a> From<Element<'a>> for Node<'a> {
@@ -202,6 +208,40 @@ impl<'a> Node<'a> {
         Ok(())
     }
 
+    /// Select an option of a select element by the value or text of the option.
+    #[tracing::instrument]
+    pub fn select_option(&self, option: &str) -> Result<(), anyhow::Error> {
+        let selected: bool = self.call_js_fn(
+            r#"
+            function(option) {
+                const options = Array.from(this.options || []);
+                const found = options.find((o) => o.value === option)
+                    || options.find((o) => o.textContent.trim() === option);
+                if (!found) {
+                    return false;
+                }
+                this.value = found.value;
+                this.dispatchEvent(new Event("input", { bubbles: true }));
+                this.dispatchEvent(new Event("change", { bubbles: true }));
+                return true;
+            }
+        "#,
+            vec![serde_json::Value::String(option.to_string())],
+            false,
+        )?;
+        if !selected {
+            anyhow::bail!("No option {option:?} found in the {} element", self.name());
+        }
+        Ok(())
+    }
+
+    /// Scroll the page until the node is visible.
+    #[tracing::instrument]
+    pub fn scroll_into_view(&self) -> Result<(), anyhow::Error> {
+        self.inner.scroll_into_view()?;
+        Ok(())
+    }
+
     /// Get the outer HTML of the node.
     #[tracing::instrument]
     pub fn outer_html(&self) -> Result<String, anyhow::Error> {
@@ -1,9 +1,14 @@
 use crate::context::page::BrowserMode;
 use crate::context::page::Page;
+use crate::context::page::{
+    CrawlOutcome, CrawlSummary, FrontierEntry, FrontierStore, InMemoryFrontier, Sitemap,
+};
+use crate::context::Fetcher;
+use chrono::{DateTime, Utc};
 use core::task::Context;
+use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
 use once_cell::sync::OnceCell;
-use std::collections::HashSet;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::atomic::AtomicBool;
@@ -13,6 +18,8 @@ use std::sync::Arc;
 use std::task::Poll;
 use std::task::Waker;
 use texting_robots::Robot;
+use tokio::sync::Notify;
+use tokio::sync::Semaphore;
 use tokio::time::Duration;
 use tokio::time::Instant;
 use url::Origin;
@@ -157,7 +164,11 @@ impl ActiveLinks {
     }
 
     fn add(&self) {
-        self.active.fetch_add(1, Ordering::SeqCst);
+        self.add_many(1);
+    }
+
+    fn add_many(&self, count: usize) {
+        self.active.fetch_add(count, Ordering::SeqCst);
     }
 
     fn remove(&self) {
@@ -196,11 +207,98 @@ impl std::future::Future for &ActiveLinks {
     }
 }
 
+/// Options that limit a crawl and choose where its progress is stored.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use kalosm_language::prelude::*;
+///
+/// let options = CrawlOptions::new()
+///     .with_max_depth(3)
+///     .with_max_pages_per_domain(100)
+///     .with_max_concurrency(4)
+///     .with_frontier(DiskFrontier::new_at("./crawl").unwrap());
+/// ```
+#[derive(Clone)]
+pub struct CrawlOptions {
+    frontier: Arc<dyn FrontierStore>,
+    max_depth: Option<usize>,
+    max_pages_per_domain: Option<usize>,
+    max_concurrency: Option<usize>,
+    sitemaps: bool,
+    sitemaps_modified_since: Option<DateTime<Utc>>,
+}
+
+impl Default for CrawlOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CrawlOptions {
+    /// Create new options for a crawl without any limits that keeps the frontier in memory.
+    pub fn new() -> Self {
+        Self {
+            frontier: Arc::new(InMemoryFrontier::new()),
+            max_depth: None,
+            max_pages_per_domain: None,
+            max_concurrency: None,
+            sitemaps: false,
+            sitemaps_modified_since: None,
+        }
+    }
+
+    /// Set the store for the URLs the crawler has queued and visited. If the store already contains URLs from an earlier crawl,
+    /// the crawl resumes from where it stopped. (default: [`InMemoryFrontier`])
+    pub fn with_frontier(mut self, frontier: impl FrontierStore) -> Self {
+        self.frontier = Arc::new(frontier);
+        self
+    }
+
+    /// Set the maximum number of links the crawler follows from the start page. (default: unlimited)
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Set the maximum number of pages the crawler visits on each domain. (default: unlimited)
+    pub fn with_max_pages_per_domain(mut self, max_pages: usize) -> Self {
+        self.max_pages_per_domain = Some(max_pages);
+        self
+    }
+
+    /// Set the maximum number of pages the crawler visits at the same time across all domains. (default: unlimited)
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = Some(max_concurrency.max(1));
+        self
+    }
+
+    /// Seed the crawl with the pages listed in the [`Sitemap`]s of each website the crawler visits. Sitemaps are read from the
+    /// robots.txt file of the website, or `/sitemap.xml` if the robots.txt file doesn't list any. (default: false)
+    pub fn with_sitemaps(mut self, sitemaps: bool) -> Self {
+        self.sitemaps = sitemaps;
+        self
+    }
+
+    /// Seed the crawl with the pages listed in the [`Sitemap`]s of each website that were modified after a time. Pages without a
+    /// modification time are always included.
+    ///
+    /// Crawling again with a new frontier and the time of the last crawl only visits the pages that changed since then.
+    pub fn with_sitemaps_modified_since(mut self, modified_since: DateTime<Utc>) -> Self {
+        self.sitemaps = true;
+        self.sitemaps_modified_since = Some(modified_since);
+        self
+    }
+}
+
 pub(crate) struct Crawler<T> {
     active: Arc<ActiveLinks>,
     visit: Arc<T>,
     mode: BrowserMode,
-    queued: Arc<DashMap<url::Origin, DomainQueue<T>>>,
+    options: CrawlOptions,
+    concurrency: Option<Arc<Semaphore>>,
+    queued: Arc<DashMap<url::Origin, DomainQueue>>,
     aborted: Arc<AtomicBool>,
 }
 
@@ -210,6 +308,8 @@ impl<T> Clone for Crawler<T> {
             active: self.active.clone(),
             visit: self.visit.clone(),
             mode: self.mode,
+            options: self.options.clone(),
+            concurrency: self.concurrency.clone(),
             queued: self.queued.clone(),
             aborted: self.aborted.clone(),
         }
@@ -217,10 +317,14 @@ impl<T> Clone for Crawler<T> {
 }
 
 impl<T: CrawlingCallback> Crawler<T> {
-    pub fn new(mode: BrowserMode, visit: T) -> Self {
+    pub fn new(mode: BrowserMode, options: CrawlOptions, visit: T) -> Self {
         Self {
             active: Arc::new(ActiveLinks::new()),
             mode,
+            concurrency: options
+                .max_concurrency
+                .map(|permits| Arc::new(Semaphore::new(permits))),
+            options,
             queued: Default::default(),
             visit: Arc::new(visit),
             aborted: Default::default(),
@@ -239,49 +343,234 @@ impl<T: CrawlingCallback> Crawler<T> {
         self.active.abort();
     }
 
-    pub async fn crawl(&mut self, url: Url) -> anyhow::Result<()> {
+    pub async fn crawl(&mut self, url: Url) -> anyhow::Result<CrawlSummary> {
+        if !self.is_aborted() {
+            // Pick up the URLs an earlier crawl with the same frontier left in the queue
+            for (origin, queued) in self.options.frontier.resume()? {
+                self.active.add_many(queued);
+                self.wake_or_fail(origin).await;
+            }
+
+            if let Some(origin) = self.push(FrontierEntry::new(url, 0))? {
+                self.wake_or_fail(origin).await;
+            }
+
+            self.active.wait().await;
+
+            // Stop the tasks that wait for new URLs on each domain
+            for queue in self.queued.iter() {
+                queue.abort();
+            }
+        }
+
+        self.options.frontier.summary()
+    }
+
+    async fn add_urls(&self, entries: Vec<FrontierEntry>) {
         if self.is_aborted() {
-            return Ok(());
+            return;
         }
 
-        self.add_urls(vec![url]).await?;
+        for entry in entries {
+            let url = entry.url.clone();
+            match self.push(entry) {
+                Ok(Some(origin)) => self.wake_or_fail(origin).await,
+                Ok(None) => {}
+                // One link that can't be added shouldn't drop the rest of the links on the page
+                Err(err) => tracing::error!("Error adding {}: {}", url, err),
+            }
+        }
+    }
 
-        self.active.wait().await;
+    /// Add a URL to the frontier. Returns the origin of the URL if it was added.
+    fn push(&self, mut entry: FrontierEntry) -> anyhow::Result<Option<Origin>> {
+        if !matches!(entry.url.scheme(), "http" | "https") {
+            return Ok(None);
+        }
+        if self
+            .options
+            .max_depth
+            .is_some_and(|max_depth| entry.depth > max_depth)
+        {
+            return Ok(None);
+        }
+        // Strip the fragment and query from the url to avoid duplicates
+        entry.url.set_fragment(None);
+        entry.url.set_query(None);
+
+        let origin = entry.url.origin();
+        if self
+            .queued
+            .get(&origin)
+            .is_some_and(|queue| queue.is_full())
+        {
+            return Ok(None);
+        }
+        if !self.options.frontier.push(entry)? {
+            return Ok(None);
+        }
+        self.active.add();
 
-        Ok(())
+        Ok(Some(origin))
     }
 
-    async fn add_urls(&self, urls: Vec<Url>) -> anyhow::Result<()> {
-        if self.is_aborted() {
-            return Ok(());
+    /// Add the pages listed in the sitemaps of an origin to the frontier.
+    async fn seed_from_sitemaps(&self, origin: &Origin, robot: Option<&Robot>) {
+        for mut sitemap in Sitemap::from_robot(origin, robot) {
+            if let Some(modified_since) = self.options.sitemaps_modified_since {
+                sitemap = sitemap.with_modified_since(modified_since);
+            }
+            let entries = match sitemap.entries().await {
+                Ok(entries) => entries,
+                Err(err) => {
+                    tracing::warn!("Error reading sitemap {}: {}", sitemap.url(), err);
+                    continue;
+                }
+            };
+            for entry in entries {
+                // Sitemaps may only list pages on their own website
+                if entry.url.origin() != *origin {
+                    continue;
+                }
+                if let Err(err) = self.push(FrontierEntry::new(entry.url, 0)) {
+                    tracing::error!("Error adding urls: {}", err);
+                }
+            }
         }
+    }
+
+    /// Wake the queue of an origin. If the queue can't be created, every queued URL of the origin is recorded as failed so the crawl
+    /// doesn't wait for them.
+    async fn wake_or_fail(&self, origin: Origin) {
+        if let Err(err) = self.wake(origin.clone()).await {
+            tracing::warn!(
+                "Failed to start crawling {}: {}",
+                origin.ascii_serialization(),
+                err
+            );
+            self.fail_queued(&origin, &err);
+        }
+    }
 
-        for url in urls {
-            let origin = url.origin();
-            if let Some(mut queue) = self.queued.get_mut(&origin) {
-                queue.push(url);
-                continue;
+    fn fail_queued(&self, origin: &Origin, err: &anyhow::Error) {
+        // Another task may have created the queue for the origin in the meantime
+        if let Some(queue) = self.queued.get(origin) {
+            queue.wake();
+            return;
+        }
+        loop {
+            match self.options.frontier.pop(origin) {
+                Ok(Some(entry)) => {
+                    let outcome = CrawlOutcome::Failed(err.to_string());
+                    if let Err(err) = self.options.frontier.finish(&entry.url, outcome) {
+                        tracing::error!("Error updating the crawl frontier: {}", err);
+                    }
+                    self.active.remove();
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    tracing::error!("Error reading the crawl frontier: {}", err);
+                    break;
+                }
             }
+        }
+    }
+
+    async fn wake(&self, origin: Origin) -> anyhow::Result<()> {
+        if let Some(queue) = self.queued.get(&origin) {
+            queue.wake();
+            return Ok(());
+        }
 
-            let mut queue = DomainQueue::new(origin.clone(), self.clone()).await?;
-            queue.push(url);
-            self.queued.insert(origin, queue);
+        let queue = DomainQueue::new(origin.clone(), self.clone()).await?;
+        match self.queued.entry(origin) {
+            Entry::Occupied(existing) => {
+                queue.abort();
+                existing.get().wake();
+            }
+            Entry::Vacant(slot) => {
+                queue.wake();
+                slot.insert(queue);
+            }
         }
 
         Ok(())
     }
+
+    /// Visit a page and queue the links the callback wants to follow.
+    async fn visit_entry(
+        &self,
+        entry: &FrontierEntry,
+        cooldown: Duration,
+    ) -> anyhow::Result<Visit> {
+        let wait_until = Instant::now() + cooldown;
+        tokio::time::sleep_until(wait_until).await;
+        let _permit = match &self.concurrency {
+            Some(semaphore) => Some(semaphore.acquire().await?),
+            None => None,
+        };
+
+        let page = Page::new_wait_until(entry.url.clone(), self.mode, wait_until)?;
+        // Load the page before visiting it so pages that fail to load are recorded in the summary
+        page.html().await?;
+
+        // A page that redirected is visited under the URL it redirected to, unless the crawler already reached that URL
+        let mut final_url = page.final_url();
+        final_url.set_fragment(None);
+        final_url.set_query(None);
+        let redirected_to = (final_url != entry.url).then_some(final_url);
+        if let Some(target) = &redirected_to {
+            let target = FrontierEntry::new(target.clone(), entry.depth);
+            if !self.options.frontier.claim(target)? {
+                return Ok(Visit {
+                    redirected_to,
+                    feedback: None,
+                });
+            }
+        }
+
+        let mut feedback = self.visit.visit(page.clone()).await;
+
+        if let CrawlFeedback::Continue(filter) = &mut feedback {
+            match page.links().await {
+                Ok(mut new_urls) => {
+                    new_urls.retain(|url| filter.follow_link(url));
+                    let entries = new_urls
+                        .into_iter()
+                        .map(|url| FrontierEntry::new(url, entry.depth + 1))
+                        .collect();
+                    self.add_urls(entries).await;
+                }
+                Err(err) => tracing::error!("Error getting links: {}", err),
+            }
+        }
+
+        if let Some(target) = &redirected_to {
+            if let Err(err) = self.options.frontier.finish(target, CrawlOutcome::Visited) {
+                tracing::error!("Error updating the crawl frontier: {}", err);
+            }
+        }
+
+        Ok(Visit {
+            redirected_to,
+            feedback: Some(feedback),
+        })
+    }
 }
 
-async fn try_get_robot(origin: &Origin) -> anyhow::Result<Option<Robot>> {
+/// What happened when the crawler visited a URL from the frontier.
+struct Visit {
+    /// The URL the page redirected to, if it redirected.
+    redirected_to: Option<Url>,
+    /// The feedback of the callback, or `None` if the page redirected to a URL the crawler already reached another way.
+    feedback: Option<CrawlFeedback>,
+}
+
+pub(crate) async fn try_get_robot(origin: &Origin) -> anyhow::Result<Option<Robot>> {
     let robots_txt_url = origin.ascii_serialization() + "/robots.txt";
     let robots_txt_url = Url::parse(&robots_txt_url)?;
-    let robots_txt_content = match reqwest::get(robots_txt_url.clone()).await {
-        Ok(response) => match response.text().await {
-            Ok(text) => text,
-            Err(_) => {
-                return Ok(None);
-            }
-        },
+    let robots_txt_content = match Fetcher::global().get(robots_txt_url).await {
+        Ok(response) => response.text(),
         Err(_) => {
             return Ok(None);
         }
@@ -291,86 +580,108 @@ async fn try_get_robot(origin: &Origin) -> anyhow::Result<Option<Robot>> {
     Ok(Some(robots_txt))
 }
 
-struct DomainQueue<T> {
-    visited: HashSet<Url>,
-    queue: tokio::sync::mpsc::UnboundedSender<Url>,
-    crawler: Crawler<T>,
+/// A task that visits the URLs in the frontier for one origin.
+struct DomainQueue {
+    wake: Arc<Notify>,
+    full: Arc<AtomicBool>,
     task: tokio::task::JoinHandle<()>,
 }
 
-impl<T: CrawlingCallback> DomainQueue<T> {
-    async fn new(origin: Origin, crawler: Crawler<T>) -> anyhow::Result<Self> {
+impl DomainQueue {
+    async fn new<T: CrawlingCallback>(origin: Origin, crawler: Crawler<T>) -> anyhow::Result<Self> {
         let robots_txt = try_get_robot(&origin).await?;
-        let (queue, mut rx) = tokio::sync::mpsc::unbounded_channel::<Url>();
+        let wake = Arc::new(Notify::new());
+        let full = Arc::new(AtomicBool::new(false));
 
         let pool = get_local_pool();
         let task = {
-            let crawler = crawler.clone();
+            let wake = wake.clone();
+            let full = full.clone();
             pool.spawn_pinned(move || async move {
+                let frontier = crawler.options.frontier.clone();
                 let cooldown = robots_txt
                     .as_ref()
                     .and_then(|r| r.delay)
                     .map(|delay| Duration::from_secs(delay as u64))
                     .unwrap_or(COOLDOWN);
-                while let Some(url) = rx.recv().await {
-                    if let Some(robot) = &robots_txt {
-                        if !robot.allowed(url.as_str()) {
+                if crawler.options.sitemaps {
+                    crawler
+                        .seed_from_sitemaps(&origin, robots_txt.as_ref())
+                        .await;
+                }
+                let mut visited = frontier.visited(&origin).unwrap_or_else(|err| {
+                    tracing::error!("Error reading the crawl frontier: {}", err);
+                    0
+                });
+                loop {
+                    let entry = match frontier.pop(&origin) {
+                        Ok(Some(entry)) => entry,
+                        Ok(None) => {
+                            wake.notified().await;
                             continue;
                         }
-                    }
-                    let mode = crawler.mode;
-                    let wait_until = Instant::now() + cooldown;
-                    let page = Page::new_wait_until(url, mode, wait_until).unwrap();
-
-                    let visit = crawler.visit.visit(page.clone());
-
-                    let feedback = visit.await;
-
-                    match feedback {
-                        CrawlFeedback::Continue(mut filter) => match page.links().await {
-                            Ok(mut new_urls) => {
-                                new_urls.retain(|url| filter.follow_link(url));
-                                if let Err(err) = crawler.add_urls(new_urls).await {
-                                    tracing::error!("Error adding urls: {}", err);
+                        Err(err) => {
+                            tracing::error!("Error reading the crawl frontier: {}", err);
+                            wake.notified().await;
+                            continue;
+                        }
+                    };
+
+                    let mut stop = false;
+                    let outcome = if crawler
+                        .options
+                        .max_pages_per_domain
+                        .is_some_and(|max_pages| visited >= max_pages)
+                    {
+                        full.store(true, Ordering::SeqCst);
+                        CrawlOutcome::Skipped
+                    } else if robots_txt
+                        .as_ref()
+                        .is_some_and(|robot| !robot.allowed(entry.url.as_str()))
+                    {
+                        CrawlOutcome::SkippedByRobots
+                    } else {
+                        match crawler.visit_entry(&entry, cooldown).await {
+                            Ok(visit) => {
+                                if let Some(feedback) = &visit.feedback {
+                                    visited += 1;
+                                    stop = matches!(feedback, CrawlFeedback::Stop);
+                                }
+                                match visit.redirected_to {
+                                    Some(target) => CrawlOutcome::Redirected(target),
+                                    None => CrawlOutcome::Visited,
                                 }
                             }
-                            Err(err) => tracing::error!("Error getting links: {}", err),
-                        },
-                        CrawlFeedback::Stop => {
-                            crawler.abort();
-                            return;
+                            Err(err) => CrawlOutcome::Failed(err.to_string()),
                         }
+                    };
+                    if let Err(err) = frontier.finish(&entry.url, outcome) {
+                        tracing::error!("Error updating the crawl frontier: {}", err);
+                    }
+
+                    if stop {
+                        crawler.abort();
+                        return;
                     }
                     crawler.active.remove();
                 }
             })
         };
 
-        Ok(Self {
-            task,
-            queue,
-            visited: HashSet::new(),
-            crawler,
-        })
+        Ok(Self { wake, full, task })
     }
 
     fn abort(&self) {
         self.task.abort();
     }
 
-    fn push(&mut self, mut url: Url) {
-        // Strip the fragment and query from the url to avoid duplicates
-        url.set_fragment(None);
-        url.set_query(None);
-        if self.visited.contains(&url) {
-            return;
-        }
-
-        self.crawler.active.add();
-
-        self.visited.insert(url.clone());
+    fn wake(&self) {
+        self.wake.notify_one();
+    }
 
-        let _ = self.queue.send(url);
+    /// Check if the queue has visited the maximum number of pages for the domain.
+    fn is_full(&self) -> bool {
+        self.full.load(Ordering::SeqCst)
     }
 }
 
@@ -386,3 +697,131 @@ fn get_local_pool() -> tokio_util::task::LocalPoolHandle {
         })
         .clone()
 }
+
+#[cfg(test)]
+fn recording_callback(
+    visited: Arc<std::sync::Mutex<Vec<Url>>>,
+    feedback: fn() -> CrawlFeedback,
+) -> impl CrawlingCallback {
+    move |page: Page| {
+        let visited = visited.clone();
+        Box::pin(async move {
+            visited.lock().unwrap().push(page.final_url());
+            feedback()
+        }) as Pin<Box<dyn Future<Output = CrawlFeedback>>>
+    }
+}
+
+#[cfg(test)]
+fn sorted_paths<'a>(urls: impl IntoIterator<Item = &'a Url>) -> Vec<String> {
+    let mut paths: Vec<_> = urls.into_iter().map(|url| url.path().to_string()).collect();
+    paths.sort();
+    paths
+}
+
+#[tokio::test]
+async fn crawl_follows_links_on_the_domain_and_respects_robots() {
+    use crate::context::fixtures::FixtureSite;
+
+    let site = FixtureSite::blog().serve().await;
+    let visited = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let summary = Page::crawl_with_options(
+        site.url("/"),
+        BrowserMode::Static,
+        CrawlOptions::new(),
+        recording_callback(visited.clone(), || {
+            CrawlFeedback::follow_filtered(|url: &Url| url.host_str() == Some("127.0.0.1"))
+        }),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        sorted_paths(&summary.visited),
+        [
+            "/",
+            "/busy",
+            "/posts/first",
+            "/posts/fourth",
+            "/posts/second",
+            "/posts/third"
+        ]
+    );
+    // Pages that redirect are visited under the URL they redirected to, and links on them are resolved against that URL
+    assert_eq!(
+        summary.redirected,
+        [(site.url("/old-post"), site.url("/posts/third"))]
+    );
+    assert_eq!(
+        sorted_paths(visited.lock().unwrap().iter()),
+        [
+            "/",
+            "/busy",
+            "/posts/first",
+            "/posts/fourth",
+            "/posts/second",
+            "/posts/third"
+        ]
+    );
+    assert_eq!(
+        sorted_paths(&summary.skipped_by_robots),
+        ["/private/drafts"]
+    );
+    assert_eq!(summary.failed.len(), 1);
+    assert_eq!(summary.failed[0].0.path(), "/missing");
+    assert!(summary.failed[0].1.contains("404"));
+    assert!(summary.skipped.is_empty());
+    assert_eq!(summary.pending, 0);
+
+    // Disallowed pages and pages on other hosts are never requested
+    assert_eq!(site.requests("/private/drafts"), 0);
+    assert_eq!(site.requests("/posts/mirror"), 0);
+    // Rate limited pages are retried, and every page is only visited once
+    assert_eq!(site.requests("/busy"), 2);
+    assert_eq!(site.requests("/posts/first"), 1);
+    assert_eq!(site.requests("/posts/second"), 1);
+    // Links to the page a redirect led to don't visit it again
+    assert_eq!(site.requests("/old-post"), 1);
+    assert_eq!(site.requests("/posts/third"), 1);
+}
+
+#[test]
+fn follow_domain_only_follows_links_on_the_domain() {
+    let mut feedback = CrawlFeedback::follow_domain("localhost");
+    assert!(feedback.should_follow(&Url::parse("http://localhost:8080/posts/mirror").unwrap()));
+    assert!(!feedback.should_follow(&Url::parse("http://127.0.0.1:8080/posts/mirror").unwrap()));
+    assert!(!feedback.should_follow(&Url::parse("https://example.com/").unwrap()));
+}
+
+#[tokio::test]
+async fn crawl_is_seeded_from_sitemaps_and_limited() {
+    use crate::context::fixtures::FixtureSite;
+
+    let site = FixtureSite::blog().serve().await;
+    let summary = Page::crawl_with_options(
+        site.url("/"),
+        BrowserMode::Static,
+        CrawlOptions::new().with_sitemaps(true),
+        recording_callback(Default::default(), CrawlFeedback::follow_none),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        sorted_paths(&summary.visited),
+        ["/", "/posts/first", "/posts/from-sitemap"]
+    );
+    assert_eq!(site.requests("/sitemap.xml"), 1);
+
+    // Only the start page is visited when no links may be followed from it
+    let site = FixtureSite::blog().serve().await;
+    let summary = Page::crawl_with_options(
+        site.url("/"),
+        BrowserMode::Static,
+        CrawlOptions::new().with_max_depth(0),
+        recording_callback(Default::default(), CrawlFeedback::follow_all),
+    )
+    .await
+    .unwrap();
+    assert_eq!(sorted_paths(&summary.visited), ["/"]);
+    assert_eq!(site.requests("/posts/first"), 0);
+}
@@ -0,0 +1,671 @@
+use heed::types::{Bytes, SerdeBincode, Str};
+use heed::{Database, EnvOpenOptions, RwTxn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use url::{Origin, Url};
+
+/// A URL waiting to be visited by the crawler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrontierEntry {
+    /// The URL to visit.
+    pub url: Url,
+    /// The number of links the crawler followed from the start of the crawl to find the URL.
+    pub depth: usize,
+}
+
+impl FrontierEntry {
+    /// Create a new entry for a URL at a depth.
+    pub fn new(url: Url, depth: usize) -> Self {
+        Self { url, depth }
+    }
+}
+
+/// What happened to a URL the crawler took from the frontier.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CrawlOutcome {
+    /// The page was visited.
+    Visited,
+    /// The robots.txt file of the site does not allow crawling the page.
+    SkippedByRobots,
+    /// The page was not visited because the crawler already visited the maximum number of pages on the domain.
+    Skipped,
+    /// The page could not be loaded.
+    Failed(String),
+    /// The page redirected to another URL. The outcome of the page it redirected to is recorded under that URL.
+    Redirected(Url),
+}
+
+/// A summary of the URLs in the frontier of a crawl.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CrawlSummary {
+    /// The pages that were visited.
+    pub visited: Vec<Url>,
+    /// The pages the robots.txt file of the site did not allow crawling.
+    pub skipped_by_robots: Vec<Url>,
+    /// The pages that were not visited because the crawl limits were reached.
+    pub skipped: Vec<Url>,
+    /// The pages that could not be loaded with the error that occurred.
+    pub failed: Vec<(Url, String)>,
+    /// The pages that redirected to another URL with the URL they redirected to.
+    pub redirected: Vec<(Url, Url)>,
+    /// The number of URLs that are still queued. A crawl that was stopped early can be resumed with the same frontier to visit them.
+    pub pending: usize,
+}
+
+impl CrawlSummary {
+    fn add(&mut self, url: Url, state: UrlState) {
+        match state {
+            UrlState::Queued | UrlState::InProgress => self.pending += 1,
+            UrlState::Finished(CrawlOutcome::Visited) => self.visited.push(url),
+            UrlState::Finished(CrawlOutcome::SkippedByRobots) => self.skipped_by_robots.push(url),
+            UrlState::Finished(CrawlOutcome::Skipped) => self.skipped.push(url),
+            UrlState::Finished(CrawlOutcome::Failed(error)) => self.failed.push((url, error)),
+            UrlState::Finished(CrawlOutcome::Redirected(target)) => {
+                self.redirected.push((url, target))
+            }
+        }
+    }
+}
+
+/// A store for the frontier of a crawl: the URLs that are waiting to be visited, and the outcome of the URLs that were already visited.
+///
+/// Every URL is only added to the frontier once. The crawler takes URLs from the queue of each origin with [`FrontierStore::pop`] and
+/// records the outcome with [`FrontierStore::finish`]. If the crawl stops before a URL is finished, [`FrontierStore::resume`] queues
+/// it again the next time the frontier is crawled.
+///
+/// Kalosm includes an [`InMemoryFrontier`] and a [`DiskFrontier`] that keeps the progress of a crawl on disk.
+pub trait FrontierStore: Send + Sync + 'static {
+    /// Add a URL to the queue of its origin. Returns `false` if the URL was already added to the frontier.
+    fn push(&self, entry: FrontierEntry) -> anyhow::Result<bool>;
+
+    /// Add a URL the crawler reached without taking it from the queue, like the page a queued URL redirected to. The URL is
+    /// marked as in progress instead of being queued. Returns `false` if the URL was already added to the frontier.
+    fn claim(&self, entry: FrontierEntry) -> anyhow::Result<bool>;
+
+    /// Take the next URL from the queue of an origin.
+    fn pop(&self, origin: &Origin) -> anyhow::Result<Option<FrontierEntry>>;
+
+    /// Record the outcome of a URL that was taken from the frontier.
+    fn finish(&self, url: &Url, outcome: CrawlOutcome) -> anyhow::Result<()>;
+
+    /// Queue every URL that was taken from the frontier but never finished again, and return the number of queued URLs for each origin.
+    fn resume(&self) -> anyhow::Result<Vec<(Origin, usize)>>;
+
+    /// Get the number of pages that were visited on an origin.
+    fn visited(&self, origin: &Origin) -> anyhow::Result<usize>;
+
+    /// Summarize the outcome of every URL in the frontier.
+    fn summary(&self) -> anyhow::Result<CrawlSummary>;
+}
+
+/// The state of a URL in the frontier.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+enum UrlState {
+    Queued,
+    InProgress,
+    Finished(CrawlOutcome),
+}
+
+fn origin_key(origin: &Origin) -> String {
+    origin.ascii_serialization()
+}
+
+fn parse_origin(key: &str) -> Option<Origin> {
+    Url::parse(key).ok().map(|url| url.origin())
+}
+
+/// A [`FrontierStore`] that keeps the frontier in memory.
+///
+/// The frontier can be cloned to share it between crawls. A crawl that was stopped early can be resumed by crawling again with a clone of the same frontier.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryFrontier {
+    inner: Arc<Mutex<InMemoryFrontierInner>>,
+}
+
+#[derive(Debug, Default)]
+struct InMemoryFrontierInner {
+    urls: HashMap<Url, (usize, UrlState)>,
+    queues: HashMap<String, VecDeque<Url>>,
+    visited: HashMap<String, usize>,
+}
+
+impl InMemoryFrontier {
+    /// Create a new empty frontier.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl FrontierStore for InMemoryFrontier {
+    fn push(&self, entry: FrontierEntry) -> anyhow::Result<bool> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.urls.contains_key(&entry.url) {
+            return Ok(false);
+        }
+        inner
+            .queues
+            .entry(origin_key(&entry.url.origin()))
+            .or_default()
+            .push_back(entry.url.clone());
+        inner
+            .urls
+            .insert(entry.url, (entry.depth, UrlState::Queued));
+        Ok(true)
+    }
+
+    fn claim(&self, entry: FrontierEntry) -> anyhow::Result<bool> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.urls.contains_key(&entry.url) {
+            return Ok(false);
+        }
+        inner
+            .urls
+            .insert(entry.url, (entry.depth, UrlState::InProgress));
+        Ok(true)
+    }
+
+    fn pop(&self, origin: &Origin) -> anyhow::Result<Option<FrontierEntry>> {
+        let mut inner = self.inner.lock().unwrap();
+        let Some(url) = inner
+            .queues
+            .get_mut(&origin_key(origin))
+            .and_then(|queue| queue.pop_front())
+        else {
+            return Ok(None);
+        };
+        let Some((depth, state)) = inner.urls.get_mut(&url) else {
+            return Ok(None);
+        };
+        *state = UrlState::InProgress;
+        Ok(Some(FrontierEntry::new(url, *depth)))
+    }
+
+    fn finish(&self, url: &Url, outcome: CrawlOutcome) -> anyhow::Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        if outcome == CrawlOutcome::Visited {
+            *inner.visited.entry(origin_key(&url.origin())).or_default() += 1;
+        }
+        if let Some((_, state)) = inner.urls.get_mut(url) {
+            *state = UrlState::Finished(outcome);
+        }
+        Ok(())
+    }
+
+    fn resume(&self) -> anyhow::Result<Vec<(Origin, usize)>> {
+        let mut inner = self.inner.lock().unwrap();
+        let mut unfinished = Vec::new();
+        for (url, (_, state)) in &mut inner.urls {
+            if *state == UrlState::InProgress {
+                *state = UrlState::Queued;
+                unfinished.push(url.clone());
+            }
+        }
+        unfinished.sort();
+        for url in unfinished {
+            inner
+                .queues
+                .entry(origin_key(&url.origin()))
+                .or_default()
+                .push_back(url);
+        }
+        Ok(inner
+            .queues
+            .iter()
+            .filter(|(_, queue)| !queue.is_empty())
+            .filter_map(|(origin, queue)| Some((parse_origin(origin)?, queue.len())))
+            .collect())
+    }
+
+    fn visited(&self, origin: &Origin) -> anyhow::Result<usize> {
+        let inner = self.inner.lock().unwrap();
+        Ok(inner
+            .visited
+            .get(&origin_key(origin))
+            .copied()
+            .unwrap_or_default())
+    }
+
+    fn summary(&self) -> anyhow::Result<CrawlSummary> {
+        let inner = self.inner.lock().unwrap();
+        let mut urls = inner.urls.iter().collect::<Vec<_>>();
+        urls.sort_by(|(first, _), (second, _)| first.cmp(second));
+        let mut summary = CrawlSummary::default();
+        for (url, (_, state)) in urls {
+            summary.add(url.clone(), state.clone());
+        }
+        Ok(summary)
+    }
+}
+
+/// A URL in the [`DiskFrontier`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UrlRecord {
+    url: String,
+    depth: usize,
+    state: UrlState,
+}
+
+/// The key of a URL in the [`DiskFrontier`]. LMDB rejects keys longer than 511 bytes, so URLs are keyed by their hash and the
+/// URL itself is stored in the [`UrlRecord`].
+fn url_key(url: &str) -> [u8; 32] {
+    Sha256::digest(url.as_bytes()).into()
+}
+
+/// The number of queued and visited URLs of an origin in the [`DiskFrontier`].
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct OriginStats {
+    queued: usize,
+    visited: usize,
+}
+
+const NEXT_SEQUENCE_KEY: &str = "next-sequence";
+
+/// A [`FrontierStore`] that keeps the frontier in a database on disk.
+///
+/// Every change to the frontier is committed immediately, so a crawl that is stopped, or a process that exits in the middle of a
+/// crawl, can be resumed by crawling again with a frontier at the same path. URLs the crawler was visiting when the crawl stopped
+/// are visited again.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use kalosm_language::prelude::*;
+/// use std::future::Future;
+/// use std::pin::Pin;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let options = CrawlOptions::new()
+///         .with_frontier(DiskFrontier::new_at("./crawl").unwrap())
+///         .with_max_pages_per_domain(100);
+///     let summary = Page::crawl_with_options(
+///         Url::parse("https://floneum.com/kalosm/docs").unwrap(),
+///         BrowserMode::Static,
+///         options,
+///         |page: Page| {
+///             Box::pin(async move {
+///                 println!("Visited {}", page.url());
+///                 CrawlFeedback::follow_domain("floneum.com")
+///             }) as Pin<Box<dyn Future<Output = CrawlFeedback>>>
+///         },
+///     )
+///     .await
+///     .unwrap();
+///     println!("Visited {} pages", summary.visited.len());
+/// }
+/// ```
+#[derive(Clone)]
+pub struct DiskFrontier {
+    env: heed::Env,
+    // The URLs in the frontier, keyed by the hash of the URL
+    urls: Database<Bytes, SerdeBincode<UrlRecord>>,
+    // The queue of each origin, keyed by the origin followed by a zero byte and a big endian sequence number
+    queue: Database<Bytes, Str>,
+    origins: Database<Str, SerdeBincode<OriginStats>>,
+    metadata: Database<Str, SerdeBincode<u64>>,
+}
+
+impl DiskFrontier {
+    /// Create a new frontier at the given path. If a frontier already exists at the path, it will be reused.
+    pub fn new_at(path: impl AsRef<std::path::Path>) -> anyhow::Result<Self> {
+        const ONE_GIB: usize = 1024 * 1024 * 1024;
+
+        std::fs::create_dir_all(&path)?;
+
+        let env = unsafe {
+            EnvOpenOptions::new()
+                .map_size(ONE_GIB)
+                .max_dbs(4)
+                .open(path)
+        }?;
+
+        let mut wtxn = env.write_txn()?;
+        let urls = env.create_database(&mut wtxn, Some("frontier-url-records"))?;
+        let queue = env.create_database(&mut wtxn, Some("frontier-queue"))?;
+        let origins = env.create_database(&mut wtxn, Some("frontier-origins"))?;
+        let metadata = env.create_database(&mut wtxn, Some("frontier-metadata"))?;
+        wtxn.commit()?;
+
+        Ok(Self {
+            env,
+            urls,
+            queue,
+            origins,
+            metadata,
+        })
+    }
+
+    fn enqueue(&self, wtxn: &mut RwTxn, url: &Url) -> anyhow::Result<()> {
+        let origin = origin_key(&url.origin());
+        let sequence = self
+            .metadata
+            .get(wtxn, NEXT_SEQUENCE_KEY)?
+            .unwrap_or_default();
+        self.metadata
+            .put(wtxn, NEXT_SEQUENCE_KEY, &(sequence + 1))?;
+        self.queue
+            .put(wtxn, &queue_key(&origin, sequence), url.as_str())?;
+
+        let mut stats = self.origins.get(wtxn, &origin)?.unwrap_or_default();
+        stats.queued += 1;
+        self.origins.put(wtxn, &origin, &stats)?;
+        Ok(())
+    }
+}
+
+fn queue_key(origin: &str, sequence: u64) -> Vec<u8> {
+    let mut key = queue_prefix(origin);
+    key.extend_from_slice(&sequence.to_be_bytes());
+    key
+}
+
+fn queue_prefix(origin: &str) -> Vec<u8> {
+    let mut prefix = origin.as_bytes().to_vec();
+    prefix.push(0);
+    prefix
+}
+
+impl FrontierStore for DiskFrontier {
+    fn push(&self, entry: FrontierEntry) -> anyhow::Result<bool> {
+        let mut wtxn = self.env.write_txn()?;
+        let key = url_key(entry.url.as_str());
+        if self.urls.get(&wtxn, &key)?.is_some() {
+            return Ok(false);
+        }
+        let record = UrlRecord {
+            url: entry.url.to_string(),
+            depth: entry.depth,
+            state: UrlState::Queued,
+        };
+        self.urls.put(&mut wtxn, &key, &record)?;
+        self.enqueue(&mut wtxn, &entry.url)?;
+        wtxn.commit()?;
+        Ok(true)
+    }
+
+    fn claim(&self, entry: FrontierEntry) -> anyhow::Result<bool> {
+        let mut wtxn = self.env.write_txn()?;
+        let key = url_key(entry.url.as_str());
+        if self.urls.get(&wtxn, &key)?.is_some() {
+            return Ok(false);
+        }
+        let record = UrlRecord {
+            url: entry.url.to_string(),
+            depth: entry.depth,
+            state: UrlState::InProgress,
+        };
+        self.urls.put(&mut wtxn, &key, &record)?;
+        wtxn.commit()?;
+        Ok(true)
+    }
+
+    fn pop(&self, origin: &Origin) -> anyhow::Result<Option<FrontierEntry>> {
+        let origin = origin_key(origin);
+        let mut wtxn = self.env.write_txn()?;
+        let next = self
+            .queue
+            .prefix_iter(&wtxn, &queue_prefix(&origin))?
+            .next()
+            .transpose()?
+            .map(|(key, url)| (key.to_vec(), url.to_string()));
+        let Some((key, url)) = next else {
+            return Ok(None);
+        };
+        self.queue.delete(&mut wtxn, &key)?;
+
+        let mut stats = self.origins.get(&wtxn, &origin)?.unwrap_or_default();
+        stats.queued = stats.queued.saturating_sub(1);
+        self.origins.put(&mut wtxn, &origin, &stats)?;
+
+        let key = url_key(&url);
+        let Some(mut record) = self.urls.get(&wtxn, &key)? else {
+            wtxn.commit()?;
+            return Ok(None);
+        };
+        record.state = UrlState::InProgress;
+        self.urls.put(&mut wtxn, &key, &record)?;
+        wtxn.commit()?;
+
+        Ok(Some(FrontierEntry::new(Url::parse(&url)?, record.depth)))
+    }
+
+    fn finish(&self, url: &Url, outcome: CrawlOutcome) -> anyhow::Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+        if outcome == CrawlOutcome::Visited {
+            let origin = origin_key(&url.origin());
+            let mut stats = self.origins.get(&wtxn, &origin)?.unwrap_or_default();
+            stats.visited += 1;
+            self.origins.put(&mut wtxn, &origin, &stats)?;
+        }
+        let key = url_key(url.as_str());
+        if let Some(mut record) = self.urls.get(&wtxn, &key)? {
+            record.state = UrlState::Finished(outcome);
+            self.urls.put(&mut wtxn, &key, &record)?;
+        }
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    fn resume(&self) -> anyhow::Result<Vec<(Origin, usize)>> {
+        let mut wtxn = self.env.write_txn()?;
+        let mut unfinished = Vec::new();
+        for item in self.urls.iter(&wtxn)? {
+            let (key, record) = item?;
+            if record.state == UrlState::InProgress {
+                unfinished.push((key.to_vec(), record));
+            }
+        }
+        // Queue the URLs in a stable order. The keys are hashes, so they are not in the order of the URLs
+        unfinished.sort_by(|(_, first), (_, second)| first.url.cmp(&second.url));
+        for (key, mut record) in unfinished {
+            record.state = UrlState::Queued;
+            self.urls.put(&mut wtxn, &key, &record)?;
+            self.enqueue(&mut wtxn, &Url::parse(&record.url)?)?;
+        }
+
+        let mut queued = Vec::new();
+        for item in self.origins.iter(&wtxn)? {
+            let (origin, stats) = item?;
+            if stats.queued > 0 {
+                queued.extend(parse_origin(origin).map(|origin| (origin, stats.queued)));
+            }
+        }
+        wtxn.commit()?;
+
+        Ok(queued)
+    }
+
+    fn visited(&self, origin: &Origin) -> anyhow::Result<usize> {
+        let rtxn = self.env.read_txn()?;
+        Ok(self
+            .origins
+            .get(&rtxn, &origin_key(origin))?
+            .unwrap_or_default()
+            .visited)
+    }
+
+    fn summary(&self) -> anyhow::Result<CrawlSummary> {
+        let rtxn = self.env.read_txn()?;
+        let mut records = Vec::new();
+        for item in self.urls.iter(&rtxn)? {
+            let (_, record) = item?;
+            records.push((Url::parse(&record.url)?, record.state));
+        }
+        records.sort_by(|(first, _), (second, _)| first.cmp(second));
+        let mut summary = CrawlSummary::default();
+        for (url, state) in records {
+            summary.add(url, state);
+        }
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+fn check_frontier(frontier: &impl FrontierStore) {
+    let url = |path: &str| Url::parse(&format!("https://example.com{path}")).unwrap();
+    let origin = url("/").origin();
+
+    assert!(frontier.push(FrontierEntry::new(url("/"), 0)).unwrap());
+    assert!(frontier.push(FrontierEntry::new(url("/a"), 1)).unwrap());
+    assert!(frontier.push(FrontierEntry::new(url("/b"), 1)).unwrap());
+    assert!(!frontier.push(FrontierEntry::new(url("/a"), 2)).unwrap());
+    assert!(frontier
+        .push(FrontierEntry::new(
+            Url::parse("https://other.com").unwrap(),
+            1
+        ))
+        .unwrap());
+
+    assert_eq!(
+        frontier.pop(&origin).unwrap(),
+        Some(FrontierEntry::new(url("/"), 0))
+    );
+    frontier.finish(&url("/"), CrawlOutcome::Visited).unwrap();
+    assert_eq!(
+        frontier.pop(&origin).unwrap(),
+        Some(FrontierEntry::new(url("/a"), 1))
+    );
+    assert_eq!(frontier.visited(&origin).unwrap(), 1);
+}
+
+#[test]
+fn in_memory_frontier_resumes_unfinished_urls() {
+    let frontier = InMemoryFrontier::new();
+    check_frontier(&frontier);
+
+    // The crawl stopped while visiting /a, so it is queued again
+    let mut queued = frontier.resume().unwrap();
+    queued.sort_by_key(|(origin, _)| origin.ascii_serialization());
+    assert_eq!(
+        queued,
+        [
+            (Url::parse("https://example.com").unwrap().origin(), 2),
+            (Url::parse("https://other.com").unwrap().origin(), 1)
+        ]
+    );
+    let origin = Url::parse("https://example.com").unwrap().origin();
+    let next = frontier.pop(&origin).unwrap().unwrap();
+    assert_eq!(next.url.path(), "/b");
+    frontier
+        .finish(&next.url, CrawlOutcome::SkippedByRobots)
+        .unwrap();
+    let next = frontier.pop(&origin).unwrap().unwrap();
+    assert_eq!(
+        next,
+        FrontierEntry::new(Url::parse("https://example.com/a").unwrap(), 1)
+    );
+    frontier
+        .finish(&next.url, CrawlOutcome::Failed("timed out".to_string()))
+        .unwrap();
+    assert_eq!(frontier.pop(&origin).unwrap(), None);
+
+    let summary = frontier.summary().unwrap();
+    assert_eq!(
+        summary.visited,
+        [Url::parse("https://example.com/").unwrap()]
+    );
+    assert_eq!(
+        summary.failed,
+        [(
+            Url::parse("https://example.com/a").unwrap(),
+            "timed out".to_string()
+        )]
+    );
+    assert_eq!(
+        summary.skipped_by_robots,
+        [Url::parse("https://example.com/b").unwrap()]
+    );
+    assert_eq!(summary.pending, 1);
+}
+
+#[test]
+fn disk_frontier_persists_across_reopens() {
+    let dir = tempfile::tempdir().unwrap();
+    {
+        let frontier = DiskFrontier::new_at(dir.path()).unwrap();
+        check_frontier(&frontier);
+    }
+
+    let frontier = DiskFrontier::new_at(dir.path()).unwrap();
+    let origin = Url::parse("https://example.com").unwrap().origin();
+    assert_eq!(frontier.visited(&origin).unwrap(), 1);
+    assert!(frontier.resume().unwrap().contains(&(origin.clone(), 2)));
+    let paths = std::iter::from_fn(|| frontier.pop(&origin).unwrap())
+        .map(|entry| entry.url.path().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(paths, ["/b", "/a"]);
+
+    let summary = frontier.summary().unwrap();
+    assert_eq!(
+        summary.visited,
+        [Url::parse("https://example.com/").unwrap()]
+    );
+    assert_eq!(summary.pending, 3);
+}
+
+#[cfg(test)]
+fn check_claim(frontier: &impl FrontierStore) {
+    let url = |path: &str| Url::parse(&format!("https://example.com{path}")).unwrap();
+    let origin = url("/").origin();
+
+    assert!(frontier.push(FrontierEntry::new(url("/old"), 0)).unwrap());
+    assert!(frontier
+        .push(FrontierEntry::new(url("/queued"), 0))
+        .unwrap());
+    let old = frontier.pop(&origin).unwrap().unwrap();
+
+    // The page /old redirected to is claimed instead of queued, so links to it are never visited again
+    assert!(frontier.claim(FrontierEntry::new(url("/new"), 0)).unwrap());
+    assert!(!frontier
+        .claim(FrontierEntry::new(url("/queued"), 0))
+        .unwrap());
+    assert!(!frontier.push(FrontierEntry::new(url("/new"), 1)).unwrap());
+    frontier
+        .finish(&old.url, CrawlOutcome::Redirected(url("/new")))
+        .unwrap();
+    frontier
+        .finish(&url("/new"), CrawlOutcome::Visited)
+        .unwrap();
+    assert_eq!(
+        frontier.pop(&origin).unwrap(),
+        Some(FrontierEntry::new(url("/queued"), 0))
+    );
+    assert_eq!(frontier.pop(&origin).unwrap(), None);
+
+    let summary = frontier.summary().unwrap();
+    assert_eq!(summary.visited, [url("/new")]);
+    assert_eq!(summary.redirected, [(url("/old"), url("/new"))]);
+    assert_eq!(frontier.visited(&origin).unwrap(), 1);
+}
+
+#[test]
+fn claimed_urls_are_never_queued() {
+    check_claim(&InMemoryFrontier::new());
+    let dir = tempfile::tempdir().unwrap();
+    check_claim(&DiskFrontier::new_at(dir.path()).unwrap());
+}
+
+#[test]
+fn long_urls_are_stored_in_every_frontier() {
+    let url = Url::parse(&format!("https://example.com/search?q={}", "a".repeat(600))).unwrap();
+    let check = |frontier: &dyn FrontierStore| {
+        assert!(frontier.push(FrontierEntry::new(url.clone(), 0)).unwrap());
+        assert!(!frontier.push(FrontierEntry::new(url.clone(), 1)).unwrap());
+        let claimed = Url::parse(&format!("{url}&page=2")).unwrap();
+        assert!(frontier
+            .claim(FrontierEntry::new(claimed.clone(), 0))
+            .unwrap());
+        assert_eq!(
+            frontier.pop(&url.origin()).unwrap(),
+            Some(FrontierEntry::new(url.clone(), 0))
+        );
+        frontier.finish(&url, CrawlOutcome::Visited).unwrap();
+        frontier.finish(&claimed, CrawlOutcome::Visited).unwrap();
+        assert_eq!(frontier.summary().unwrap().visited, [url.clone(), claimed]);
+    };
+    check(&InMemoryFrontier::new());
+    let dir = tempfile::tempdir().unwrap();
+    check(&DiskFrontier::new_at(dir.path()).unwrap());
+}
@@ -1,24 +1,61 @@
 use super::document::Document;
+use super::Fetcher;
+use crate::search::HtmlSimplifier;
+use scraper::Html;
 use url::Url;
 
+mod automation;
+pub use automation::*;
 mod browse;
 pub use browse::*;
 mod crawl;
 pub use crawl::*;
+mod frontier;
+pub use frontier::*;
 mod node;
 pub use node::*;
 #[allow(clippy::module_inception)]
 mod page;
 pub use page::*;
+mod sitemap;
+pub use sitemap::*;
+mod snapshot;
+pub use snapshot::*;
+
+/// The format of the body of an article extracted from a page.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArticleFormat {
+    /// Plain text without any structure.
+    #[default]
+    PlainText,
+    /// Markdown that keeps the headings, lists, tables, links and code blocks of the article. See [`HtmlSimplifier::markdown`].
+    Markdown,
+}
 
 pub(crate) async fn get_article(url: Url) -> Result<Document, anyhow::Error> {
-    let html = reqwest::get(url.clone()).await?.text().await?;
-    extract_article(&html)
+    let response = Fetcher::global().get(url).await?;
+    extract_article(&response.text(), response.url())
+}
+
+pub(crate) fn extract_article(html: &str, url: &Url) -> anyhow::Result<Document> {
+    extract_article_as(html, url, ArticleFormat::PlainText)
 }
 
-pub(crate) fn extract_article(html: &str) -> anyhow::Result<Document> {
-    let cleaned =
-        readability::extractor::extract(&mut html.as_bytes(), &Url::parse("https://example.com")?)
-            .unwrap();
-    Ok(Document::from_parts(cleaned.title, cleaned.text))
+/// Extract the article from the HTML of a page. Relative links in the article are resolved against the URL of the page.
+pub(crate) fn extract_article_as(
+    html: &str,
+    url: &Url,
+    format: ArticleFormat,
+) -> anyhow::Result<Document> {
+    let cleaned = readability::extractor::extract(&mut html.as_bytes(), url)?;
+    let body = match format {
+        ArticleFormat::PlainText => cleaned.text,
+        ArticleFormat::Markdown => {
+            let mut simplifier = HtmlSimplifier::default();
+            simplifier.include_links();
+            simplifier.resolve_links_against(url.clone());
+            simplifier.markdown(&Html::parse_fragment(&cleaned.content))
+        }
+    };
+    Ok(Document::from_parts(cleaned.title, body))
 }
@@ -1,8 +1,10 @@
 use super::browse::Tab;
 use super::{super::document::Document, NodeRef};
-use super::{extract_article, AnyNode};
+use super::{extract_article, extract_article_as, AnyNode, ArticleFormat};
 use crate::context::page::crawl::Crawler;
 pub use crate::context::page::crawl::CrawlingCallback;
+use crate::context::page::{CrawlOptions, CrawlSummary};
+use crate::context::Fetcher;
 use image::DynamicImage;
 use once_cell::sync::OnceCell;
 use scraper::{Html, Selector};
@@ -113,6 +115,14 @@ impl Page {
         }
     }
 
+    /// Get the URL the page was loaded from after following any redirects.
+    pub fn final_url(&self) -> Url {
+        match self {
+            Self::Static(page) => page.final_url(),
+            Self::Dynamic(page) => page.url(),
+        }
+    }
+
     /// Extract the article from the page.
     pub async fn article(&self) -> anyhow::Result<Document> {
         match self {
@@ -121,6 +131,14 @@ impl Page {
         }
     }
 
+    /// Extract the article from the page with the body in a format.
+    pub async fn article_with_format(&self, format: ArticleFormat) -> anyhow::Result<Document> {
+        match self {
+            Self::Static(page) => page.article_with_format(format).await,
+            Self::Dynamic(page) => page.article_with_format(format),
+        }
+    }
+
     /// Get the title of the page.
     pub async fn title(&self) -> Option<String> {
         match self {
@@ -139,13 +157,14 @@ impl Page {
 
     /// Get all the links from the page.
     pub async fn links(&self) -> anyhow::Result<Vec<Url>> {
-        let mut links: Vec<_> = self
-            .html()
-            .await?
+        let html = self.html().await?;
+        // Relative links are resolved against the URL after any redirects
+        let base = self.final_url();
+        let mut links: Vec<_> = html
             .select(&Selector::parse("a").unwrap())
             .filter_map(|e| {
                 let href = e.value().attr("href")?;
-                let url = self.url().join(href).ok()?;
+                let url = base.join(href).ok()?;
                 Some(url)
             })
             .collect();
@@ -162,7 +181,20 @@ impl Page {
         mode: BrowserMode,
         visit: impl CrawlingCallback,
     ) -> anyhow::Result<()> {
-        Crawler::new(mode, visit).crawl(start).await
+        Self::crawl_with_options(start, mode, CrawlOptions::default(), visit).await?;
+        Ok(())
+    }
+
+    /// Start crawling from this page with [`CrawlOptions`] that limit the crawl and choose where its progress is stored.
+    ///
+    /// Returns a [`CrawlSummary`] of the pages in the frontier that were visited, skipped or failed to load.
+    pub async fn crawl_with_options(
+        start: Url,
+        mode: BrowserMode,
+        options: CrawlOptions,
+        visit: impl CrawlingCallback,
+    ) -> anyhow::Result<CrawlSummary> {
+        Crawler::new(mode, options, visit).crawl(start).await
     }
 }
 
@@ -182,6 +214,7 @@ pub enum BrowserMode {
 pub struct StaticPage {
     wait_until: Instant,
     url: Url,
+    redirected_url: OnceCell<Url>,
     html: OnceCell<Html>,
 }
 
@@ -191,6 +224,7 @@ impl StaticPage {
         Ok(Self {
             wait_until: Instant::now(),
             url: url.clone(),
+            redirected_url: OnceCell::new(),
             html: OnceCell::new(),
         })
     }
@@ -199,23 +233,31 @@ impl StaticPage {
         Ok(Self {
             wait_until,
             url: url.clone(),
+            redirected_url: OnceCell::new(),
             html: OnceCell::new(),
         })
     }
 
-    /// Get the URL of the page.
+    /// Get the URL the page was created with.
     pub fn url(&self) -> Url {
         self.url.clone()
     }
 
+    /// Get the URL the page was loaded from after following any redirects. Before the page is loaded, this is the same as [`StaticPage::url`].
+    pub fn final_url(&self) -> Url {
+        self.redirected_url.get().unwrap_or(&self.url).clone()
+    }
+
     /// Get the HTML of the page.
     pub async fn html_ref(&self) -> anyhow::Result<&Html> {
         match self.html.get() {
             Some(html) => Ok(html),
             None => {
                 tokio::time::sleep_until(self.wait_until).await;
-                let html = reqwest::get(self.url.clone()).await?.text().await?;
-                let html = Html::parse_document(&html);
+                let response = Fetcher::global().get(self.url.clone()).await?;
+                // Relative links are resolved against the URL after any redirects
+                let _ = self.redirected_url.set(response.url().clone());
+                let html = Html::parse_document(&response.text());
                 self.html.set(html).unwrap();
                 Ok(self.html.get().unwrap())
             }
@@ -229,7 +271,14 @@ impl StaticPage {
 
     /// Extract the article from the page.
     pub async fn article(&self) -> anyhow::Result<Document> {
-        extract_article(&self.html_ref().await?.html())
+        let html = self.html_ref().await?.html();
+        extract_article(&html, &self.final_url())
+    }
+
+    /// Extract the article from the page with the body in a format.
+    pub async fn article_with_format(&self, format: ArticleFormat) -> anyhow::Result<Document> {
+        let html = self.html_ref().await?.html();
+        extract_article_as(&html, &self.final_url(), format)
     }
 
     /// Get the title of the page.
@@ -243,3 +292,77 @@ impl StaticPage {
             .map(|e| e.inner_html())
     }
 }
+
+#[tokio::test]
+async fn articles_are_extracted_from_fixture_pages() {
+    use crate::context::fixtures::FixtureSite;
+
+    let site = FixtureSite::blog().serve().await;
+
+    let page = Page::new(site.url("/posts/first"), BrowserMode::Static).unwrap();
+    let article = page.article().await.unwrap();
+    assert_eq!(article.title(), "Crawling the web offline");
+    assert!(article
+        .body()
+        .contains("served from the test process itself"));
+    assert!(article.body().contains("were never requested at all"));
+    // The navigation, footer and scripts around the article are removed
+    assert!(!article
+        .body()
+        .contains("Subscribe to the fixture newsletter"));
+    assert!(!article.body().contains("Tracking script text"));
+    let markdown = page
+        .article_with_format(ArticleFormat::Markdown)
+        .await
+        .unwrap();
+    assert!(markdown.body().contains("**robots.txt**"));
+    // The page is only downloaded once
+    assert_eq!(site.requests("/posts/first"), 1);
+
+    // Malformed HTML is still read
+    let page = Page::new(site.url("/posts/second"), BrowserMode::Static).unwrap();
+    let article = page.article().await.unwrap();
+    assert_eq!(article.title().trim(), "A page with broken markup");
+    assert!(article.body().contains("Browsers are forgiving"));
+    assert!(article.body().contains("still has to be read"));
+    assert!(article.body().contains("A nested paragraph inside a div"));
+    assert_eq!(page.links().await.unwrap(), [site.url("/posts/first")]);
+}
+
+#[tokio::test]
+async fn redirected_pages_resolve_links_against_the_final_url() {
+    use crate::context::fixtures::FixtureSite;
+
+    let site = FixtureSite::blog().serve().await;
+    let page = Page::new(site.url("/old-post"), BrowserMode::Static).unwrap();
+    assert_eq!(page.final_url(), site.url("/old-post"));
+    assert_eq!(
+        page.title().await.as_deref(),
+        Some("An old post that moved")
+    );
+    // The URL the page was created with doesn't change when it is redirected
+    assert_eq!(page.url(), site.url("/old-post"));
+    assert_eq!(page.final_url(), site.url("/posts/third"));
+    assert_eq!(page.links().await.unwrap(), [site.url("/posts/fourth")]);
+}
+
+#[tokio::test]
+async fn relative_links_in_articles_are_resolved_against_the_page() {
+    use crate::context::fixtures::FixtureSite;
+
+    let html = include_str!("../fixtures/blog/first.html")
+        .replace("a sitemap,", "a <a href=\"../sitemap.xml\">sitemap</a>,");
+    let site = FixtureSite::new()
+        .with_html("/posts/first", html)
+        .serve()
+        .await;
+
+    let page = Page::new(site.url("/posts/first"), BrowserMode::Static).unwrap();
+    let markdown = page
+        .article_with_format(ArticleFormat::Markdown)
+        .await
+        .unwrap();
+    assert!(markdown
+        .body()
+        .contains(&format!("[sitemap]({})", site.url("/sitemap.xml"))));
+}
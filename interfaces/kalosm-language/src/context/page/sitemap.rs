@@ -0,0 +1,425 @@
+use crate::context::document::{Document, IntoDocument, IntoDocuments};
+use crate::context::Fetcher;
+use chrono::{DateTime, NaiveDate, Utc};
+use quick_xml::events::Event;
+use std::collections::HashSet;
+use std::io::Read;
+use texting_robots::Robot;
+use url::{Origin, Url};
+
+use super::crawl::try_get_robot;
+
+/// The maximum number of nested sitemap indexes that are followed.
+const MAX_SITEMAP_DEPTH: usize = 4;
+
+/// A page listed in a [`Sitemap`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SitemapEntry {
+    /// The URL of the page.
+    pub url: Url,
+    /// The time the page was last modified, if the sitemap lists it.
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// A [sitemap](https://www.sitemaps.org/protocol.html) that lists the pages of a website.
+///
+/// Sitemaps may be XML files, plain text files with one URL per line, gzip compressed, or indexes of other sitemaps. Nested
+/// sitemap indexes are followed automatically.
+///
+/// # Example
+/// ```rust, no_run
+/// use kalosm_language::prelude::*;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let sitemaps = Sitemap::discover(&Url::parse("https://floneum.com").unwrap())
+///         .await
+///         .unwrap();
+///     for sitemap in sitemaps {
+///         for entry in sitemap.entries().await.unwrap() {
+///             println!("{} (last modified {:?})", entry.url, entry.last_modified);
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sitemap {
+    url: Url,
+    modified_since: Option<DateTime<Utc>>,
+}
+
+impl From<Url> for Sitemap {
+    fn from(url: Url) -> Self {
+        Self::new(url)
+    }
+}
+
+impl Sitemap {
+    /// Create a new sitemap from the URL of the sitemap file.
+    pub fn new(url: Url) -> Self {
+        Self {
+            url,
+            modified_since: None,
+        }
+    }
+
+    /// Find the sitemaps of the website a URL is on. The sitemaps are read from the `Sitemap:` lines of the robots.txt file of
+    /// the website. If the robots.txt file doesn't list any sitemaps, `/sitemap.xml` is used.
+    pub async fn discover(url: &Url) -> anyhow::Result<Vec<Self>> {
+        let origin = url.origin();
+        let robot = try_get_robot(&origin).await?;
+        Ok(Self::from_robot(&origin, robot.as_ref()))
+    }
+
+    /// Get the sitemaps from a robots.txt file that was already fetched.
+    pub(crate) fn from_robot(origin: &Origin, robot: Option<&Robot>) -> Vec<Self> {
+        let mut sitemaps = robot
+            .map(|robot| {
+                robot
+                    .sitemaps
+                    .iter()
+                    .filter_map(|sitemap| Url::parse(sitemap.trim()).ok())
+                    .map(Self::new)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        if sitemaps.is_empty() {
+            if let Ok(url) = Url::parse(&(origin.ascii_serialization() + "/sitemap.xml")) {
+                sitemaps.push(Self::new(url));
+            }
+        }
+        sitemaps
+    }
+
+    /// Only include pages that were modified after a time. Pages without a modification time are always included.
+    ///
+    /// This can be used to only re-crawl the pages that changed since the last crawl.
+    pub fn with_modified_since(mut self, modified_since: DateTime<Utc>) -> Self {
+        self.modified_since = Some(modified_since);
+        self
+    }
+
+    /// Get the URL of the sitemap.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Read the pages listed in the sitemap and any nested sitemaps.
+    ///
+    /// Nested sitemaps that fail to load or parse are skipped with a warning. Only an error reading this sitemap is returned.
+    pub async fn entries(&self) -> anyhow::Result<Vec<SitemapEntry>> {
+        let mut entries = Vec::new();
+        let mut seen = HashSet::new();
+        let mut sitemaps = vec![(self.url.clone(), 0)];
+        while let Some((url, depth)) = sitemaps.pop() {
+            if !seen.insert(url.clone()) {
+                continue;
+            }
+            let parsed = match read_sitemap(url.clone()).await {
+                Ok(parsed) => parsed,
+                Err(err) if depth == 0 => return Err(err),
+                Err(err) => {
+                    tracing::warn!("Error reading the sitemap {}: {}", url, err);
+                    continue;
+                }
+            };
+
+            for entry in parsed.pages {
+                if self.is_modified(&entry) {
+                    entries.push(entry);
+                }
+            }
+            if depth < MAX_SITEMAP_DEPTH {
+                // Keep the nested sitemaps in the order they are listed
+                for sitemap in parsed.sitemaps.into_iter().rev() {
+                    if self.is_modified(&sitemap) {
+                        sitemaps.push((sitemap.url, depth + 1));
+                    }
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn is_modified(&self, entry: &SitemapEntry) -> bool {
+        match (self.modified_since, entry.last_modified) {
+            (Some(since), Some(modified)) => modified > since,
+            _ => true,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl IntoDocuments for Sitemap {
+    async fn into_documents(self) -> anyhow::Result<Vec<Document>> {
+        let mut documents = Vec::new();
+        for entry in self.entries().await? {
+            match entry.url.clone().into_document().await {
+                Ok(mut document) => {
+                    if let Some(last_modified) = entry.last_modified {
+                        document.set_updated_at(last_modified);
+                    }
+                    documents.push(document);
+                }
+                Err(err) => tracing::warn!("Error reading {}: {}", entry.url, err),
+            }
+        }
+        Ok(documents)
+    }
+}
+
+/// Fetch and parse a sitemap.
+async fn read_sitemap(url: Url) -> anyhow::Result<ParsedSitemap> {
+    let response = Fetcher::global().get(url).await?;
+    let contents = decompress(response.bytes())?;
+    parse_sitemap(&contents)
+}
+
+/// Decompress a sitemap if it is gzip compressed.
+fn decompress(bytes: &[u8]) -> anyhow::Result<String> {
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut contents = String::new();
+        flate2::read::GzDecoder::new(bytes).read_to_string(&mut contents)?;
+        Ok(contents)
+    } else {
+        Ok(String::from_utf8_lossy(bytes).into_owned())
+    }
+}
+
+/// The pages and nested sitemaps listed in a sitemap file.
+#[derive(Debug, Default)]
+struct ParsedSitemap {
+    pages: Vec<SitemapEntry>,
+    sitemaps: Vec<SitemapEntry>,
+}
+
+fn parse_sitemap(contents: &str) -> anyhow::Result<ParsedSitemap> {
+    let mut parsed = ParsedSitemap::default();
+
+    // Text sitemaps list one URL per line
+    if !contents.trim_start().starts_with('<') {
+        parsed.pages = contents
+            .lines()
+            .filter_map(|line| Url::parse(line.trim()).ok())
+            .map(|url| SitemapEntry {
+                url,
+                last_modified: None,
+            })
+            .collect();
+        return Ok(parsed);
+    }
+
+    // The url or sitemap element we are reading and the text of its child elements
+    let mut current: Option<(bool, Option<String>, Option<String>)> = None;
+    let mut element = Vec::new();
+    let mut reader = quick_xml::Reader::from_str(contents);
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) => {
+                let name = start.local_name().as_ref().to_vec();
+                match name.as_slice() {
+                    b"url" => current = Some((false, None, None)),
+                    b"sitemap" => current = Some((true, None, None)),
+                    _ => {}
+                }
+                element = name;
+            }
+            Event::Text(text) => {
+                if let Some((_, loc, lastmod)) = &mut current {
+                    let text = text.unescape()?.trim().to_string();
+                    match element.as_slice() {
+                        b"loc" => loc.get_or_insert_with(String::new).push_str(&text),
+                        b"lastmod" => lastmod.get_or_insert_with(String::new).push_str(&text),
+                        _ => {}
+                    }
+                }
+            }
+            Event::CData(text) => {
+                if let Some((_, loc, _)) = &mut current {
+                    if element.as_slice() == b"loc" {
+                        loc.get_or_insert_with(String::new)
+                            .push_str(String::from_utf8_lossy(&text).trim());
+                    }
+                }
+            }
+            Event::End(end) => {
+                element.clear();
+                if matches!(end.local_name().as_ref(), b"url" | b"sitemap") {
+                    if let Some((is_sitemap, Some(loc), lastmod)) = current.take() {
+                        if let Ok(url) = Url::parse(&loc) {
+                            let entry = SitemapEntry {
+                                url,
+                                last_modified: lastmod.as_deref().and_then(parse_lastmod),
+                            };
+                            if is_sitemap {
+                                parsed.sitemaps.push(entry);
+                            } else {
+                                parsed.pages.push(entry);
+                            }
+                        }
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Parse a [W3C datetime](https://www.w3.org/TR/NOTE-datetime) from the lastmod element of a sitemap.
+fn parse_lastmod(lastmod: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(lastmod) {
+        return Some(time.with_timezone(&Utc));
+    }
+    // Seconds are optional in W3C datetimes
+    let offset = lastmod
+        .strip_suffix('Z')
+        .map(|time| format!("{time}+00:00"));
+    let with_offset = offset.as_deref().unwrap_or(lastmod);
+    if let Ok(time) = DateTime::parse_from_str(with_offset, "%Y-%m-%dT%H:%M%:z") {
+        return Some(time.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(lastmod, "%Y-%m-%d").ok()?;
+    Some(date.and_hms_opt(0, 0, 0)?.and_utc())
+}
+
+#[test]
+fn sitemaps_and_indexes_are_parsed() {
+    let sitemap = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url>
+    <loc>https://example.com/</loc>
+    <lastmod>2024-01-02</lastmod>
+  </url>
+  <url>
+    <loc>https://example.com/docs?page=1&amp;lang=en</loc>
+    <lastmod>2024-03-04T05:06:07+01:00</lastmod>
+  </url>
+  <url><loc><![CDATA[https://example.com/about]]></loc></url>
+</urlset>"#;
+    let parsed = parse_sitemap(sitemap).unwrap();
+    assert!(parsed.sitemaps.is_empty());
+    assert_eq!(
+        parsed.pages,
+        [
+            SitemapEntry {
+                url: Url::parse("https://example.com/").unwrap(),
+                last_modified: Some("2024-01-02T00:00:00Z".parse().unwrap()),
+            },
+            SitemapEntry {
+                url: Url::parse("https://example.com/docs?page=1&lang=en").unwrap(),
+                last_modified: Some("2024-03-04T04:06:07Z".parse().unwrap()),
+            },
+            SitemapEntry {
+                url: Url::parse("https://example.com/about").unwrap(),
+                last_modified: None,
+            },
+        ]
+    );
+
+    let index = r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <sitemap><loc>https://example.com/sitemap-docs.xml.gz</loc><lastmod>2024-05-06T07:08Z</lastmod></sitemap>
+</sitemapindex>"#;
+    let parsed = parse_sitemap(index).unwrap();
+    assert!(parsed.pages.is_empty());
+    assert_eq!(
+        parsed.sitemaps,
+        [SitemapEntry {
+            url: Url::parse("https://example.com/sitemap-docs.xml.gz").unwrap(),
+            last_modified: Some("2024-05-06T07:08:00Z".parse().unwrap()),
+        }]
+    );
+
+    let text = "https://example.com/a\n\nhttps://example.com/b\n";
+    let parsed = parse_sitemap(text).unwrap();
+    assert_eq!(parsed.pages.len(), 2);
+}
+
+#[test]
+fn gzip_sitemaps_are_decompressed() {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder
+        .write_all(b"<urlset><url><loc>https://example.com/</loc></url></urlset>")
+        .unwrap();
+    let compressed = encoder.finish().unwrap();
+    let parsed = parse_sitemap(&decompress(&compressed).unwrap()).unwrap();
+    assert_eq!(parsed.pages[0].url.as_str(), "https://example.com/");
+}
+
+#[tokio::test]
+async fn sitemaps_are_discovered_from_robots_txt() {
+    use crate::context::fixtures::FixtureSite;
+
+    let site = FixtureSite::blog().serve().await;
+    let sitemaps = Sitemap::discover(&site.url("/posts/first")).await.unwrap();
+    assert_eq!(sitemaps, [Sitemap::new(site.url("/sitemap.xml"))]);
+    let entries = sitemaps[0].entries().await.unwrap();
+    assert_eq!(
+        entries,
+        [
+            SitemapEntry {
+                url: site.url("/posts/first"),
+                last_modified: Some("2024-01-02T00:00:00Z".parse().unwrap()),
+            },
+            SitemapEntry {
+                url: site.url("/posts/from-sitemap"),
+                last_modified: None,
+            },
+        ]
+    );
+
+    // Without a robots.txt file, the sitemap is read from /sitemap.xml
+    let site = FixtureSite::new()
+        .with_sitemap(
+            "/sitemap.xml",
+            "<urlset><url><loc>{origin}/only-page</loc></url></urlset>",
+        )
+        .serve()
+        .await;
+    let sitemaps = Sitemap::discover(&site.url("/")).await.unwrap();
+    assert_eq!(sitemaps, [Sitemap::new(site.url("/sitemap.xml"))]);
+    let entries = sitemaps[0].entries().await.unwrap();
+    assert_eq!(entries[0].url, site.url("/only-page"));
+    assert_eq!(site.requests("/robots.txt"), 1);
+}
+
+#[tokio::test]
+async fn broken_nested_sitemaps_are_skipped() {
+    use crate::context::fixtures::FixtureSite;
+
+    let site = FixtureSite::new()
+        .with_sitemap(
+            "/sitemap.xml",
+            "<sitemapindex><sitemap><loc>{origin}/missing.xml</loc></sitemap><sitemap><loc>{origin}/broken.xml</loc></sitemap><sitemap><loc>{origin}/pages.xml</loc></sitemap></sitemapindex>",
+        )
+        .with_sitemap("/broken.xml", "<urlset><url></urlset>")
+        .with_sitemap(
+            "/pages.xml",
+            "<urlset><url><loc>{origin}/page</loc></url></urlset>",
+        )
+        .serve()
+        .await;
+    let entries = Sitemap::new(site.url("/sitemap.xml"))
+        .entries()
+        .await
+        .unwrap();
+    assert_eq!(
+        entries,
+        [SitemapEntry {
+            url: site.url("/page"),
+            last_modified: None,
+        }]
+    );
+    assert_eq!(site.requests("/missing.xml"), 1);
+
+    // An error reading the sitemap itself is still returned
+    assert!(Sitemap::new(site.url("/missing.xml"))
+        .entries()
+        .await
+        .is_err());
+}
@@ -0,0 +1,522 @@
+use headless_chrome::protocol::cdp::types::Method;
+use headless_chrome::protocol::cdp::DOM::{BackendNodeId, NodeId};
+use headless_chrome::Element;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use super::{Node, Tab};
+
+/// Roles of elements that can be interacted with.
+const INTERACTIVE_ROLES: &[&str] = &[
+    "button",
+    "checkbox",
+    "combobox",
+    "link",
+    "listbox",
+    "menuitem",
+    "menuitemcheckbox",
+    "menuitemradio",
+    "option",
+    "radio",
+    "searchbox",
+    "slider",
+    "spinbutton",
+    "switch",
+    "tab",
+    "textbox",
+    "treeitem",
+];
+
+/// Roles of elements that are included in a snapshot if they have a name, to give an agent context about the page.
+const CONTENT_ROLES: &[&str] = &["heading", "img", "image", "dialog", "alert"];
+
+/// States that are shown in a snapshot if they are set.
+const STATES: &[&str] = &[
+    "checked", "disabled", "expanded", "focused", "pressed", "required", "selected",
+];
+
+/// The bounding box of an element in the page, in CSS pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    /// The left edge of the element.
+    pub x: f64,
+    /// The top edge of the element.
+    pub y: f64,
+    /// The width of the element.
+    pub width: f64,
+    /// The height of the element.
+    pub height: f64,
+}
+
+/// An element in an [`AccessibilitySnapshot`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SnapshotElement {
+    /// The id of the element in the snapshot. Use this id to act on the element with [`Tab::act`].
+    pub id: usize,
+    /// The accessibility role of the element, like `button`, `link` or `textbox`.
+    pub role: String,
+    /// The accessible name of the element. For most elements this is the text or label of the element.
+    pub name: String,
+    /// The current value of the element, like the text in a text box.
+    pub value: Option<String>,
+    /// The states of the element that are set, like `checked`, `disabled` or `focused`.
+    pub states: Vec<String>,
+    /// The bounding box of the element in the page.
+    pub bounds: BoundingBox,
+    #[serde(skip)]
+    backend_node_id: BackendNodeId,
+}
+
+impl Display for SnapshotElement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {} {:?}", self.id, self.role, self.name)?;
+        if let Some(value) = &self.value {
+            write!(f, " value={value:?}")?;
+        }
+        if !self.states.is_empty() {
+            write!(f, " ({})", self.states.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// A compact, numbered snapshot of the visible interactive elements of a page, captured from the accessibility tree of the browser.
+///
+/// Snapshots are much smaller than the HTML of a page, so they fit in the context of a model. The snapshot is formatted with one element
+/// per line when it is displayed, and the ids of the elements can be used to act on the page with [`Tab::act`] in the tab the snapshot was
+/// taken in. The ids are only valid until the page changes; take a new snapshot after navigating.
+///
+/// # Example
+/// ```rust, no_run
+/// use kalosm_language::prelude::*;
+///
+/// fn main() -> anyhow::Result<()> {
+///     let tab = Tab::new(Url::parse("https://www.google.com")?, true)?;
+///     let snapshot = tab.accessibility_snapshot()?;
+///     // [1] combobox "Search" (focused)
+///     // [2] button "Google Search"
+///     // ...
+///     println!("{snapshot}");
+///     let search = snapshot
+///         .elements()
+///         .iter()
+///         .find(|element| element.role == "combobox")
+///         .unwrap();
+///     tab.act(&snapshot, SnapshotAction::Type { id: search.id, text: "kalosm".to_string() })?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AccessibilitySnapshot {
+    elements: Vec<SnapshotElement>,
+    /// The id of the tab the snapshot was taken in.
+    #[serde(skip)]
+    target_id: String,
+}
+
+impl AccessibilitySnapshot {
+    /// Get the elements in the snapshot, in document order.
+    pub fn elements(&self) -> &[SnapshotElement] {
+        &self.elements
+    }
+
+    /// Get an element by its id.
+    pub fn get(&self, id: usize) -> Option<&SnapshotElement> {
+        self.elements.iter().find(|element| element.id == id)
+    }
+}
+
+impl Display for AccessibilitySnapshot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for element in &self.elements {
+            writeln!(f, "{element}")?;
+        }
+        Ok(())
+    }
+}
+
+/// An action on an element in an [`AccessibilitySnapshot`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum SnapshotAction {
+    /// Click the element.
+    Click {
+        /// The id of the element.
+        id: usize,
+    },
+    /// Click the element and type text into it.
+    Type {
+        /// The id of the element.
+        id: usize,
+        /// The text to type.
+        text: String,
+    },
+    /// Select an option of a select element by its value or text.
+    Select {
+        /// The id of the element.
+        id: usize,
+        /// The value or text of the option.
+        value: String,
+    },
+    /// Scroll the element into view.
+    Scroll {
+        /// The id of the element.
+        id: usize,
+    },
+}
+
+impl Tab {
+    /// Capture a snapshot of the visible interactive elements of the current page. See [`AccessibilitySnapshot`].
+    #[tracing::instrument]
+    pub fn accessibility_snapshot(&self) -> anyhow::Result<AccessibilitySnapshot> {
+        let tree = self.inner.call_method(GetFullAxTree {})?;
+        // Read the layout of every node at once instead of asking for the box model of each element
+        let layout = layout_bounds(self.inner.call_method(CaptureSnapshot {
+            computed_styles: Vec::new(),
+        })?);
+        let mut elements = Vec::new();
+        for candidate in snapshot_candidates(tree.nodes) {
+            // Elements without a layout are not rendered, and elements without an area can't be seen or clicked
+            let Some(bounds) = layout
+                .get(&candidate.backend_node_id)
+                .filter(|bounds| bounds.width > 0. && bounds.height > 0.)
+            else {
+                continue;
+            };
+            elements.push(SnapshotElement {
+                id: elements.len() + 1,
+                bounds: *bounds,
+                ..candidate
+            });
+        }
+        Ok(AccessibilitySnapshot {
+            elements,
+            target_id: self.inner.get_target_id().to_string(),
+        })
+    }
+
+    /// Get the node of an element in a snapshot of this tab.
+    pub fn snapshot_node(
+        &self,
+        snapshot: &AccessibilitySnapshot,
+        id: usize,
+    ) -> anyhow::Result<Node<'_>> {
+        if snapshot.target_id != *self.inner.get_target_id() {
+            anyhow::bail!(
+                "The snapshot was taken in a different tab. Take a new snapshot of this tab"
+            );
+        }
+        let element = snapshot
+            .get(id)
+            .ok_or_else(|| anyhow::anyhow!("No element with id {id} in the snapshot"))?;
+        // Node ids are only assigned to nodes after the document is requested
+        self.inner.get_document()?;
+        let pushed = self.inner.call_method(PushNodesByBackendIds {
+            backend_node_ids: vec![element.backend_node_id],
+        })?;
+        let node_id = pushed
+            .node_ids
+            .first()
+            .copied()
+            .filter(|node_id| *node_id != 0)
+            .ok_or_else(|| {
+                anyhow::anyhow!("Element {id} is no longer in the page. Take a new snapshot")
+            })?;
+        Ok(Element::new(&self.inner, node_id)?.into())
+    }
+
+    /// Perform an action on an element in a snapshot of this tab.
+    #[tracing::instrument(skip(snapshot))]
+    pub fn act(
+        &self,
+        snapshot: &AccessibilitySnapshot,
+        action: SnapshotAction,
+    ) -> anyhow::Result<()> {
+        match action {
+            SnapshotAction::Click { id } => self.snapshot_node(snapshot, id)?.click(),
+            SnapshotAction::Type { id, text } => self.snapshot_node(snapshot, id)?.send_keys(&text),
+            SnapshotAction::Select { id, value } => {
+                self.snapshot_node(snapshot, id)?.select_option(&value)
+            }
+            SnapshotAction::Scroll { id } => self.snapshot_node(snapshot, id)?.scroll_into_view(),
+        }
+    }
+}
+
+/// Find the nodes of an accessibility tree that should be in a snapshot. The bounds and ids are filled in later.
+fn snapshot_candidates(nodes: Vec<AxNode>) -> Vec<SnapshotElement> {
+    nodes
+        .into_iter()
+        .filter(|node| !node.ignored)
+        .filter_map(|node| {
+            let backend_node_id = node.backend_dom_node_id?;
+            let role = node.role.as_ref()?.as_string()?;
+            let name = node
+                .name
+                .as_ref()
+                .and_then(AxValue::as_string)
+                .unwrap_or_default();
+            let interactive = INTERACTIVE_ROLES.contains(&role.as_str());
+            let content = CONTENT_ROLES.contains(&role.as_str()) && !name.is_empty();
+            if !interactive && !content {
+                return None;
+            }
+            let value = node
+                .value
+                .as_ref()
+                .and_then(AxValue::as_string)
+                .filter(|value| !value.is_empty());
+            let states = node
+                .properties
+                .iter()
+                .filter(|property| STATES.contains(&property.name.as_str()))
+                .filter(|property| property.value.is_set())
+                .map(|property| property.name.clone())
+                .collect();
+            Some(SnapshotElement {
+                id: 0,
+                role,
+                name,
+                value,
+                states,
+                bounds: BoundingBox {
+                    x: 0.,
+                    y: 0.,
+                    width: 0.,
+                    height: 0.,
+                },
+                backend_node_id,
+            })
+        })
+        .collect()
+}
+
+/// The `Accessibility.getFullAXTree` DevTools method.
+#[derive(Debug, Serialize)]
+struct GetFullAxTree {}
+
+impl Method for GetFullAxTree {
+    const NAME: &'static str = "Accessibility.getFullAXTree";
+    type ReturnObject = FullAxTree;
+}
+
+#[derive(Debug, Deserialize)]
+struct FullAxTree {
+    nodes: Vec<AxNode>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AxNode {
+    #[serde(default)]
+    ignored: bool,
+    role: Option<AxValue>,
+    name: Option<AxValue>,
+    value: Option<AxValue>,
+    #[serde(default)]
+    properties: Vec<AxProperty>,
+    #[serde(rename = "backendDOMNodeId")]
+    backend_dom_node_id: Option<BackendNodeId>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AxValue {
+    value: Option<serde_json::Value>,
+}
+
+impl AxValue {
+    fn as_string(&self) -> Option<String> {
+        match self.value.as_ref()? {
+            serde_json::Value::String(value) => Some(value.trim().to_string()),
+            serde_json::Value::Null => None,
+            other => Some(other.to_string()),
+        }
+    }
+
+    /// Check if a state is set. Tristate values like `mixed` are set.
+    fn is_set(&self) -> bool {
+        match &self.value {
+            Some(serde_json::Value::Bool(value)) => *value,
+            Some(serde_json::Value::String(value)) => value != "false",
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AxProperty {
+    name: String,
+    value: AxValue,
+}
+
+/// The `DOMSnapshot.captureSnapshot` DevTools method.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CaptureSnapshot {
+    computed_styles: Vec<String>,
+}
+
+impl Method for CaptureSnapshot {
+    const NAME: &'static str = "DOMSnapshot.captureSnapshot";
+    type ReturnObject = DomSnapshot;
+}
+
+#[derive(Debug, Deserialize)]
+struct DomSnapshot {
+    documents: Vec<DocumentSnapshot>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DocumentSnapshot {
+    nodes: NodeTreeSnapshot,
+    layout: LayoutTreeSnapshot,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NodeTreeSnapshot {
+    #[serde(default)]
+    backend_node_id: Vec<BackendNodeId>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LayoutTreeSnapshot {
+    /// The index of the node of each layout object.
+    node_index: Vec<usize>,
+    /// The bounds of each layout object as x, y, width and height.
+    bounds: Vec<Vec<f64>>,
+}
+
+/// Get the bounds of every node with a layout from a DOM snapshot. Nodes with more than one layout object, like text that wraps over
+/// multiple lines, get the box around every layout object.
+fn layout_bounds(snapshot: DomSnapshot) -> HashMap<BackendNodeId, BoundingBox> {
+    let mut bounds: HashMap<BackendNodeId, BoundingBox> = HashMap::new();
+    for document in snapshot.documents {
+        let layouts = document
+            .layout
+            .node_index
+            .iter()
+            .zip(&document.layout.bounds);
+        for (&index, rect) in layouts {
+            let (Some(&backend_node_id), [x, y, width, height]) =
+                (document.nodes.backend_node_id.get(index), rect.as_slice())
+            else {
+                continue;
+            };
+            let rect = BoundingBox {
+                x: *x,
+                y: *y,
+                width: *width,
+                height: *height,
+            };
+            bounds
+                .entry(backend_node_id)
+                .and_modify(|bounds| *bounds = bounds.union(&rect))
+                .or_insert(rect);
+        }
+    }
+    bounds
+}
+
+impl BoundingBox {
+    /// Get the smallest box that contains both boxes.
+    fn union(&self, other: &Self) -> Self {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Self {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
+}
+
+/// The `DOM.pushNodesByBackendIdsToFrontend` DevTools method.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PushNodesByBackendIds {
+    backend_node_ids: Vec<BackendNodeId>,
+}
+
+impl Method for PushNodesByBackendIds {
+    const NAME: &'static str = "DOM.pushNodesByBackendIdsToFrontend";
+    type ReturnObject = PushedNodes;
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PushedNodes {
+    node_ids: Vec<NodeId>,
+}
+
+#[test]
+fn snapshots_keep_interactive_and_named_elements() {
+    let tree: FullAxTree = serde_json::from_value(serde_json::json!({
+        "nodes": [
+            {"nodeId": "1", "ignored": false, "role": {"type": "role", "value": "RootWebArea"}, "name": {"type": "computedString", "value": "Example"}, "backendDOMNodeId": 1},
+            {"nodeId": "2", "ignored": false, "role": {"type": "role", "value": "heading"}, "name": {"type": "computedString", "value": "Welcome"}, "backendDOMNodeId": 2},
+            {"nodeId": "3", "ignored": false, "role": {"type": "role", "value": "generic"}, "name": {"type": "computedString", "value": ""}, "backendDOMNodeId": 3},
+            {"nodeId": "4", "ignored": false, "role": {"type": "role", "value": "textbox"}, "name": {"type": "computedString", "value": "Search "}, "value": {"type": "string", "value": "kalosm"}, "properties": [{"name": "focused", "value": {"type": "booleanOrUndefined", "value": true}}, {"name": "disabled", "value": {"type": "boolean", "value": false}}], "backendDOMNodeId": 4},
+            {"nodeId": "5", "ignored": true, "role": {"type": "role", "value": "button"}, "backendDOMNodeId": 5},
+            {"nodeId": "6", "ignored": false, "role": {"type": "role", "value": "checkbox"}, "name": {"type": "computedString", "value": "Remember me"}, "properties": [{"name": "checked", "value": {"type": "tristate", "value": "true"}}], "backendDOMNodeId": 6},
+            {"nodeId": "7", "ignored": false, "role": {"type": "role", "value": "img"}, "name": {"type": "computedString", "value": ""}, "backendDOMNodeId": 7}
+        ]
+    }))
+    .unwrap();
+
+    let elements = snapshot_candidates(tree.nodes)
+        .into_iter()
+        .enumerate()
+        .map(|(i, element)| SnapshotElement {
+            id: i + 1,
+            ..element
+        })
+        .collect();
+    let snapshot = AccessibilitySnapshot {
+        elements,
+        target_id: String::new(),
+    };
+    assert_eq!(
+        snapshot.to_string(),
+        "[1] heading \"Welcome\"\n[2] textbox \"Search\" value=\"kalosm\" (focused)\n[3] checkbox \"Remember me\" (checked)\n"
+    );
+    assert_eq!(snapshot.get(2).unwrap().backend_node_id, 4);
+
+    let layout: DomSnapshot = serde_json::from_value(serde_json::json!({
+        "documents": [{
+            "nodes": {"backendNodeId": [1, 2, 4, 6, 7]},
+            "layout": {
+                "nodeIndex": [0, 1, 2, 2, 4],
+                "bounds": [[0, 0, 800, 600], [10, 20, 100, 30], [10, 50, 40, 10], [0, 60, 20, 10], [5, 5, 0, 0]]
+            }
+        }],
+        "strings": []
+    }))
+    .unwrap();
+    let bounds = layout_bounds(layout);
+    assert_eq!(
+        bounds[&2],
+        BoundingBox {
+            x: 10.,
+            y: 20.,
+            width: 100.,
+            height: 30.,
+        }
+    );
+    // Text that wraps over two lines gets the box around both lines
+    assert_eq!(
+        bounds[&4],
+        BoundingBox {
+            x: 0.,
+            y: 50.,
+            width: 50.,
+            height: 20.,
+        }
+    );
+    // The checkbox is not rendered, and the image has no area
+    assert!(!bounds.contains_key(&6));
+    assert_eq!(bounds[&7].width, 0.);
+}
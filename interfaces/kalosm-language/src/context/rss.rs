@@ -1,9 +1,17 @@
+use chrono::{DateTime, Utc};
+use futures_util::Stream;
 use rss::Channel;
+use serde::Deserialize;
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
 use url::Url;
 
-use super::document::{Document, IntoDocuments};
+use super::document::{Document, IntoDocument, IntoDocuments};
+use super::Fetcher;
 
-/// A RSS feed that can be used to add documents to a search index.
+/// A RSS, Atom or [JSON Feed](https://www.jsonfeed.org/) feed that can be used to add documents to a search index.
+///
+/// The format of the feed is detected automatically.
 ///
 /// # Example
 /// ```rust, no_run
@@ -45,37 +53,448 @@ impl RssFeed {
         &self.0
     }
 
+    /// Read the items in the feed without reading the articles they link to.
+    pub async fn items(&self) -> anyhow::Result<Vec<FeedItem>> {
+        let feed = Fetcher::global().get(self.0.clone()).await?.text();
+        parse_feed(&feed, &self.0)
+    }
+
     /// Read the top N documents from the RSS feed.
     pub async fn read_top_n(&self, top_n: usize) -> anyhow::Result<Vec<Document>> {
-        let xml = reqwest::get(self.0.clone()).await?.text().await?;
-        let channel = Channel::read_from(xml.as_bytes())?;
         let mut documents = Vec::new();
-        for item in channel.items().iter().take(top_n) {
-            let mut message = String::new();
-            if let Some(title) = item.title() {
-                message.push_str(&format!("### {}\n", title));
-            }
-            let (source_url, content) = if let Some(content) = item.content() {
-                (None, content.to_string())
-            } else if let Some(source_url) = item.link() {
-                (
-                    Some(source_url),
-                    reqwest::get(source_url).await?.text().await?,
+        for item in self.items().await?.into_iter().take(top_n) {
+            documents.push(item.into_document().await?);
+        }
+        Ok(documents)
+    }
+}
+
+/// An item in a [`RssFeed`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedItem {
+    /// The unique id of the item. This is the GUID of RSS items, the id of Atom entries and JSON Feed items, or the link
+    /// of the item if the feed doesn't give it an id.
+    pub id: String,
+    /// The title of the item.
+    pub title: Option<String>,
+    /// The page the item links to.
+    pub link: Option<Url>,
+    /// The HTML or text content of the item if the feed includes it.
+    pub content: Option<String>,
+    /// The summary of the item.
+    pub summary: Option<String>,
+    /// The time the item was published.
+    pub published: Option<DateTime<Utc>>,
+    /// The URL of the feed the item is from. Links in the content of items without a link of their own are relative to the feed.
+    pub feed_url: Url,
+}
+
+impl FeedItem {
+    fn new(
+        feed_url: &Url,
+        id: Option<String>,
+        title: Option<String>,
+        link: Option<Url>,
+        content: Option<String>,
+        summary: Option<String>,
+        published: Option<DateTime<Utc>>,
+    ) -> Option<Self> {
+        let id = id
+            .filter(|id| !id.is_empty())
+            .or_else(|| link.as_ref().map(|link| link.to_string()))
+            .or_else(|| title.clone())?;
+        Some(Self {
+            id,
+            title,
+            link,
+            content,
+            summary,
+            published,
+            feed_url: feed_url.clone(),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl IntoDocument for FeedItem {
+    async fn into_document(self) -> anyhow::Result<Document> {
+        // Prefer the content in the feed over downloading the page the item links to
+        let content = match (self.content, &self.link) {
+            (Some(content), _) => content,
+            (None, Some(link)) => Fetcher::global().get(link.clone()).await?.text(),
+            (None, None) => self.summary.unwrap_or_default(),
+        };
+
+        let base = self.link.as_ref().unwrap_or(&self.feed_url);
+        let article = readability::extractor::extract(&mut std::io::Cursor::new(&content), base)?;
+
+        let title = self.title.unwrap_or(article.title);
+        let mut document = Document::from_parts(title, article.text);
+        if let Some(published) = self.published {
+            document.set_created_at(published);
+        }
+        if let Some(link) = self.link {
+            document.metadata_mut().set_source(link);
+        }
+        Ok(document)
+    }
+}
+
+/// Parse a RSS, Atom or JSON feed.
+fn parse_feed(feed: &str, url: &Url) -> anyhow::Result<Vec<FeedItem>> {
+    let link = |href: &str| url.join(href.trim()).ok();
+
+    if feed.trim_start().starts_with('{') {
+        let feed: JsonFeed = serde_json::from_str(feed)?;
+        return Ok(feed
+            .items
+            .into_iter()
+            .filter_map(|item| {
+                FeedItem::new(
+                    url,
+                    item.id.map(|id| match id {
+                        serde_json::Value::String(id) => id,
+                        id => id.to_string(),
+                    }),
+                    item.title,
+                    item.url.as_deref().and_then(link),
+                    item.content_html.or(item.content_text),
+                    item.summary,
+                    item.date_published
+                        .or(item.date_modified)
+                        .as_deref()
+                        .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+                        .map(|date| date.with_timezone(&Utc)),
                 )
-            } else {
-                (None, String::new())
-            };
+            })
+            .collect());
+    }
+
+    match Channel::read_from(feed.as_bytes()) {
+        Ok(channel) => Ok(channel
+            .items()
+            .iter()
+            .filter_map(|item| {
+                FeedItem::new(
+                    url,
+                    item.guid().map(|guid| guid.value().to_string()),
+                    item.title().map(ToString::to_string),
+                    item.link().and_then(link),
+                    item.content().map(ToString::to_string),
+                    item.description().map(ToString::to_string),
+                    item.pub_date()
+                        .and_then(|date| DateTime::parse_from_rfc2822(date.trim()).ok())
+                        .map(|date| date.with_timezone(&Utc)),
+                )
+            })
+            .collect()),
+        Err(rss_error) => {
+            let feed = atom_syndication::Feed::read_from(feed.as_bytes()).map_err(|_| {
+                anyhow::anyhow!("Failed to parse the feed as RSS or Atom: {rss_error}")
+            })?;
+            Ok(feed
+                .entries()
+                .iter()
+                .filter_map(|entry| {
+                    let href = entry
+                        .links()
+                        .iter()
+                        .find(|link| link.rel() == "alternate")
+                        .or_else(|| entry.links().first())
+                        .map(|link| link.href());
+                    FeedItem::new(
+                        url,
+                        Some(entry.id().to_string()),
+                        Some(entry.title().value.clone()),
+                        href.and_then(link),
+                        entry
+                            .content()
+                            .and_then(|content| content.value())
+                            .map(ToString::to_string),
+                        entry.summary().map(|summary| summary.value.clone()),
+                        Some(
+                            entry
+                                .published()
+                                .unwrap_or(entry.updated())
+                                .with_timezone(&Utc),
+                        ),
+                    )
+                })
+                .collect())
+        }
+    }
+}
+
+/// A [JSON Feed](https://www.jsonfeed.org/version/1.1/).
+#[derive(Deserialize)]
+struct JsonFeed {
+    #[serde(default)]
+    items: Vec<JsonFeedItem>,
+}
+
+#[derive(Deserialize)]
+struct JsonFeedItem {
+    id: Option<serde_json::Value>,
+    url: Option<String>,
+    title: Option<String>,
+    content_html: Option<String>,
+    content_text: Option<String>,
+    summary: Option<String>,
+    date_published: Option<String>,
+    date_modified: Option<String>,
+}
+
+/// Polls a [`RssFeed`] and returns only the items that were not seen in an earlier poll.
+///
+/// Items are identified by their GUID or id. The publish date of each item is set as the creation time of its document with
+/// [`Document::set_created_at`].
+///
+/// # Example
+/// ```rust, no_run
+/// use kalosm_language::prelude::*;
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let feed = RssFeed::new(Url::parse("https://rss.nytimes.com/services/xml/rss/nyt/US.xml").unwrap());
+///     let mut documents = FeedWatcher::new(feed)
+///         .with_interval(Duration::from_secs(60 * 10))
+///         .into_stream();
+///     while let Some(document) = documents.next().await {
+///         println!("New article: {}", document.title());
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FeedWatcher {
+    feed: RssFeed,
+    interval: Duration,
+    seen: HashSet<String>,
+    published_after: Option<DateTime<Utc>>,
+}
 
-            let url = match source_url {
-                Some(url) => Url::parse(url).unwrap(),
-                None => self.0.clone(),
-            };
+impl FeedWatcher {
+    /// Create a new watcher for a feed that polls every 15 minutes.
+    pub fn new(feed: impl Into<RssFeed>) -> Self {
+        Self {
+            feed: feed.into(),
+            interval: Duration::from_secs(15 * 60),
+            seen: HashSet::new(),
+            published_after: None,
+        }
+    }
 
-            let article =
-                readability::extractor::extract(&mut std::io::Cursor::new(&content), &url)?;
+    /// Set the time between polls of the stream. (default: 15 minutes)
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Mark the ids of items as seen so they are not returned. This can be used with [`FeedWatcher::seen`] to keep watching a feed after a restart.
+    pub fn with_seen(mut self, ids: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.seen.extend(ids.into_iter().map(Into::into));
+        self
+    }
+
+    /// Only return items published after a time. Items without a publish date are always returned.
+    pub fn with_published_after(mut self, published_after: DateTime<Utc>) -> Self {
+        self.published_after = Some(published_after);
+        self
+    }
 
-            documents.push(Document::from_parts(article.title, article.text));
+    /// Get the ids of the items that were seen in the last poll.
+    pub fn seen(&self) -> &HashSet<String> {
+        &self.seen
+    }
+
+    /// Read the feed once and return the documents for the items that are new since the last poll, oldest first.
+    ///
+    /// Items that fail to load are returned again by the next poll.
+    pub async fn poll(&mut self) -> anyhow::Result<Vec<Document>> {
+        let items = self.feed.items().await?;
+        let mut seen = HashSet::new();
+        let mut documents = Vec::new();
+        // Feeds list the newest items first
+        for item in items.into_iter().rev() {
+            if self.seen.contains(&item.id) {
+                seen.insert(item.id);
+                continue;
+            }
+            if let (Some(after), Some(published)) = (self.published_after, item.published) {
+                if published <= after {
+                    seen.insert(item.id);
+                    continue;
+                }
+            }
+            let id = item.id.clone();
+            match item.into_document().await {
+                Ok(document) => {
+                    seen.insert(id);
+                    documents.push(document);
+                }
+                Err(err) => tracing::warn!("Error reading feed item {}: {}", id, err),
+            }
         }
+        // Only remember the items that are still in the feed
+        self.seen = seen;
         Ok(documents)
     }
+
+    /// Poll the feed on the interval and stream the documents for new items. The first poll happens immediately.
+    pub fn into_stream(self) -> impl Stream<Item = Document> + Send + Unpin {
+        Box::pin(futures_util::stream::unfold(
+            (self, VecDeque::new(), true),
+            |(mut watcher, mut pending, mut first)| async move {
+                loop {
+                    if let Some(document) = pending.pop_front() {
+                        return Some((document, (watcher, pending, first)));
+                    }
+                    if !first {
+                        tokio::time::sleep(watcher.interval).await;
+                    }
+                    first = false;
+                    match watcher.poll().await {
+                        Ok(documents) => pending.extend(documents),
+                        Err(err) => {
+                            tracing::error!("Error polling feed {}: {}", watcher.feed.url(), err)
+                        }
+                    }
+                }
+            },
+        ))
+    }
+}
+
+#[test]
+fn rss_atom_and_json_feeds_are_parsed() {
+    let url = Url::parse("https://example.com/feed").unwrap();
+
+    let rss = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>Blog</title><link>https://example.com</link><description>A blog</description>
+<item><title>Second</title><link>/second</link><guid>post-2</guid><pubDate>Tue, 02 Jan 2024 10:00:00 +0000</pubDate></item>
+<item><title>First</title><link>https://example.com/first</link></item>
+</channel></rss>"#;
+    let items = parse_feed(rss, &url).unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].id, "post-2");
+    assert_eq!(
+        items[0].link.as_ref().unwrap().as_str(),
+        "https://example.com/second"
+    );
+    assert_eq!(
+        items[0].published,
+        Some("2024-01-02T10:00:00Z".parse().unwrap())
+    );
+    // Items without a GUID are identified by their link
+    assert_eq!(items[1].id, "https://example.com/first");
+
+    let atom = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Blog</title><id>urn:blog</id><updated>2024-02-01T00:00:00Z</updated>
+  <entry>
+    <title>Hello Atom</title><id>urn:post:1</id>
+    <link rel="alternate" href="https://example.com/hello"/>
+    <published>2024-01-31T12:00:00+01:00</published><updated>2024-02-01T00:00:00Z</updated>
+    <content type="html">&lt;p&gt;Hello&lt;/p&gt;</content>
+  </entry>
+</feed>"#;
+    let items = parse_feed(atom, &url).unwrap();
+    assert_eq!(
+        items,
+        [FeedItem {
+            id: "urn:post:1".to_string(),
+            title: Some("Hello Atom".to_string()),
+            link: Some(Url::parse("https://example.com/hello").unwrap()),
+            content: Some("<p>Hello</p>".to_string()),
+            summary: None,
+            published: Some("2024-01-31T11:00:00Z".parse().unwrap()),
+            feed_url: url.clone(),
+        }]
+    );
+
+    let json = r#"{
+        "version": "https://jsonfeed.org/version/1.1",
+        "title": "Blog",
+        "items": [
+            {"id": "1", "url": "https://example.com/json", "title": "Hello JSON", "content_text": "Hello", "date_published": "2024-03-01T00:00:00Z"},
+            {"id": 2, "content_html": "<p>No title</p>"}
+        ]
+    }"#;
+    let items = parse_feed(json, &url).unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].title.as_deref(), Some("Hello JSON"));
+    assert_eq!(items[0].content.as_deref(), Some("Hello"));
+    assert_eq!(items[1].id, "2");
+}
+
+#[tokio::test]
+async fn feed_watcher_only_returns_new_items() {
+    use super::fetch::test_server;
+    use std::sync::{Arc, Mutex};
+
+    let item = |id: usize| {
+        format!(
+            "<item><title>Post {id}</title><guid>{id}</guid><pubDate>0{id} Jan 2024 00:00:00 +0000</pubDate><content:encoded><![CDATA[<p>Post number {id}</p>]]></content:encoded></item>"
+        )
+    };
+    let feed = |items: &[usize]| {
+        format!(
+            r#"<rss version="2.0" xmlns:content="http://purl.org/rss/1.0/modules/content/"><channel><title>Blog</title><link>https://example.com</link><description>A blog</description>{}</channel></rss>"#,
+            items.iter().rev().map(|id| item(*id)).collect::<String>()
+        )
+    };
+    let body = Arc::new(Mutex::new(feed(&[1, 2])));
+    let router = axum::Router::new().route(
+        "/feed.xml",
+        axum::routing::get({
+            let body = body.clone();
+            move || async move { body.lock().unwrap().clone() }
+        }),
+    );
+    let url = test_server(router).await.join("/feed.xml").unwrap();
+    let mut watcher = FeedWatcher::new(url);
+    let titles = |documents: Vec<Document>| {
+        documents
+            .iter()
+            .map(|document| document.title().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    let documents = watcher.poll().await.unwrap();
+    assert_eq!(
+        documents[0].created_at(),
+        Some("2024-01-01T00:00:00Z".parse().unwrap())
+    );
+    assert_eq!(titles(documents), ["Post 1", "Post 2"]);
+    assert!(watcher.poll().await.unwrap().is_empty());
+
+    *body.lock().unwrap() = feed(&[1, 2, 3]);
+    assert_eq!(titles(watcher.poll().await.unwrap()), ["Post 3"]);
+}
+
+#[tokio::test]
+async fn feed_items_are_read_from_fixture_sites() {
+    use crate::context::fixtures::FixtureSite;
+
+    let site = FixtureSite::blog().serve().await;
+    let feed = RssFeed::new(site.url("/feed.xml"));
+    let documents = feed.read_top_n(2).await.unwrap();
+    assert_eq!(documents.len(), 2);
+
+    // Items without content are read from the article they link to
+    assert_eq!(documents[0].title(), "Crawling the web offline");
+    assert!(documents[0]
+        .body()
+        .contains("served from the test process itself"));
+    assert_eq!(
+        documents[0].created_at(),
+        Some("2024-01-02T10:00:00Z".parse().unwrap())
+    );
+
+    // Items with content are read from the feed
+    assert_eq!(documents[1].title(), "A short note");
+    assert!(documents[1]
+        .body()
+        .contains("The whole note is in the feed"));
+    assert_eq!(site.requests("/notes/short"), 0);
 }
@@ -0,0 +1,144 @@
+use url::Url;
+
+use super::provider::{send_json, SearchProvider, WebSearchResult};
+
+/// The endpoint of the Brave web search API.
+const BRAVE_ENDPOINT: &str = "https://api.search.brave.com/res/v1/web/search";
+
+/// The maximum number of results the Brave API returns for one request.
+const BRAVE_MAX_COUNT: usize = 20;
+
+/// A [`SearchProvider`] for the [Brave Search API](https://brave.com/search/api/).
+///
+/// # Example
+/// ```rust, no_run
+/// use kalosm_language::prelude::*;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     // You must have the BRAVE_API_KEY environment variable set to run this example.
+///     let brave = Brave::new(std::env::var("BRAVE_API_KEY").unwrap());
+///     for result in brave.search("What is Floneum?", 5).await.unwrap() {
+///         println!("{}. {} ({})", result.rank, result.title, result.url);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Brave {
+    api_key: String,
+    endpoint: Url,
+}
+
+impl Brave {
+    /// Create a new provider with a Brave Search API key.
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            endpoint: Url::parse(BRAVE_ENDPOINT).unwrap(),
+        }
+    }
+
+    /// Set the URL of the web search endpoint. This can be used with a proxy in front of the API. (default: `https://api.search.brave.com/res/v1/web/search`)
+    pub fn with_endpoint(mut self, endpoint: Url) -> Self {
+        self.endpoint = endpoint;
+        self
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct BraveResponse {
+    web: Option<BraveWebResults>,
+}
+
+#[derive(serde::Deserialize)]
+struct BraveWebResults {
+    #[serde(default)]
+    results: Vec<BraveResult>,
+}
+
+#[derive(serde::Deserialize)]
+struct BraveResult {
+    url: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    description: String,
+}
+
+#[async_trait::async_trait]
+impl SearchProvider for Brave {
+    async fn search(&self, query: &str, top_n: usize) -> anyhow::Result<Vec<WebSearchResult>> {
+        let count = top_n.clamp(1, BRAVE_MAX_COUNT).to_string();
+        let response: BraveResponse = send_json(|client| {
+            client
+                .get(self.endpoint.clone())
+                .query(&[("q", query), ("count", count.as_str())])
+                .header("X-Subscription-Token", self.api_key.as_str())
+        })
+        .await?;
+
+        let results = response.web.map(|web| web.results).unwrap_or_default();
+        Ok(WebSearchResult::ranked(
+            results
+                .into_iter()
+                .map(|result| (result.title, result.url, result.description)),
+            top_n,
+        ))
+    }
+}
+
+#[tokio::test]
+async fn brave_results_are_parsed() {
+    use axum::extract::Query;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::response::IntoResponse;
+    use std::collections::HashMap;
+
+    let router = axum::Router::new().route(
+        "/res/v1/web/search",
+        axum::routing::get(
+            |headers: HeaderMap, Query(query): Query<HashMap<String, String>>| async move {
+                if !headers
+                    .get("X-Subscription-Token")
+                    .is_some_and(|key| key == "secret")
+                {
+                    return StatusCode::UNAUTHORIZED.into_response();
+                }
+                assert_eq!(query["q"], "floneum");
+                assert_eq!(query["count"], "1");
+                axum::Json(serde_json::json!({
+                    "type": "search",
+                    "web": {
+                        "type": "search",
+                        "results": [
+                            {"title": "Floneum", "url": "https://floneum.com/", "description": "A visual editor for AI workflows"}
+                        ]
+                    }
+                }))
+                .into_response()
+            },
+        ),
+    );
+    let endpoint = crate::context::fetch::test_server(router)
+        .await
+        .join("/res/v1/web/search")
+        .unwrap();
+
+    let results = Brave::new("secret")
+        .with_endpoint(endpoint.clone())
+        .search("floneum", 1)
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].title, "Floneum");
+    assert_eq!(results[0].url.as_str(), "https://floneum.com/");
+    assert_eq!(results[0].rank, 1);
+
+    // Errors from the API are returned instead of an empty list of results
+    let error = Brave::new("wrong")
+        .with_endpoint(endpoint)
+        .search("floneum", 1)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("401"));
+}
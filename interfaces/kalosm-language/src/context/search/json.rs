@@ -0,0 +1,221 @@
+use reqwest::header::{HeaderName, HeaderValue};
+use serde_json::Value;
+use url::Url;
+
+use super::provider::{send_json, SearchProvider, WebSearchResult};
+
+/// A [`SearchProvider`] for any search API that answers a GET request with JSON.
+///
+/// The query is sent as a query parameter of the endpoint. The results are read from the response with
+/// [JSON pointers](https://datatracker.ietf.org/doc/html/rfc6901): one pointer to the list of results, and one pointer for each
+/// field relative to a result. By default the results are read from `/results` and each result has a `/title`, `/url` and `/snippet`.
+///
+/// # Example
+/// ```rust, no_run
+/// use kalosm_language::prelude::*;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     // A search API that responds with {"data": {"hits": [{"name": "...", "link": "...", "summary": "..."}]}}
+///     let provider = JsonSearchProvider::new(Url::parse("https://search.example.com/api").unwrap())
+///         .with_query_parameter("query")
+///         .with_header("Authorization", "Bearer my-token")
+///         .unwrap()
+///         .with_results_pointer("/data/hits")
+///         .with_title_pointer("/name")
+///         .with_url_pointer("/link")
+///         .with_snippet_pointer("/summary");
+///     for result in provider.search("What is Floneum?", 5).await.unwrap() {
+///         println!("{}. {} ({})", result.rank, result.title, result.url);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct JsonSearchProvider {
+    endpoint: Url,
+    query_parameter: String,
+    count_parameter: Option<String>,
+    headers: Vec<(HeaderName, HeaderValue)>,
+    results_pointer: String,
+    title_pointer: String,
+    url_pointer: String,
+    snippet_pointer: String,
+}
+
+impl JsonSearchProvider {
+    /// Create a new provider for a search endpoint. Any query parameters in the endpoint are sent with every search.
+    pub fn new(endpoint: Url) -> Self {
+        Self {
+            endpoint,
+            query_parameter: "q".to_string(),
+            count_parameter: None,
+            headers: Vec::new(),
+            results_pointer: "/results".to_string(),
+            title_pointer: "/title".to_string(),
+            url_pointer: "/url".to_string(),
+            snippet_pointer: "/snippet".to_string(),
+        }
+    }
+
+    /// Set the name of the query parameter the search query is sent in. (default: `q`)
+    pub fn with_query_parameter(mut self, parameter: impl Into<String>) -> Self {
+        self.query_parameter = parameter.into();
+        self
+    }
+
+    /// Send the number of requested results in a query parameter. (default: the number of results is not sent)
+    pub fn with_count_parameter(mut self, parameter: impl Into<String>) -> Self {
+        self.count_parameter = Some(parameter.into());
+        self
+    }
+
+    /// Send a header with every search, for example an API key. Returns an error if the header name or value is invalid.
+    pub fn with_header(mut self, name: &str, value: &str) -> anyhow::Result<Self> {
+        self.headers
+            .push((HeaderName::try_from(name)?, HeaderValue::try_from(value)?));
+        Ok(self)
+    }
+
+    /// Set the JSON pointer to the list of results in the response. (default: `/results`)
+    pub fn with_results_pointer(mut self, pointer: impl Into<String>) -> Self {
+        self.results_pointer = pointer.into();
+        self
+    }
+
+    /// Set the JSON pointer to the title, relative to a result. (default: `/title`)
+    pub fn with_title_pointer(mut self, pointer: impl Into<String>) -> Self {
+        self.title_pointer = pointer.into();
+        self
+    }
+
+    /// Set the JSON pointer to the URL, relative to a result. Results without a valid URL are skipped. (default: `/url`)
+    pub fn with_url_pointer(mut self, pointer: impl Into<String>) -> Self {
+        self.url_pointer = pointer.into();
+        self
+    }
+
+    /// Set the JSON pointer to the snippet, relative to a result. (default: `/snippet`)
+    pub fn with_snippet_pointer(mut self, pointer: impl Into<String>) -> Self {
+        self.snippet_pointer = pointer.into();
+        self
+    }
+}
+
+/// Read a string from a JSON value. Missing values are read as an empty string.
+fn pointer_string(value: &Value, pointer: &str) -> String {
+    match value.pointer(pointer) {
+        Some(Value::String(string)) => string.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchProvider for JsonSearchProvider {
+    async fn search(&self, query: &str, top_n: usize) -> anyhow::Result<Vec<WebSearchResult>> {
+        let mut url = self.endpoint.clone();
+        {
+            let mut pairs = url.query_pairs_mut();
+            pairs.append_pair(&self.query_parameter, query);
+            if let Some(count) = &self.count_parameter {
+                pairs.append_pair(count, &top_n.to_string());
+            }
+        }
+        let response: Value = send_json(|client| {
+            let mut request = client.get(url.clone());
+            for (name, value) in &self.headers {
+                request = request.header(name.clone(), value.clone());
+            }
+            request
+        })
+        .await?;
+
+        let results = response
+            .pointer(&self.results_pointer)
+            .and_then(Value::as_array)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Search response has no list of results at {}",
+                    self.results_pointer
+                )
+            })?;
+        Ok(WebSearchResult::ranked(
+            results.iter().map(|result| {
+                (
+                    pointer_string(result, &self.title_pointer),
+                    pointer_string(result, &self.url_pointer),
+                    pointer_string(result, &self.snippet_pointer),
+                )
+            }),
+            top_n,
+        ))
+    }
+}
+
+#[tokio::test]
+async fn json_results_are_read_with_pointers() {
+    use axum::extract::Query;
+    use axum::http::HeaderMap;
+    use std::collections::HashMap;
+
+    let router = axum::Router::new().route(
+        "/api",
+        axum::routing::get(
+            |headers: HeaderMap, Query(query): Query<HashMap<String, String>>| async move {
+                assert_eq!(headers["authorization"], "Bearer token");
+                assert_eq!(query["query"], "floneum");
+                assert_eq!(query["limit"], "3");
+                assert_eq!(query["lang"], "en");
+                axum::Json(serde_json::json!({
+                    "data": {
+                        "hits": [
+                            {"name": "Floneum", "link": {"href": "https://floneum.com/"}, "summary": "A visual editor"},
+                            {"name": "Kalosm", "link": {"href": "https://docs.rs/kalosm"}, "summary": null}
+                        ]
+                    }
+                }))
+            },
+        ),
+    );
+    let mut endpoint = crate::context::fetch::test_server(router)
+        .await
+        .join("/api")
+        .unwrap();
+    endpoint.set_query(Some("lang=en"));
+
+    let provider = JsonSearchProvider::new(endpoint)
+        .with_query_parameter("query")
+        .with_count_parameter("limit")
+        .with_header("Authorization", "Bearer token")
+        .unwrap()
+        .with_results_pointer("/data/hits")
+        .with_title_pointer("/name")
+        .with_url_pointer("/link/href")
+        .with_snippet_pointer("/summary");
+    let results = provider.search("floneum", 3).await.unwrap();
+    assert_eq!(
+        results,
+        [
+            WebSearchResult {
+                title: "Floneum".to_string(),
+                url: Url::parse("https://floneum.com/").unwrap(),
+                snippet: "A visual editor".to_string(),
+                rank: 1,
+            },
+            WebSearchResult {
+                title: "Kalosm".to_string(),
+                url: Url::parse("https://docs.rs/kalosm").unwrap(),
+                snippet: String::new(),
+                rank: 2,
+            },
+        ]
+    );
+
+    // A response without results at the pointer is an error
+    let error = provider
+        .with_results_pointer("/missing")
+        .search("floneum", 3)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("/missing"));
+}
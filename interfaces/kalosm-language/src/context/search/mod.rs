@@ -1,15 +1,23 @@
 #![allow(missing_docs)]
 
-use rand::seq::SliceRandom;
 use url::Url;
 
-use super::{
-    document::{Document, IntoDocuments},
-    page::get_article,
-};
+use super::document::{Document, IntoDocuments};
+
+mod brave;
+pub use brave::*;
+mod json;
+pub use json::*;
+mod provider;
+use provider::send_json;
+pub use provider::*;
+mod searxng;
+pub use searxng::*;
 
 /// A search query that can be used to search for documents on the web.
 ///
+/// The pages of the top results are fetched and read as articles. Pages that can't be read are skipped.
+///
 /// # Example
 /// ```rust, no_run
 /// // You must have the SERPER_API_KEY environment variable set to run this example.
@@ -34,16 +42,21 @@ use super::{
 /// ```
 pub struct SearchQuery<'a> {
     query: &'a str,
-    api_key: &'a str,
+    provider: Box<dyn SearchProvider + 'a>,
     top: usize,
 }
 
 impl<'a> SearchQuery<'a> {
-    /// Create a new search query.
+    /// Create a new search query that searches with [Serper](https://serper.dev).
     pub fn new(query: &'a str, api_key: &'a str, top_n: usize) -> Self {
+        Self::with_provider(query, Serper::new(api_key), top_n)
+    }
+
+    /// Create a new search query that searches with any [`SearchProvider`].
+    pub fn with_provider(query: &'a str, provider: impl SearchProvider + 'a, top_n: usize) -> Self {
         Self {
             query,
-            api_key,
+            provider: Box::new(provider),
             top: top_n,
         }
     }
@@ -52,18 +65,72 @@ impl<'a> SearchQuery<'a> {
 #[async_trait::async_trait]
 impl IntoDocuments for SearchQuery<'_> {
     async fn into_documents(self) -> anyhow::Result<Vec<Document>> {
-        let mut search_results = search(self.api_key, self.query).await?;
+        self.provider.search_documents(self.query, self.top).await
+    }
+}
 
-        let mut documents = vec![];
-        search_results.organic.shuffle(&mut rand::thread_rng());
+/// The endpoint of the Serper search API.
+const SERPER_ENDPOINT: &str = "https://google.serper.dev/search";
+
+/// A [`SearchProvider`] for the [Serper](https://serper.dev) Google search API.
+#[derive(Debug, Clone)]
+pub struct Serper {
+    api_key: Option<String>,
+    endpoint: Url,
+}
 
-        for result in search_results.organic.into_iter().take(self.top) {
-            if let Some(link) = &result.link {
-                documents.push(get_article(Url::parse(link)?).await?);
-            }
+impl Serper {
+    /// Create a new provider with a Serper API key.
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: Some(api_key.into()),
+            endpoint: Url::parse(SERPER_ENDPOINT).unwrap(),
         }
+    }
+
+    /// Create a new provider with the API key in the `SERPER_API_KEY` environment variable. If the variable is not set, searches return an error.
+    pub fn from_env() -> Self {
+        Self {
+            api_key: std::env::var("SERPER_API_KEY").ok(),
+            ..Self::new(String::new())
+        }
+    }
+
+    /// Set the URL of the search endpoint. (default: `https://google.serper.dev/search`)
+    pub fn with_endpoint(mut self, endpoint: Url) -> Self {
+        self.endpoint = endpoint;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchProvider for Serper {
+    async fn search(&self, query: &str, top_n: usize) -> anyhow::Result<Vec<WebSearchResult>> {
+        let api_key = self
+            .api_key
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("The SERPER_API_KEY environment variable is not set"))?;
+        let mut response: SearchResult = send_json(|client| {
+            client
+                .post(self.endpoint.clone())
+                .header("X-API-KEY", api_key)
+                .json(&serde_json::json!({
+                    "q": query
+                }))
+        })
+        .await?;
 
-        Ok(documents)
+        response.organic.sort_by_key(|result| result.position);
+        Ok(WebSearchResult::ranked(
+            response.organic.into_iter().filter_map(|result| {
+                Some((
+                    result.title.unwrap_or_default(),
+                    result.link?,
+                    result.snippet,
+                ))
+            }),
+            top_n,
+        ))
     }
 }
 
@@ -127,8 +194,9 @@ pub struct RelatedSearches {
     pub query: String,
 }
 
+#[deprecated(note = "Use `Serper::search` instead, which is sent with the shared `Fetcher`")]
 pub async fn search(api_key: &str, query: &str) -> Result<SearchResult, reqwest::Error> {
-    let url = Url::parse("https://google.serper.dev/search").unwrap();
+    let url = Url::parse(SERPER_ENDPOINT).unwrap();
     let client = reqwest::Client::new();
     let res = client
         .post(url)
@@ -137,15 +205,59 @@ pub async fn search(api_key: &str, query: &str) -> Result<SearchResult, reqwest:
             "q": query
         }))
         .send()
-        .await
-        .unwrap();
+        .await?;
     res.json().await
 }
 
 #[tokio::test]
+#[allow(deprecated)]
 async fn search_result() {
     if let Some(key) = option_env!("SERPER_API_KEY") {
         let result = search(key, "apple inc").await.unwrap();
         println!("{:#?}", result);
     }
 }
+
+#[tokio::test]
+async fn serper_results_are_ranked_by_position() {
+    use axum::http::HeaderMap;
+
+    let router = axum::Router::new().route(
+        "/search",
+        axum::routing::post(
+            |headers: HeaderMap, axum::Json(body): axum::Json<serde_json::Value>| async move {
+                assert_eq!(headers["x-api-key"], "secret");
+                assert_eq!(body["q"], "floneum");
+                axum::Json(serde_json::json!({
+                    "organic": [
+                        {"title": "GitHub", "link": "https://github.com/floneum/floneum", "position": 2},
+                        {"title": "Floneum", "link": "https://floneum.com/", "snippet": "A visual editor", "position": 1},
+                        {"title": "No link", "position": 3}
+                    ]
+                }))
+            },
+        ),
+    );
+    let endpoint = crate::context::fetch::test_server(router)
+        .await
+        .join("/search")
+        .unwrap();
+
+    let results = Serper::new("secret")
+        .with_endpoint(endpoint)
+        .search("floneum", 5)
+        .await
+        .unwrap();
+    let urls = results
+        .iter()
+        .map(|result| (result.rank, result.url.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        urls,
+        [
+            (1, "https://floneum.com/"),
+            (2, "https://github.com/floneum/floneum")
+        ]
+    );
+    assert_eq!(results[0].snippet, "A visual editor");
+}
@@ -0,0 +1,121 @@
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use url::Url;
+
+use crate::context::document::{Document, IntoDocument};
+use crate::context::Fetcher;
+
+/// A result from a [`SearchProvider`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct WebSearchResult {
+    /// The title of the page.
+    pub title: String,
+    /// The URL of the page.
+    pub url: Url,
+    /// A short snippet of the page that matches the query. This may be empty if the search engine doesn't return snippets.
+    pub snippet: String,
+    /// The position of the result in the search results, starting at 1.
+    pub rank: usize,
+}
+
+impl WebSearchResult {
+    /// Rank a list of `(title, url, snippet)` results in the order they are listed. Results with an invalid URL are skipped.
+    pub(crate) fn ranked(
+        results: impl IntoIterator<Item = (String, String, String)>,
+        top_n: usize,
+    ) -> Vec<Self> {
+        results
+            .into_iter()
+            .filter_map(|(title, url, snippet)| {
+                let url = Url::parse(url.trim()).ok()?;
+                Some((title, url, snippet))
+            })
+            .take(top_n)
+            .enumerate()
+            .map(|(i, (title, url, snippet))| Self {
+                title,
+                url,
+                snippet,
+                rank: i + 1,
+            })
+            .collect()
+    }
+
+    /// Fetch the page the result links to and extract the article.
+    pub async fn article(&self) -> anyhow::Result<Document> {
+        self.url.clone().into_document().await
+    }
+}
+
+#[async_trait::async_trait]
+impl IntoDocument for WebSearchResult {
+    async fn into_document(self) -> anyhow::Result<Document> {
+        self.article().await
+    }
+}
+
+/// A web search engine that can be used with [`SearchQuery`](super::SearchQuery) and [`WebSearchTool`](crate::tool::WebSearchTool).
+///
+/// Search providers only return the results from the search engine. Use [`WebSearchResult::article`] to fetch the pages.
+///
+/// # Example
+/// ```rust, no_run
+/// use kalosm_language::prelude::*;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let searxng = SearxNg::new(Url::parse("http://localhost:8080").unwrap());
+///     for result in searxng.search("What is Floneum?", 5).await.unwrap() {
+///         println!("{}. {} ({})\n{}", result.rank, result.title, result.url, result.snippet);
+///     }
+/// }
+/// ```
+#[async_trait::async_trait]
+pub trait SearchProvider: Send + Sync {
+    /// Search the web and return at most `top_n` results, ordered by rank.
+    async fn search(&self, query: &str, top_n: usize) -> anyhow::Result<Vec<WebSearchResult>>;
+
+    /// Search the web and fetch the articles of at most `top_n` results. Pages that can't be read are skipped.
+    async fn search_documents(&self, query: &str, top_n: usize) -> anyhow::Result<Vec<Document>> {
+        let results = self.search(query, top_n).await?;
+        let mut documents = Vec::new();
+        for result in results {
+            match result.article().await {
+                Ok(document) => documents.push(document),
+                Err(err) => tracing::warn!("Error reading {}: {}", result.url, err),
+            }
+        }
+        Ok(documents)
+    }
+}
+
+#[async_trait::async_trait]
+impl<P: SearchProvider + ?Sized> SearchProvider for Box<P> {
+    async fn search(&self, query: &str, top_n: usize) -> anyhow::Result<Vec<WebSearchResult>> {
+        (**self).search(query, top_n).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<P: SearchProvider + ?Sized> SearchProvider for Arc<P> {
+    async fn search(&self, query: &str, top_n: usize) -> anyhow::Result<Vec<WebSearchResult>> {
+        (**self).search(query, top_n).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<P: SearchProvider + ?Sized> SearchProvider for &P {
+    async fn search(&self, query: &str, top_n: usize) -> anyhow::Result<Vec<WebSearchResult>> {
+        (**self).search(query, top_n).await
+    }
+}
+
+/// Send a request to a search engine with the [global fetcher](Fetcher::global) and parse the JSON response.
+pub(crate) async fn send_json<T: DeserializeOwned>(
+    request: impl Fn(&reqwest::Client) -> reqwest::RequestBuilder,
+) -> anyhow::Result<T> {
+    let response = Fetcher::global()
+        .send(|client| request(client).header(reqwest::header::ACCEPT, "application/json"))
+        .await?;
+    Ok(serde_json::from_slice(response.bytes())?)
+}
@@ -0,0 +1,146 @@
+use url::Url;
+
+use super::provider::{send_json, SearchProvider, WebSearchResult};
+
+/// A [`SearchProvider`] for a [SearxNG](https://docs.searxng.org) instance.
+///
+/// SearxNG is a metasearch engine you can host yourself, so it doesn't require an API key. The instance must have the `json`
+/// format enabled in the `search.formats` section of its settings.
+///
+/// # Example
+/// ```rust, no_run
+/// use kalosm_language::prelude::*;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let searxng = SearxNg::new(Url::parse("http://localhost:8080").unwrap()).with_language("en");
+///     let documents = SearchQuery::with_provider("What is Floneum?", searxng, 5)
+///         .into_documents()
+///         .await
+///         .unwrap();
+///     println!("{:#?}", documents);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SearxNg {
+    url: Url,
+    language: Option<String>,
+    categories: Option<String>,
+}
+
+impl SearxNg {
+    /// Create a new provider for the SearxNG instance at a URL.
+    pub fn new(mut url: Url) -> Self {
+        // Make sure the search endpoint is joined to the path of the instance instead of replacing the last segment
+        if !url.path().ends_with('/') {
+            let path = format!("{}/", url.path());
+            url.set_path(&path);
+        }
+        Self {
+            url,
+            language: None,
+            categories: None,
+        }
+    }
+
+    /// Set the language of the search results, for example `en` or `de-DE`.
+    pub fn with_language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Set the comma separated categories to search, for example `general,science`. (default: the instance's default categories)
+    pub fn with_categories(mut self, categories: impl Into<String>) -> Self {
+        self.categories = Some(categories.into());
+        self
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct SearxNgResponse {
+    #[serde(default)]
+    results: Vec<SearxNgResult>,
+}
+
+#[derive(serde::Deserialize)]
+struct SearxNgResult {
+    url: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    content: String,
+}
+
+#[async_trait::async_trait]
+impl SearchProvider for SearxNg {
+    async fn search(&self, query: &str, top_n: usize) -> anyhow::Result<Vec<WebSearchResult>> {
+        let mut url = self.url.join("search")?;
+        {
+            let mut pairs = url.query_pairs_mut();
+            pairs.append_pair("q", query).append_pair("format", "json");
+            if let Some(language) = &self.language {
+                pairs.append_pair("language", language);
+            }
+            if let Some(categories) = &self.categories {
+                pairs.append_pair("categories", categories);
+            }
+        }
+        let response: SearxNgResponse = send_json(|client| client.get(url.clone())).await?;
+
+        Ok(WebSearchResult::ranked(
+            response
+                .results
+                .into_iter()
+                .map(|result| (result.title, result.url, result.content)),
+            top_n,
+        ))
+    }
+}
+
+#[tokio::test]
+async fn searxng_results_are_parsed() {
+    use axum::extract::Query;
+    use std::collections::HashMap;
+
+    let router = axum::Router::new().route(
+        "/search",
+        axum::routing::get(|Query(query): Query<HashMap<String, String>>| async move {
+            assert_eq!(query["q"], "floneum");
+            assert_eq!(query["format"], "json");
+            assert_eq!(query["language"], "en");
+            axum::Json(serde_json::json!({
+                "query": "floneum",
+                "results": [
+                    {"url": "https://floneum.com/", "title": "Floneum", "content": "A visual editor for AI workflows", "engine": "duckduckgo"},
+                    {"url": "not a url", "title": "Broken", "content": ""},
+                    {"url": "https://github.com/floneum/floneum", "title": "GitHub", "content": "Source code"},
+                    {"url": "https://docs.rs/kalosm", "title": "Docs"}
+                ]
+            }))
+        }),
+    );
+    let url = crate::context::fetch::test_server(router).await;
+
+    let results = SearxNg::new(url)
+        .with_language("en")
+        .search("floneum", 2)
+        .await
+        .unwrap();
+    assert_eq!(
+        results,
+        [
+            WebSearchResult {
+                title: "Floneum".to_string(),
+                url: Url::parse("https://floneum.com/").unwrap(),
+                snippet: "A visual editor for AI workflows".to_string(),
+                rank: 1,
+            },
+            WebSearchResult {
+                title: "GitHub".to_string(),
+                url: Url::parse("https://github.com/floneum/floneum").unwrap(),
+                snippet: "Source code".to_string(),
+                rank: 2,
+            },
+        ]
+    );
+}
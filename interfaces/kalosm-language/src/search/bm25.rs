@@ -0,0 +1,508 @@
+//! A persistent BM25 keyword index that can be used alongside a [`VectorDB`](crate::vector_db::VectorDB) for hybrid retrieval.
+
+use std::collections::HashMap;
+
+use heed::types::{Bytes, SerdeBincode, Str, U32};
+use heed::{byteorder::BigEndian, Database, EnvOpenOptions};
+use rust_stemmers::{Algorithm, Stemmer};
+use rustc_hash::FxHashSet;
+use serde::{Deserialize, Serialize};
+use whatlang::Lang;
+
+use crate::vector_db::EmbeddingId;
+
+/// The default list of english stopwords used by [`Bm25Tokenizer`].
+#[rustfmt::skip]
+pub const ENGLISH_STOPWORDS: &[&str] = &[
+    "a", "about", "above", "after", "again", "against", "all", "am", "an", "and", "any", "are",
+    "as", "at", "be", "because", "been", "before", "being", "below", "between", "both", "but",
+    "by", "can", "could", "did", "do", "does", "doing", "down", "during", "each", "few", "for",
+    "from", "further", "had", "has", "have", "having", "he", "her", "here", "hers", "herself",
+    "him", "himself", "his", "how", "i", "if", "in", "into", "is", "it", "its", "itself", "just",
+    "me", "more", "most", "my", "myself", "no", "nor", "not", "now", "of", "off", "on", "once",
+    "only", "or", "other", "our", "ours", "ourselves", "out", "over", "own", "same", "she",
+    "should", "so", "some", "such", "than", "that", "the", "their", "theirs", "them",
+    "themselves", "then", "there", "these", "they", "this", "those", "through", "to", "too",
+    "under", "until", "up", "very", "was", "we", "were", "what", "when", "where", "which",
+    "while", "who", "whom", "why", "will", "with", "would", "you", "your", "yours", "yourself",
+    "yourselves",
+];
+
+/// A tokenizer for the [`Bm25Index`] that splits text into lowercase words, removes stopwords and stems the remaining words.
+#[derive(Debug, Clone)]
+pub struct Bm25Tokenizer {
+    stemmer: Option<Algorithm>,
+    stopwords: FxHashSet<String>,
+}
+
+impl Default for Bm25Tokenizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bm25Tokenizer {
+    /// Create a new tokenizer with english stemming and the [`ENGLISH_STOPWORDS`] list.
+    pub fn new() -> Self {
+        Self {
+            stemmer: Some(Algorithm::English),
+            stopwords: ENGLISH_STOPWORDS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Set the language the tokenizer stems words with. If there is no stemmer for the language, words will not be stemmed.
+    ///
+    /// NOTE: This does not change the stopwords list. Use [`Bm25Tokenizer::with_stopwords`] to set stopwords for the language.
+    pub fn with_language(mut self, language: Lang) -> Self {
+        self.stemmer = stemmer_for_language(language);
+        self
+    }
+
+    /// Disable stemming.
+    pub fn without_stemming(mut self) -> Self {
+        self.stemmer = None;
+        self
+    }
+
+    /// Replace the list of stopwords that are removed from the text.
+    pub fn with_stopwords(
+        mut self,
+        stopwords: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.stopwords = stopwords
+            .into_iter()
+            .map(|word| word.into().to_lowercase())
+            .collect();
+        self
+    }
+
+    /// Split a string into a list of normalized terms.
+    pub fn tokenize(&self, text: &str) -> Vec<String> {
+        let stemmer = self.stemmer.map(Stemmer::create);
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_lowercase())
+            .filter(|word| !self.stopwords.contains(word))
+            .map(|word| match &stemmer {
+                Some(stemmer) => stemmer.stem(&word).into_owned(),
+                None => word,
+            })
+            .collect()
+    }
+}
+
+fn stemmer_for_language(language: Lang) -> Option<Algorithm> {
+    Some(match language {
+        Lang::Ara => Algorithm::Arabic,
+        Lang::Dan => Algorithm::Danish,
+        Lang::Nld => Algorithm::Dutch,
+        Lang::Eng => Algorithm::English,
+        Lang::Fin => Algorithm::Finnish,
+        Lang::Fra => Algorithm::French,
+        Lang::Deu => Algorithm::German,
+        Lang::Ell => Algorithm::Greek,
+        Lang::Hun => Algorithm::Hungarian,
+        Lang::Ita => Algorithm::Italian,
+        Lang::Nob => Algorithm::Norwegian,
+        Lang::Por => Algorithm::Portuguese,
+        Lang::Ron => Algorithm::Romanian,
+        Lang::Rus => Algorithm::Russian,
+        Lang::Spa => Algorithm::Spanish,
+        Lang::Swe => Algorithm::Swedish,
+        Lang::Tam => Algorithm::Tamil,
+        Lang::Tur => Algorithm::Turkish,
+        _ => return None,
+    })
+}
+
+/// One occurrence of a term in the index. The term and the id of the document are stored in the key of the posting.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Posting {
+    term_frequency: u32,
+    document_length: u32,
+}
+
+/// The prefix of the keys of every posting of a term: the term followed by a zero byte. Terms never contain a zero byte.
+fn term_prefix(term: &str) -> Vec<u8> {
+    let mut prefix = term.as_bytes().to_vec();
+    prefix.push(0);
+    prefix
+}
+
+/// The key of the posting of a term in a document: the [`term_prefix`] followed by the big endian id of the document.
+fn posting_key(term: &str, id: u32) -> Vec<u8> {
+    let mut key = term_prefix(term);
+    key.extend_from_slice(&id.to_be_bytes());
+    key
+}
+
+const DOCUMENT_COUNT_KEY: &str = "document-count";
+const TOTAL_LENGTH_KEY: &str = "total-length";
+
+/// A persistent [BM25](https://en.wikipedia.org/wiki/Okapi_BM25) inverted index for keyword search.
+///
+/// The index maps [`EmbeddingId`]s to the text they were created from so keyword search results can be combined with the results of a [`VectorDB`](crate::vector_db::VectorDB) search.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// # use kalosm_language::prelude::*;
+/// let index = Bm25Index::new().unwrap();
+/// index.add_document(EmbeddingId(0), "Kalosm can be used to build local AI applications").unwrap();
+/// index.add_document(EmbeddingId(1), "The quick brown fox jumps over the lazy dog").unwrap();
+///
+/// let results = index.search("building applications", 1).unwrap();
+/// assert_eq!(results[0].value, EmbeddingId(0));
+/// ```
+pub struct Bm25Index {
+    env: heed::Env,
+    // Every posting is stored under its own key so adding or removing a document only touches the postings of that document
+    postings: Database<Bytes, SerdeBincode<Posting>>,
+    documents: Database<U32<BigEndian>, SerdeBincode<Vec<(String, u32)>>>,
+    stats: Database<Str, SerdeBincode<u64>>,
+    tokenizer: Bm25Tokenizer,
+    k1: f32,
+    b: f32,
+}
+
+impl Bm25Index {
+    /// Create a new temporary keyword index.
+    pub fn new() -> heed::Result<Self> {
+        let dir = tempfile::tempdir()?;
+
+        Self::new_at(dir.path())
+    }
+
+    /// Create a new keyword index at the given path.
+    pub fn new_at(path: impl AsRef<std::path::Path>) -> heed::Result<Self> {
+        const ONE_GIB: usize = 1024 * 1024 * 1024;
+
+        std::fs::create_dir_all(&path)?;
+
+        let env = unsafe {
+            EnvOpenOptions::new()
+                .map_size(ONE_GIB)
+                .max_dbs(3)
+                .open(path)
+        }?;
+
+        Self::from_env(env)
+    }
+
+    /// Create a keyword index inside an existing heed environment. The environment must allow at least three named databases.
+    ///
+    /// This can be used to store the keyword index next to a vector database:
+    /// ```rust, no_run
+    /// # use kalosm_language::prelude::*;
+    /// let db = VectorDB::<UnknownVectorSpace>::new().unwrap();
+    /// let index = Bm25Index::from_env(db.env().clone()).unwrap();
+    /// ```
+    pub fn from_env(env: heed::Env) -> heed::Result<Self> {
+        let mut wtxn = env.write_txn()?;
+        let postings = env.create_database(&mut wtxn, Some("bm25-term-postings"))?;
+        let documents = env.create_database(&mut wtxn, Some("bm25-documents"))?;
+        let stats = env.create_database(&mut wtxn, Some("bm25-stats"))?;
+        wtxn.commit()?;
+
+        Ok(Self {
+            env,
+            postings,
+            documents,
+            stats,
+            tokenizer: Bm25Tokenizer::default(),
+            k1: 1.2,
+            b: 0.75,
+        })
+    }
+
+    /// Set the tokenizer used to split documents and queries into terms.
+    ///
+    /// NOTE: Changing the tokenizer of an index that already contains documents will make the existing terms inconsistent with new queries.
+    pub fn with_tokenizer(mut self, tokenizer: Bm25Tokenizer) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// Set the term frequency saturation parameter. (default: 1.2)
+    pub fn with_k1(mut self, k1: f32) -> Self {
+        self.k1 = k1;
+        self
+    }
+
+    /// Set the document length normalization parameter. (default: 0.75)
+    pub fn with_b(mut self, b: f32) -> Self {
+        self.b = b;
+        self
+    }
+
+    /// Get the tokenizer the index uses.
+    pub fn tokenizer(&self) -> &Bm25Tokenizer {
+        &self.tokenizer
+    }
+
+    /// Add the text of a document to the index. If the id is already in the index, the old text is replaced.
+    pub fn add_document(&self, id: EmbeddingId, text: &str) -> anyhow::Result<()> {
+        self.add_documents([(id, text)])
+    }
+
+    /// Add a batch of documents to the index.
+    pub fn add_documents<T: AsRef<str>>(
+        &self,
+        documents: impl IntoIterator<Item = (EmbeddingId, T)>,
+    ) -> anyhow::Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+
+        for (id, text) in documents {
+            self.remove_in_txn(&mut wtxn, id)?;
+
+            let mut term_frequencies: HashMap<String, u32> = HashMap::new();
+            let mut document_length = 0;
+            for term in self.tokenizer.tokenize(text.as_ref()) {
+                *term_frequencies.entry(term).or_default() += 1;
+                document_length += 1;
+            }
+
+            for (term, &term_frequency) in &term_frequencies {
+                let posting = Posting {
+                    term_frequency,
+                    document_length,
+                };
+                self.postings
+                    .put(&mut wtxn, &posting_key(term, id.0), &posting)?;
+            }
+
+            let terms = term_frequencies.into_iter().collect::<Vec<_>>();
+            self.documents.put(&mut wtxn, &id.0, &terms)?;
+            self.update_stats(&mut wtxn, 1, document_length as i64)?;
+        }
+
+        wtxn.commit()?;
+
+        Ok(())
+    }
+
+    /// Remove a document from the index.
+    pub fn remove_document(&self, id: EmbeddingId) -> anyhow::Result<()> {
+        self.remove_documents([id])
+    }
+
+    /// Remove a batch of documents from the index.
+    pub fn remove_documents(
+        &self,
+        ids: impl IntoIterator<Item = EmbeddingId>,
+    ) -> anyhow::Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+        for id in ids {
+            self.remove_in_txn(&mut wtxn, id)?;
+        }
+        wtxn.commit()?;
+
+        Ok(())
+    }
+
+    fn remove_in_txn(&self, wtxn: &mut heed::RwTxn, id: EmbeddingId) -> anyhow::Result<()> {
+        let Some(terms) = self.documents.get(wtxn, &id.0)? else {
+            return Ok(());
+        };
+
+        let mut document_length = 0;
+        for (term, term_frequency) in terms {
+            document_length += term_frequency;
+            self.postings.delete(wtxn, &posting_key(&term, id.0))?;
+        }
+        self.documents.delete(wtxn, &id.0)?;
+        self.update_stats(wtxn, -1, -(document_length as i64))?;
+
+        Ok(())
+    }
+
+    fn update_stats(
+        &self,
+        wtxn: &mut heed::RwTxn,
+        document_count: i64,
+        total_length: i64,
+    ) -> anyhow::Result<()> {
+        for (key, change) in [
+            (DOCUMENT_COUNT_KEY, document_count),
+            (TOTAL_LENGTH_KEY, total_length),
+        ] {
+            let current = self.stats.get(wtxn, key)?.unwrap_or_default();
+            let new = (current as i64 + change).max(0) as u64;
+            self.stats.put(wtxn, key, &new)?;
+        }
+        Ok(())
+    }
+
+    /// Clear the keyword index.
+    pub fn clear(&self) -> anyhow::Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+        self.postings.clear(&mut wtxn)?;
+        self.documents.clear(&mut wtxn)?;
+        self.stats.clear(&mut wtxn)?;
+        wtxn.commit()?;
+
+        Ok(())
+    }
+
+    /// Get the number of documents in the index.
+    pub fn len(&self) -> anyhow::Result<usize> {
+        let rtxn = self.env.read_txn()?;
+        Ok(self
+            .stats
+            .get(&rtxn, DOCUMENT_COUNT_KEY)?
+            .unwrap_or_default() as usize)
+    }
+
+    /// Check if the index is empty.
+    pub fn is_empty(&self) -> anyhow::Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Get the N documents with the highest BM25 score for the given query. Documents that do not contain any of the query terms are not returned.
+    pub fn search(&self, query: &str, n: usize) -> anyhow::Result<Vec<Bm25SearchResult>> {
+        let rtxn = self.env.read_txn()?;
+        let document_count = self
+            .stats
+            .get(&rtxn, DOCUMENT_COUNT_KEY)?
+            .unwrap_or_default();
+        if document_count == 0 || n == 0 {
+            return Ok(Vec::new());
+        }
+        let total_length = self.stats.get(&rtxn, TOTAL_LENGTH_KEY)?.unwrap_or_default();
+        let average_length = (total_length as f32 / document_count as f32).max(1.0);
+
+        let mut terms = self.tokenizer.tokenize(query);
+        terms.sort();
+        terms.dedup();
+
+        let mut scores: HashMap<u32, f32> = HashMap::new();
+        for term in terms {
+            let prefix = term_prefix(&term);
+            let mut postings = Vec::new();
+            for item in self.postings.prefix_iter(&rtxn, &prefix)? {
+                let (key, posting) = item?;
+                let id = u32::from_be_bytes(key[prefix.len()..].try_into()?);
+                postings.push((id, posting));
+            }
+            if postings.is_empty() {
+                continue;
+            }
+            let document_frequency = postings.len() as f32;
+            let idf = (1.0
+                + (document_count as f32 - document_frequency + 0.5) / (document_frequency + 0.5))
+                .ln();
+            for (id, posting) in postings {
+                let term_frequency = posting.term_frequency as f32;
+                let length_ratio = posting.document_length as f32 / average_length;
+                let score = idf * term_frequency * (self.k1 + 1.0)
+                    / (term_frequency + self.k1 * (1.0 - self.b + self.b * length_ratio));
+                *scores.entry(id).or_default() += score;
+            }
+        }
+
+        let mut results = scores
+            .into_iter()
+            .map(|(id, score)| Bm25SearchResult {
+                score,
+                value: EmbeddingId(id),
+            })
+            .collect::<Vec<_>>();
+        results.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.value.cmp(&b.value)));
+        results.truncate(n);
+
+        Ok(results)
+    }
+}
+
+/// A result from a keyword search in a [`Bm25Index`].
+#[derive(Debug, Clone)]
+pub struct Bm25SearchResult {
+    /// The BM25 score of the document. Higher scores are better matches.
+    pub score: f32,
+    /// The id of the document.
+    pub value: EmbeddingId,
+}
+
+#[test]
+fn tokenizer_stems_and_removes_stopwords() {
+    let tokenizer = Bm25Tokenizer::new();
+    assert_eq!(
+        tokenizer.tokenize("The runners were Running, and the dog RUNS!"),
+        vec!["runner", "run", "dog", "run"]
+    );
+
+    let tokenizer = Bm25Tokenizer::new()
+        .without_stemming()
+        .with_stopwords(["dog"]);
+    assert_eq!(tokenizer.tokenize("The dog runs"), vec!["the", "runs"]);
+}
+
+#[test]
+fn bm25_ranks_matching_documents() {
+    let index = Bm25Index::new().unwrap();
+    index
+        .add_documents([
+            (
+                EmbeddingId(0),
+                "Kalosm is a library for local AI applications",
+            ),
+            (
+                EmbeddingId(1),
+                "The quick brown fox jumps over the lazy dog",
+            ),
+            (EmbeddingId(2), "Foxes are quick. A fox can outrun a dog."),
+        ])
+        .unwrap();
+    assert_eq!(index.len().unwrap(), 3);
+
+    let results = index.search("quick fox", 3).unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|result| result.value != EmbeddingId(0)));
+
+    let results = index.search("local applications", 3).unwrap();
+    assert_eq!(results[0].value, EmbeddingId(0));
+
+    // Replacing a document removes the old terms
+    index
+        .add_document(EmbeddingId(0), "A story about a fox")
+        .unwrap();
+    assert!(index.search("applications", 3).unwrap().is_empty());
+    assert_eq!(index.len().unwrap(), 3);
+
+    index.remove_document(EmbeddingId(1)).unwrap();
+    index.remove_document(EmbeddingId(2)).unwrap();
+    let results = index.search("fox", 3).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].value, EmbeddingId(0));
+
+    index.clear().unwrap();
+    assert!(index.is_empty().unwrap());
+}
+
+#[test]
+fn bm25_postings_of_terms_with_a_shared_prefix_are_separate() {
+    let index = Bm25Index::new()
+        .unwrap()
+        .with_tokenizer(Bm25Tokenizer::new().without_stemming());
+    index
+        .add_documents([
+            (EmbeddingId(0), "run"),
+            (EmbeddingId(1), "runner"),
+            (EmbeddingId(256), "run run"),
+        ])
+        .unwrap();
+
+    let results = index.search("run", 3).unwrap();
+    let mut ids = results
+        .iter()
+        .map(|result| result.value)
+        .collect::<Vec<_>>();
+    ids.sort();
+    assert_eq!(ids, [EmbeddingId(0), EmbeddingId(256)]);
+
+    index.remove_document(EmbeddingId(256)).unwrap();
+    let results = index.search("run", 3).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].value, EmbeddingId(0));
+    assert_eq!(index.search("runner", 3).unwrap()[0].value, EmbeddingId(1));
+}
@@ -1,19 +1,71 @@
 //! The index module contains different types of search indexes that can be used to search for [`crate::context::Document`]s created from [`crate::context::IntoDocument`] or [`crate::context::IntoDocuments`]
 
+mod bm25;
+pub use bm25::*;
 mod postprocessing;
 mod preprocessing;
 pub use preprocessing::*;
+mod tokens;
+pub use tokens::*;
 
+use crate::context::{Document, DocumentMetadata, Provenance};
 use kalosm_language_model::*;
 use std::{fmt::Debug, ops::Range};
 
 /// A document snippet that can be used to display a snippet of a document.
+///
+/// Create chunks with [`Chunk::new`] or [`Chunk::from_document`]. More fields may be added to chunks in the future.
 #[derive(Clone)]
+#[non_exhaustive]
 pub struct Chunk<S: VectorSpace> {
     /// The byte range of the chunk in the original document.
     pub byte_range: Range<usize>,
     /// The embeddings of the chunk.
     pub embeddings: Vec<Embedding<S>>,
+    /// Where the text of the chunk came from in the source of the document, like the pdf pages or markdown sections the chunk overlaps.
+    ///
+    /// Chunkers fill this in with [`Document::provenance`](crate::context::Document::provenance).
+    pub provenance: Vec<Provenance>,
+    /// The metadata of the document the chunk is from, like the source, authors and MIME type.
+    pub metadata: DocumentMetadata,
+}
+
+impl<S: VectorSpace> Chunk<S> {
+    /// Create a new chunk without any provenance or metadata.
+    pub fn new(byte_range: Range<usize>, embeddings: Vec<Embedding<S>>) -> Self {
+        Self {
+            byte_range,
+            embeddings,
+            provenance: Vec::new(),
+            metadata: DocumentMetadata::default(),
+        }
+    }
+
+    /// Create a new chunk of a document. The provenance of the byte range and the metadata are copied from the document.
+    pub fn from_document(
+        document: &Document,
+        byte_range: Range<usize>,
+        embeddings: Vec<Embedding<S>>,
+    ) -> Self {
+        Self {
+            provenance: document.provenance(byte_range.clone()),
+            metadata: document.metadata().clone(),
+            byte_range,
+            embeddings,
+        }
+    }
+
+    /// Set where the text of the chunk came from in the source of the document.
+    pub fn with_provenance(mut self, provenance: Vec<Provenance>) -> Self {
+        self.provenance = provenance;
+        self
+    }
+
+    /// Set the metadata of the document the chunk is from.
+    pub fn with_metadata(mut self, metadata: DocumentMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
 }
 
 impl<S: VectorSpace> Debug for Chunk<S> {
@@ -21,6 +73,8 @@ impl<S: VectorSpace> Debug for Chunk<S> {
         f.debug_struct("Chunk")
             .field("byte_range", &self.byte_range)
             .field("embeddings", &self.embeddings)
+            .field("provenance", &self.provenance)
+            .field("metadata", &self.metadata)
             .finish()
     }
 }
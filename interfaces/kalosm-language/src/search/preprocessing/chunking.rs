@@ -213,10 +213,7 @@ impl Chunker for ChunkStrategy {
         }
         let embeddings = embedder.embed_vec(documents).await?;
         for (byte_range, embedding) in chunk_ranges.into_iter().zip(embeddings) {
-            chunks.push(Chunk {
-                byte_range,
-                embeddings: vec![embedding],
-            });
+            chunks.push(Chunk::from_document(document, byte_range, vec![embedding]));
         }
         Ok(chunks)
     }
@@ -232,7 +229,8 @@ impl Chunker for ChunkStrategy {
     {
         let mut chunks = Vec::new();
         let mut chunk_strings = Vec::new();
-        for document in documents {
+        let documents = documents.into_iter().collect::<Vec<_>>();
+        for document in &documents {
             let body = document.body();
             let chunk = self.chunk_str(body);
             for byte_range in &chunk {
@@ -245,14 +243,11 @@ impl Chunker for ChunkStrategy {
         let mut embeddings = embeddings.drain(..);
         let mut embedded_chunks = Vec::new();
 
-        for chunk in chunks {
+        for (document, chunk) in documents.into_iter().zip(chunks) {
             let mut document_chunks = Vec::new();
             for byte_range in chunk {
                 let embedding = embeddings.next().unwrap();
-                document_chunks.push(Chunk {
-                    byte_range,
-                    embeddings: vec![embedding],
-                });
+                document_chunks.push(Chunk::from_document(document, byte_range, vec![embedding]));
             }
             embedded_chunks.push(document_chunks);
         }
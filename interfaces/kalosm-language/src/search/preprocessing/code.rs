@@ -0,0 +1,918 @@
+use kalosm_language_model::Embedder;
+use std::ops::Range;
+
+use super::Chunker;
+use crate::context::{CodeLanguage, Document, Provenance};
+use crate::search::Chunk;
+
+/// A [`Chunker`] that splits source code along the boundaries of functions, impls, classes and other items.
+///
+/// The source is parsed with a lightweight heuristic parser for Rust, Python, TypeScript and JavaScript. Items that fit in the maximum
+/// chunk size are kept whole, items that are too large are split into their children, and small neighboring items are merged together.
+/// Each chunk is embedded along with the path of its enclosing scopes (like `impl Parser > fn parse`), and that path is recorded in the
+/// [`Provenance`] of the chunk.
+///
+/// The language is read from the metadata of documents loaded with [`CodeDocument`](crate::context::CodeDocument), or it can be set with
+/// [`CodeChunker::with_language`]. Source code in an unknown language is split along lines.
+///
+/// # Example
+/// ```rust, no_run
+/// use kalosm_language::prelude::*;
+/// use std::path::PathBuf;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let document = CodeDocument::try_from(PathBuf::from("./src/main.rs"))
+///         .unwrap()
+///         .into_document()
+///         .await
+///         .unwrap();
+///     let bert = Bert::new().await.unwrap();
+///     let chunks = CodeChunker::new()
+///         .with_max_chunk_size(1000)
+///         .chunk(&document, &bert)
+///         .await
+///         .unwrap();
+///     for chunk in chunks {
+///         println!("{:?}: {}", chunk.provenance, &document.body()[chunk.byte_range]);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CodeChunker {
+    language: Option<CodeLanguage>,
+    max_chunk_size: usize,
+}
+
+impl Default for CodeChunker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A span of source code created by a [`CodeChunker`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeSpan {
+    /// The byte range of the span in the source code.
+    pub byte_range: Range<usize>,
+    /// The items the span is inside of, from the outermost item to the innermost item, like `["impl Parser", "fn parse"]`.
+    pub scope: Vec<String>,
+}
+
+impl CodeChunker {
+    /// Create a new [`CodeChunker`] that reads the language from the metadata of the document. (default max chunk size: 1500 bytes)
+    pub const fn new() -> Self {
+        Self {
+            language: None,
+            max_chunk_size: 1500,
+        }
+    }
+
+    /// Set the language of the source code instead of reading it from the metadata of the document.
+    pub fn with_language(mut self, language: CodeLanguage) -> Self {
+        self.language = Some(language);
+        self
+    }
+
+    /// Set the maximum size of a chunk in bytes. Items larger than this are split into their children, or along lines if they have no children. (default: 1500)
+    pub fn with_max_chunk_size(mut self, max_chunk_size: usize) -> Self {
+        self.max_chunk_size = max_chunk_size.max(1);
+        self
+    }
+
+    /// Split source code in the language of the chunker into spans along item boundaries.
+    pub fn split(&self, source: &str) -> Vec<CodeSpan> {
+        self.split_as(source, self.language)
+    }
+
+    fn split_as(&self, source: &str, language: Option<CodeLanguage>) -> Vec<CodeSpan> {
+        let items = match language {
+            Some(CodeLanguage::Python) => parse_indented(source),
+            Some(language) => parse_braced(source, language),
+            None => Vec::new(),
+        };
+        let mut spans = Vec::new();
+        self.split_items(source, 0..source.len(), &items, &[], &mut spans);
+        spans
+    }
+
+    /// Split a range of source code with the items inside of it.
+    fn split_items(
+        &self,
+        source: &str,
+        range: Range<usize>,
+        items: &[CodeItem],
+        scope: &[String],
+        spans: &mut Vec<CodeSpan>,
+    ) {
+        let mut segments = Vec::new();
+        let mut position = range.start;
+        for item in items {
+            segments.push(Segment::Gap(position..item.byte_range.start));
+            segments.push(Segment::Item(item));
+            position = item.byte_range.end;
+        }
+        segments.push(Segment::Gap(position..range.end));
+
+        let mut group = Group::default();
+        for segment in segments {
+            let byte_range = segment.byte_range();
+            if byte_range.is_empty() || source[byte_range.clone()].trim().is_empty() {
+                continue;
+            }
+            if byte_range.len() > self.max_chunk_size {
+                group.finish(source, scope, spans);
+                match segment {
+                    Segment::Item(item) => {
+                        let mut scope = scope.to_vec();
+                        scope.push(item.name.clone());
+                        if item.children.is_empty() {
+                            self.split_lines(source, byte_range, &scope, spans);
+                        } else {
+                            self.split_items(source, byte_range, &item.children, &scope, spans);
+                        }
+                    }
+                    Segment::Gap(_) => self.split_lines(source, byte_range, scope, spans),
+                }
+                continue;
+            }
+            // Punctuation between items like a closing brace or semicolon stays with the chunk before it
+            if !segment.is_significant(source) {
+                if group.byte_range.is_some() {
+                    group.push(source, segment);
+                }
+                continue;
+            }
+            let fits = match &group.byte_range {
+                Some(group) => byte_range.end - group.start <= self.max_chunk_size,
+                None => true,
+            };
+            if !fits {
+                group.finish(source, scope, spans);
+            }
+            group.push(source, segment);
+        }
+        group.finish(source, scope, spans);
+    }
+
+    /// Split a range of source code that is too large for one chunk along lines.
+    fn split_lines(
+        &self,
+        source: &str,
+        range: Range<usize>,
+        scope: &[String],
+        spans: &mut Vec<CodeSpan>,
+    ) {
+        let mut start = range.start;
+        let mut end = range.start;
+        for line in source[range.clone()].split_inclusive('\n') {
+            if end > start && end + line.len() - start > self.max_chunk_size {
+                push_span(source, start..end, scope.to_vec(), spans);
+                start = end;
+            }
+            end += line.len();
+        }
+        push_span(source, start..end, scope.to_vec(), spans);
+    }
+}
+
+impl Chunker for CodeChunker {
+    async fn chunk<E: Embedder + Send>(
+        &self,
+        document: &Document,
+        embedder: &E,
+    ) -> anyhow::Result<Vec<Chunk<E::VectorSpace>>> {
+        let language = self
+            .language
+            .or_else(|| CodeLanguage::from_metadata(document.metadata()));
+        let body = document.body();
+        let spans = self.split_as(body, language);
+
+        // Embed each chunk with the path of the items it is inside of so the embedding has the context of the chunk
+        let texts = spans
+            .iter()
+            .map(|span| {
+                let text = &body[span.byte_range.clone()];
+                if span.scope.is_empty() {
+                    text.to_string()
+                } else {
+                    format!("{}\n{text}", span.scope.join(" > "))
+                }
+            })
+            .collect();
+        let embeddings = embedder.embed_vec(texts).await?;
+
+        Ok(spans
+            .into_iter()
+            .zip(embeddings)
+            .map(|(span, embedding)| {
+                let mut provenance = document.provenance(span.byte_range.clone());
+                if !span.scope.is_empty() {
+                    provenance.push(Provenance::heading_path(span.scope));
+                }
+                Chunk::new(span.byte_range, vec![embedding])
+                    .with_provenance(provenance)
+                    .with_metadata(document.metadata().clone())
+            })
+            .collect())
+    }
+}
+
+/// Push a span with the whitespace around it trimmed.
+fn push_span(source: &str, range: Range<usize>, scope: Vec<String>, spans: &mut Vec<CodeSpan>) {
+    let text = &source[range.clone()];
+    let trimmed = text.trim_start();
+    let start = range.start + text.len() - trimmed.len();
+    let end = start + trimmed.trim_end().len();
+    if start < end {
+        spans.push(CodeSpan {
+            byte_range: start..end,
+            scope,
+        });
+    }
+}
+
+/// Neighboring segments of source code that are merged into one chunk.
+#[derive(Default)]
+struct Group<'a> {
+    byte_range: Option<Range<usize>>,
+    /// The segments in the group that contain more than punctuation
+    significant: Vec<Segment<'a>>,
+}
+
+impl<'a> Group<'a> {
+    fn push(&mut self, source: &str, segment: Segment<'a>) {
+        let byte_range = segment.byte_range();
+        self.byte_range = Some(match self.byte_range.take() {
+            Some(group) => group.start..byte_range.end,
+            None => byte_range.clone(),
+        });
+        if segment.is_significant(source) {
+            self.significant.push(segment);
+        }
+    }
+
+    fn finish(&mut self, source: &str, scope: &[String], spans: &mut Vec<CodeSpan>) {
+        let Some(byte_range) = self.byte_range.take() else {
+            return;
+        };
+        let mut scope = scope.to_vec();
+        // If the chunk is one item, the item is part of the scope
+        if let [Segment::Item(item)] = self.significant.as_slice() {
+            scope.push(item.name.clone());
+        }
+        self.significant.clear();
+        push_span(source, byte_range, scope, spans);
+    }
+}
+
+enum Segment<'a> {
+    Gap(Range<usize>),
+    Item(&'a CodeItem),
+}
+
+impl Segment<'_> {
+    fn byte_range(&self) -> Range<usize> {
+        match self {
+            Self::Gap(range) => range.clone(),
+            Self::Item(item) => item.byte_range.clone(),
+        }
+    }
+
+    /// Check if the segment contains more than punctuation.
+    fn is_significant(&self, source: &str) -> bool {
+        match self {
+            Self::Gap(range) => source[range.clone()].chars().any(|c| c.is_alphanumeric()),
+            Self::Item(_) => true,
+        }
+    }
+}
+
+/// An item in source code like a function, impl or class.
+#[derive(Debug)]
+struct CodeItem {
+    /// The kind and name of the item, like `fn parse`.
+    name: String,
+    /// The byte range of the item including the comments and attributes directly above it.
+    byte_range: Range<usize>,
+    children: Vec<CodeItem>,
+}
+
+fn attach(item: CodeItem, parent: Option<&mut CodeItem>, roots: &mut Vec<CodeItem>) {
+    match parent {
+        Some(parent) => parent.children.push(item),
+        None => roots.push(item),
+    }
+}
+
+/// Parse the items of a language that uses braces for blocks (Rust, TypeScript and JavaScript).
+fn parse_braced(source: &str, language: CodeLanguage) -> Vec<CodeItem> {
+    let bytes = source.as_bytes();
+    let rust = language == CodeLanguage::Rust;
+    let mut roots = Vec::new();
+    // Every open brace with the item it starts, if it starts one
+    let mut open: Vec<Option<CodeItem>> = Vec::new();
+    // The start of the current statement. The header of an item is everything between this and the opening brace
+    let mut statement_start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i = find(bytes, i, b"\n").unwrap_or(bytes.len());
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = find(bytes, i + 2, b"*/").map_or(bytes.len(), |end| end + 2);
+                continue;
+            }
+            b'"' => {
+                i = skip_string(bytes, i, b'"', !rust);
+                continue;
+            }
+            b'`' if !rust => {
+                i = skip_string(bytes, i, b'`', false);
+                continue;
+            }
+            b'\'' if !rust => {
+                i = skip_string(bytes, i, b'\'', true);
+                continue;
+            }
+            b'\'' => {
+                i = skip_rust_char(source, i);
+                continue;
+            }
+            b'r' if rust && raw_string_start(bytes, i) => {
+                i = skip_raw_string(bytes, i);
+                continue;
+            }
+            b'{' => {
+                let item =
+                    item_header(source, statement_start..i, language).map(|(start, name)| {
+                        CodeItem {
+                            name,
+                            byte_range: start..i,
+                            children: Vec::new(),
+                        }
+                    });
+                open.push(item);
+                statement_start = i + 1;
+            }
+            b'}' => {
+                if let Some(Some(mut item)) = open.pop() {
+                    item.byte_range.end = i + 1;
+                    attach(
+                        item,
+                        open.iter_mut().rev().find_map(Option::as_mut),
+                        &mut roots,
+                    );
+                }
+                statement_start = i + 1;
+            }
+            b';' => statement_start = i + 1,
+            _ => {}
+        }
+        i += 1;
+    }
+
+    // Close any items that are still open at the end of the file
+    while let Some(item) = open.pop() {
+        if let Some(mut item) = item {
+            item.byte_range.end = bytes.len();
+            attach(
+                item,
+                open.iter_mut().rev().find_map(Option::as_mut),
+                &mut roots,
+            );
+        }
+    }
+    roots
+}
+
+fn find(bytes: &[u8], from: usize, pattern: &[u8]) -> Option<usize> {
+    bytes
+        .get(from..)?
+        .windows(pattern.len())
+        .position(|window| window == pattern)
+        .map(|position| position + from)
+}
+
+/// Skip a quoted string starting at `start` and return the index after the closing quote.
+fn skip_string(bytes: &[u8], start: usize, quote: u8, single_line: bool) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'\n' if single_line => return i,
+            byte if byte == quote => return i + 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+/// Skip a rust character literal, or just the quote if it is the start of a lifetime.
+fn skip_rust_char(source: &str, start: usize) -> usize {
+    let rest = &source[start + 1..];
+    if let Some(escaped) = rest.strip_prefix('\\') {
+        return escaped
+            .find('\'')
+            .map_or(source.len(), |end| start + 2 + end + 1);
+    }
+    let mut chars = rest.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), Some('\'')) => start + 1 + c.len_utf8() + 1,
+        _ => start + 1,
+    }
+}
+
+fn is_ident_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+/// Check if a rust raw string (`r"..."`, `r#"..."#` or `br"..."`) starts at `start`.
+fn raw_string_start(bytes: &[u8], start: usize) -> bool {
+    let prefix_ok = match start.checked_sub(1).map(|i| bytes[i]) {
+        None => true,
+        Some(b'b') => start < 2 || !is_ident_byte(bytes[start - 2]),
+        Some(byte) => !is_ident_byte(byte),
+    };
+    let hashes = bytes[start + 1..]
+        .iter()
+        .take_while(|&&byte| byte == b'#')
+        .count();
+    prefix_ok && bytes.get(start + 1 + hashes) == Some(&b'"')
+}
+
+fn skip_raw_string(bytes: &[u8], start: usize) -> usize {
+    let hashes = bytes[start + 1..]
+        .iter()
+        .take_while(|&&byte| byte == b'#')
+        .count();
+    let terminator = [vec![b'"'], vec![b'#'; hashes]].concat();
+    find(bytes, start + 2 + hashes, &terminator).map_or(bytes.len(), |end| end + terminator.len())
+}
+
+/// Find the start and name of the item the header before an opening brace declares, if it declares one.
+///
+/// The header may start with statements that are not terminated by a semicolon, so every line is tried as the start of the item.
+fn item_header(
+    source: &str,
+    header: Range<usize>,
+    language: CodeLanguage,
+) -> Option<(usize, String)> {
+    let text = &source[header.clone()];
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        let candidate = &text[line_start..];
+        if !candidate.trim().is_empty() {
+            let declaration = strip_leading_trivia(candidate, language);
+            let name = match language {
+                CodeLanguage::Rust => rust_item_name(declaration),
+                _ => script_item_name(declaration),
+            };
+            if let Some(name) = name {
+                let start =
+                    header.start + line_start + (candidate.len() - candidate.trim_start().len());
+                return Some((start, name));
+            }
+        }
+        line_start += line.len();
+    }
+    None
+}
+
+/// Remove the comments, attributes and decorators before a declaration.
+fn strip_leading_trivia(mut text: &str, language: CodeLanguage) -> &str {
+    loop {
+        text = text.trim_start();
+        if text.starts_with("//") {
+            text = text.split_once('\n').map_or("", |(_, rest)| rest);
+        } else if let Some(comment) = text.strip_prefix("/*") {
+            text = comment.split_once("*/").map_or("", |(_, rest)| rest);
+        } else if language == CodeLanguage::Rust && text.starts_with('#') {
+            // Skip to the end of the attribute with balanced brackets
+            let mut depth = 0;
+            let end = text.char_indices().find_map(|(i, c)| {
+                match c {
+                    '[' => depth += 1,
+                    ']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(i + 1);
+                        }
+                    }
+                    _ => {}
+                }
+                None
+            });
+            text = end.map_or("", |end| &text[end..]);
+        } else if language != CodeLanguage::Rust && text.starts_with('@') {
+            text = text.split_once('\n').map_or("", |(_, rest)| rest);
+        } else {
+            return text;
+        }
+    }
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn leading_ident(text: &str) -> Option<&str> {
+    let end = text
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .unwrap_or(text.len());
+    (end > 0).then(|| &text[..end])
+}
+
+fn rust_item_name(declaration: &str) -> Option<String> {
+    let declaration = normalize_whitespace(declaration);
+    let mut rest = declaration.as_str();
+    loop {
+        if let Some(visibility) = rest.strip_prefix("pub(") {
+            rest = visibility.split_once(')')?.1.trim_start();
+            continue;
+        }
+        let stripped = [
+            "pub ",
+            "async ",
+            "unsafe ",
+            "const ",
+            "default ",
+            "extern \"C\" ",
+            "extern ",
+        ]
+        .iter()
+        .find_map(|modifier| rest.strip_prefix(modifier));
+        match stripped {
+            Some(stripped) => rest = stripped,
+            None => break,
+        }
+    }
+
+    if let Some(implementation) = rest.strip_prefix("impl") {
+        if implementation.starts_with([' ', '<']) {
+            let implementation = implementation.split(" where ").next().unwrap_or_default();
+            return Some(format!("impl{}", implementation.trim_end()));
+        }
+    }
+    let (keyword, rest) = rest.split_once(' ')?;
+    match keyword {
+        "fn" | "trait" | "mod" | "struct" | "enum" | "union" | "macro_rules!" => {
+            Some(format!("{keyword} {}", leading_ident(rest)?))
+        }
+        _ => None,
+    }
+}
+
+fn script_item_name(declaration: &str) -> Option<String> {
+    const NOT_METHODS: &[&str] = &[
+        "if", "for", "while", "switch", "catch", "with", "return", "function", "else", "do", "try",
+        "finally", "new", "await", "yield", "typeof", "super", "this",
+    ];
+
+    let declaration = normalize_whitespace(declaration);
+    let mut rest = declaration.as_str();
+    while let Some(stripped) = [
+        "export ",
+        "default ",
+        "declare ",
+        "abstract ",
+        "async ",
+        "public ",
+        "private ",
+        "protected ",
+        "static ",
+        "readonly ",
+        "override ",
+    ]
+    .iter()
+    .find_map(|modifier| rest.strip_prefix(modifier))
+    {
+        rest = stripped;
+    }
+
+    let (keyword, after) = rest.split_once(' ').unwrap_or((rest, ""));
+    match keyword {
+        "function" | "function*" => Some(format!("function {}", leading_ident(after)?)),
+        "class" | "interface" | "namespace" | "module" | "enum" => {
+            Some(format!("{keyword} {}", leading_ident(after)?))
+        }
+        "const" | "let" | "var" => {
+            // Functions assigned to a variable like `const parse = (input) => {`
+            let name = leading_ident(after)?;
+            let value = after.split_once('=')?.1.trim();
+            let is_function = value.ends_with("=>")
+                || value.starts_with("function")
+                || value.starts_with("async ");
+            is_function.then(|| format!("function {name}"))
+        }
+        "get" | "set" if after.contains('(') => {
+            Some(format!("{keyword} {}", leading_ident(after)?))
+        }
+        _ => {
+            // Methods like `parse(input: string): Node {`
+            let name = leading_ident(rest)?;
+            let after_name = rest[name.len()..].trim_start();
+            let is_method = (after_name.starts_with('(') || after_name.starts_with('<'))
+                && after_name.contains(')')
+                && !after_name.contains("=>")
+                && !NOT_METHODS.contains(&name);
+            is_method.then(|| name.to_string())
+        }
+    }
+}
+
+/// Parse the items of a language that uses indentation for blocks (Python).
+fn parse_indented(source: &str) -> Vec<CodeItem> {
+    let mut roots = Vec::new();
+    // The open items with the indentation of their header
+    let mut open: Vec<(usize, CodeItem)> = Vec::new();
+    // The start and indentation of the decorators and comments directly above the current line
+    let mut leading: Option<(usize, usize)> = None;
+    let mut state = PythonLineState::default();
+    let mut last_content_end = 0;
+    let mut offset = 0;
+
+    for line in source.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        let content = line.trim_end();
+        let trimmed = content.trim_start();
+        let indent = content.len() - trimmed.len();
+        // Lines inside of brackets or multi-line strings continue the previous line
+        let continuation = state.continues();
+        state.scan(line);
+        if trimmed.is_empty() {
+            continue;
+        }
+        if continuation {
+            last_content_end = line_start + content.len();
+            continue;
+        }
+
+        // A line with the same or less indentation closes the items above it
+        while open
+            .last()
+            .is_some_and(|(item_indent, _)| indent <= *item_indent)
+        {
+            let (_, mut item) = open.pop().unwrap();
+            item.byte_range.end = last_content_end;
+            attach(item, open.last_mut().map(|(_, item)| item), &mut roots);
+        }
+
+        if trimmed.starts_with('@') || trimmed.starts_with('#') {
+            if !matches!(leading, Some((_, leading_indent)) if leading_indent == indent) {
+                leading = Some((line_start + indent, indent));
+            }
+        } else {
+            if let Some(name) = python_item_name(trimmed) {
+                let start = match leading {
+                    Some((start, leading_indent)) if leading_indent == indent => start,
+                    _ => line_start + indent,
+                };
+                let item = CodeItem {
+                    name,
+                    byte_range: start..start,
+                    children: Vec::new(),
+                };
+                open.push((indent, item));
+            }
+            leading = None;
+        }
+        last_content_end = line_start + content.len();
+    }
+
+    while let Some((_, mut item)) = open.pop() {
+        item.byte_range.end = last_content_end;
+        attach(item, open.last_mut().map(|(_, item)| item), &mut roots);
+    }
+    roots
+}
+
+fn python_item_name(line: &str) -> Option<String> {
+    let line = line.strip_prefix("async ").unwrap_or(line);
+    let (keyword, rest) = line.split_once(' ')?;
+    match keyword {
+        "def" | "class" => Some(format!("{keyword} {}", leading_ident(rest.trim_start())?)),
+        _ => None,
+    }
+}
+
+/// The brackets and multi-line strings that are open at the end of a line of python.
+#[derive(Default)]
+struct PythonLineState {
+    brackets: usize,
+    string: Option<&'static str>,
+}
+
+impl PythonLineState {
+    fn continues(&self) -> bool {
+        self.brackets > 0 || self.string.is_some()
+    }
+
+    fn scan(&mut self, line: &str) {
+        let mut rest = line;
+        while !rest.is_empty() {
+            if let Some(quote) = self.string {
+                match rest.find(quote) {
+                    Some(end) => {
+                        rest = &rest[end + quote.len()..];
+                        self.string = None;
+                    }
+                    None => return,
+                }
+                continue;
+            }
+            let Some(c) = rest.chars().next() else {
+                return;
+            };
+            match c {
+                '#' => return,
+                '(' | '[' | '{' => self.brackets += 1,
+                ')' | ']' | '}' => self.brackets = self.brackets.saturating_sub(1),
+                '"' | '\'' => {
+                    let triple = if c == '"' { "\"\"\"" } else { "'''" };
+                    if let Some(string) = rest.strip_prefix(triple) {
+                        self.string = Some(triple);
+                        rest = string;
+                        continue;
+                    }
+                    // Skip a single line string
+                    let bytes = rest.as_bytes();
+                    let end = skip_string(bytes, 0, c as u8, true);
+                    rest = &rest[end.min(rest.len())..];
+                    continue;
+                }
+                _ => {}
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+}
+
+#[cfg(test)]
+fn scopes(source: &str, spans: &[CodeSpan]) -> Vec<(String, String)> {
+    spans
+        .iter()
+        .map(|span| {
+            let text = &source[span.byte_range.clone()];
+            let first_line = text.lines().next().unwrap_or_default().to_string();
+            (span.scope.join(" > "), first_line)
+        })
+        .collect()
+}
+
+#[test]
+fn rust_is_split_along_items() {
+    let source = r##"use std::fmt;
+
+/// A parser.
+#[derive(Debug)]
+pub struct Parser {
+    input: String,
+}
+
+impl Parser {
+    /// Create a new parser with a "{" in a string.
+    pub fn new(input: String) -> Self {
+        let brace = '{';
+        Self { input }
+    }
+
+    pub(crate) fn parse<'a>(&'a self) -> Vec<&'a str> {
+        // A comment with a } brace
+        self.input.split(' ').filter(|word| !word.is_empty()).collect()
+    }
+}
+
+fn main() {
+    println!("{}", r#"raw { string"#);
+}
+"##;
+    let chunker = CodeChunker::new()
+        .with_language(CodeLanguage::Rust)
+        .with_max_chunk_size(200);
+    let spans = chunker.split(source);
+    assert_eq!(
+        scopes(source, &spans),
+        [
+            ("".to_string(), "use std::fmt;".to_string()),
+            ("impl Parser".to_string(), "impl Parser {".to_string()),
+            (
+                "impl Parser > fn parse".to_string(),
+                "pub(crate) fn parse<'a>(&'a self) -> Vec<&'a str> {".to_string()
+            ),
+            ("fn main".to_string(), "fn main() {".to_string()),
+        ]
+    );
+    // Small neighboring items are merged into one chunk
+    assert!(source[spans[0].byte_range.clone()].ends_with("input: String,\n}"));
+    assert!(source[spans[1].byte_range.clone()].contains("pub fn new"));
+    // The closing brace of the impl stays with the last chunk in the impl
+    assert!(source[spans[2].byte_range.clone()].ends_with("collect()\n    }\n}"));
+}
+
+#[test]
+fn python_is_split_along_items() {
+    let source = r#"import os
+
+
+class Greeter:
+    """A class that greets people.
+
+    def not_a_method(self):
+    """
+
+    def __init__(self, name):
+        self.name = name
+
+    # Say hello
+    @property
+    def greeting(
+        self,
+    ):
+        return f"Hello {self.name}"
+
+
+async def main():
+    print(Greeter("world").greeting)
+"#;
+    let chunker = CodeChunker::new()
+        .with_language(CodeLanguage::Python)
+        .with_max_chunk_size(100);
+    let spans = chunker.split(source);
+    assert_eq!(
+        scopes(source, &spans),
+        [
+            ("".to_string(), "import os".to_string()),
+            ("class Greeter".to_string(), "class Greeter:".to_string()),
+            (
+                "class Greeter > def __init__".to_string(),
+                "def __init__(self, name):".to_string()
+            ),
+            (
+                "class Greeter > def greeting".to_string(),
+                "# Say hello".to_string()
+            ),
+            ("def main".to_string(), "async def main():".to_string()),
+        ]
+    );
+    assert!(source[spans[3].byte_range.clone()].ends_with("return f\"Hello {self.name}\""));
+}
+
+#[test]
+fn typescript_is_split_along_items() {
+    let source = r#"import { readFile } from "fs"
+
+export class Store {
+  private items: Map<string, string> = new Map();
+
+  get(key: string): string | undefined {
+    if (this.items.has(key)) {
+      return this.items.get(key);
+    }
+    return `missing ${key}`;
+  }
+}
+
+export const load = async (path: string) => {
+  const text = await readFile(path, "utf8");
+  return JSON.parse(text);
+};
+
+function helper() {
+  return { ok: true };
+}
+"#;
+    let chunker = CodeChunker::new()
+        .with_language(CodeLanguage::TypeScript)
+        .with_max_chunk_size(160);
+    let spans = chunker.split(source);
+    assert_eq!(
+        scopes(source, &spans),
+        [
+            (
+                "".to_string(),
+                "import { readFile } from \"fs\"".to_string()
+            ),
+            (
+                "class Store".to_string(),
+                "export class Store {".to_string()
+            ),
+            (
+                "class Store > get".to_string(),
+                "get(key: string): string | undefined {".to_string()
+            ),
+            (
+                "function load".to_string(),
+                "export const load = async (path: string) => {".to_string()
+            ),
+            (
+                "function helper".to_string(),
+                "function helper() {".to_string()
+            ),
+        ]
+    );
+}
@@ -1,7 +1,8 @@
-use ego_tree::NodeMut;
+use ego_tree::{NodeMut, NodeRef};
 use scraper::Html;
 use scraper::StrTendril;
 use std::collections::HashSet;
+use url::Url;
 
 fn element_hidden(element: &scraper::node::Element) -> bool {
     let style_hidden = match element.attr("style") {
@@ -58,6 +59,7 @@ pub struct HtmlSimplifier {
     important_elements: HashSet<String>,
     ignore_elements: HashSet<String>,
     standalone_elements: HashSet<String>,
+    base_url: Option<Url>,
 }
 
 impl Default for HtmlSimplifier {
@@ -77,6 +79,7 @@ impl Default for HtmlSimplifier {
             important_elements: IMPORTANT_ELEMENTS.iter().map(|s| s.to_string()).collect(),
             ignore_elements: IGNORE_ELEMENTS.iter().map(|s| s.to_string()).collect(),
             standalone_elements: STANDALONE_ELEMENTS.iter().map(|s| s.to_string()).collect(),
+            base_url: None,
         }
     }
 }
@@ -88,6 +91,11 @@ impl HtmlSimplifier {
         self.important_attributes.insert("href".to_string());
     }
 
+    /// Resolve relative links and image sources against the URL of the page when rendering [`HtmlSimplifier::markdown`].
+    pub fn resolve_links_against(&mut self, base_url: Url) {
+        self.base_url = Some(base_url);
+    }
+
     /// Retain images while simplifying the HTML.
     pub fn include_images(&mut self) {
         self.important_elements.insert("img".to_string());
@@ -137,6 +145,36 @@ impl HtmlSimplifier {
         remove_unnecessary_whitespace(html);
     }
 
+    /// Render the HTML as Markdown. Headings, paragraphs, lists, tables, block quotes, code blocks and inline formatting are
+    /// kept, while the elements this simplifier ignores and hidden elements are removed. Links and images are only rendered
+    /// if [`HtmlSimplifier::include_links`] and [`HtmlSimplifier::include_images`] were called; otherwise links are replaced
+    /// with their text and images are removed.
+    ///
+    /// # Example
+    /// ```rust
+    /// use kalosm_language::prelude::*;
+    ///
+    /// let document = "<h1>Kalosm</h1><p>A library for <a href=\"https://floneum.com\">local AI</a>.</p><ul><li>Fast</li><li>Private</li></ul>";
+    /// let html = Html::parse_document(document);
+    /// let mut simplifier = HtmlSimplifier::default();
+    /// simplifier.include_links();
+    /// assert_eq!(
+    ///     simplifier.markdown(&html),
+    ///     "# Kalosm\n\nA library for [local AI](https://floneum.com).\n\n- Fast\n- Private"
+    /// );
+    /// ```
+    pub fn markdown(&self, html: &Html) -> String {
+        let renderer = MarkdownRenderer {
+            ignore_elements: &self.ignore_elements,
+            links: self.important_elements.contains("a"),
+            images: self.important_elements.contains("img"),
+            base_url: self.base_url.as_ref(),
+        };
+        let mut markdown = String::new();
+        renderer.render_children(html.tree.root(), &mut markdown);
+        markdown.trim().to_string()
+    }
+
     fn transform_node(&mut self, mut node: ego_tree::NodeMut<'_, scraper::Node>) {
         let value = node.value();
 
@@ -417,6 +455,352 @@ fn remove_unnecessary_whitespace(html: &mut Html) {
     }
 }
 
+/// Renders a HTML tree as Markdown. See [`HtmlSimplifier::markdown`].
+struct MarkdownRenderer<'a> {
+    ignore_elements: &'a HashSet<String>,
+    links: bool,
+    images: bool,
+    base_url: Option<&'a Url>,
+}
+
+impl MarkdownRenderer<'_> {
+    /// Resolve a link against the base URL if there is one.
+    fn resolve(&self, link: &str) -> String {
+        let resolved = self.base_url.and_then(|base| base.join(link).ok());
+        match resolved {
+            Some(url) => url.to_string(),
+            None => link.replace(' ', "%20"),
+        }
+    }
+
+    fn render_children(&self, node: NodeRef<'_, scraper::Node>, out: &mut String) {
+        for child in node.children() {
+            self.render_node(child, out);
+        }
+    }
+
+    fn render_node(&self, node: NodeRef<'_, scraper::Node>, out: &mut String) {
+        match node.value() {
+            scraper::Node::Text(text) => push_text(out, text),
+            scraper::Node::Element(element) => self.render_element(node, element, out),
+            scraper::Node::Document | scraper::Node::Fragment => self.render_children(node, out),
+            _ => {}
+        }
+    }
+
+    fn render_element(
+        &self,
+        node: NodeRef<'_, scraper::Node>,
+        element: &scraper::node::Element,
+        out: &mut String,
+    ) {
+        let tag = element.name().to_lowercase();
+        if element_hidden(element) || self.ignore_elements.contains(tag.as_str()) {
+            return;
+        }
+
+        match tag.as_str() {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let text = self.inline_text(node);
+                if !text.is_empty() {
+                    let level = tag[1..].parse().unwrap_or(1);
+                    start_block(out);
+                    out.push_str(&"#".repeat(level));
+                    out.push(' ');
+                    out.push_str(&text);
+                    start_block(out);
+                }
+            }
+            "p" | "div" | "section" | "article" | "main" | "header" | "footer" | "aside"
+            | "figure" | "figcaption" | "address" | "details" | "summary" | "dl" | "dd" | "li" => {
+                start_block(out);
+                self.render_children(node, out);
+                start_block(out);
+            }
+            "dt" => {
+                let text = self.inline_text(node);
+                if !text.is_empty() {
+                    start_block(out);
+                    out.push_str(&format!("**{text}**"));
+                    start_block(out);
+                }
+            }
+            "br" => {
+                trim_trailing_spaces(out);
+                out.push('\n');
+            }
+            "hr" => {
+                start_block(out);
+                out.push_str("---");
+                start_block(out);
+            }
+            "ul" | "ol" => self.render_list(node, element, tag == "ol", out),
+            "blockquote" => {
+                let mut quote = String::new();
+                self.render_children(node, &mut quote);
+                let quote = quote.trim();
+                if !quote.is_empty() {
+                    start_block(out);
+                    for (i, line) in quote.lines().enumerate() {
+                        if i > 0 {
+                            out.push('\n');
+                        }
+                        out.push('>');
+                        if !line.is_empty() {
+                            out.push(' ');
+                            out.push_str(line);
+                        }
+                    }
+                    start_block(out);
+                }
+            }
+            "pre" => self.render_code_block(node, out),
+            "table" => self.render_table(node, out),
+            "code" | "kbd" | "samp" => {
+                let code = raw_text(node)
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if !code.is_empty() {
+                    let ticks = if code.contains('`') { "``" } else { "`" };
+                    let padding = if code.contains('`') { " " } else { "" };
+                    push_inline(
+                        out,
+                        node,
+                        &format!("{ticks}{padding}{code}{padding}{ticks}"),
+                    );
+                }
+            }
+            "strong" | "b" => self.render_emphasis(node, "**", out),
+            "em" | "i" => self.render_emphasis(node, "*", out),
+            "del" | "s" | "strike" => self.render_emphasis(node, "~~", out),
+            "a" => {
+                let href = element.attr("href").map(str::trim).filter(|href| {
+                    !href.is_empty() && !href.starts_with('#') && !href.starts_with("javascript:")
+                });
+                match href {
+                    Some(href) if self.links => {
+                        let text = self.inline_text(node);
+                        if !text.is_empty() {
+                            let href = self.resolve(href);
+                            push_inline(out, node, &format!("[{text}]({href})"));
+                        }
+                    }
+                    _ => self.render_children(node, out),
+                }
+            }
+            "img" => {
+                if let Some(src) = element.attr("src").filter(|_| self.images) {
+                    let alt = element.attr("alt").unwrap_or_default().trim();
+                    let src = src.trim();
+                    if !src.is_empty() {
+                        let src = self.resolve(src);
+                        out.push_str(&format!("![{alt}]({src})"));
+                    }
+                }
+            }
+            _ => self.render_children(node, out),
+        }
+    }
+
+    /// Render the children of a node as a single line of Markdown.
+    fn inline_text(&self, node: NodeRef<'_, scraper::Node>) -> String {
+        let mut text = String::new();
+        self.render_children(node, &mut text);
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    fn render_emphasis(&self, node: NodeRef<'_, scraper::Node>, marker: &str, out: &mut String) {
+        let text = self.inline_text(node);
+        if !text.is_empty() {
+            push_inline(out, node, &format!("{marker}{text}{marker}"));
+        }
+    }
+
+    fn render_list(
+        &self,
+        node: NodeRef<'_, scraper::Node>,
+        element: &scraper::node::Element,
+        ordered: bool,
+        out: &mut String,
+    ) {
+        let start = element
+            .attr("start")
+            .and_then(|start| start.trim().parse::<usize>().ok())
+            .unwrap_or(1);
+        let mut items = Vec::new();
+        for child in node.children() {
+            let scraper::Node::Element(child_element) = child.value() else {
+                continue;
+            };
+            let mut item = String::new();
+            if child_element.name().eq_ignore_ascii_case("li") {
+                if element_hidden(child_element) {
+                    continue;
+                }
+                self.render_children(child, &mut item);
+            } else {
+                self.render_node(child, &mut item);
+            }
+            let item = item.trim();
+            if !item.is_empty() {
+                items.push(item.to_string());
+            }
+        }
+        if items.is_empty() {
+            return;
+        }
+
+        start_block(out);
+        for (i, item) in items.iter().enumerate() {
+            let marker = if ordered {
+                format!("{}. ", start + i)
+            } else {
+                "- ".to_string()
+            };
+            if i > 0 {
+                out.push('\n');
+            }
+            out.push_str(&marker);
+            // Indent the following lines of the item so they stay inside the list item
+            let indent = " ".repeat(marker.len());
+            for (j, line) in item.lines().enumerate() {
+                if j > 0 {
+                    out.push('\n');
+                    if !line.is_empty() {
+                        out.push_str(&indent);
+                    }
+                }
+                out.push_str(line);
+            }
+        }
+        start_block(out);
+    }
+
+    fn render_code_block(&self, node: NodeRef<'_, scraper::Node>, out: &mut String) {
+        let code = raw_text(node);
+        let code = code.trim_matches('\n').trim_end();
+        if code.trim().is_empty() {
+            return;
+        }
+        // The language is usually set as a class on the code element inside of the pre element
+        let language = std::iter::once(node)
+            .chain(node.children())
+            .filter_map(|node| node.value().as_element())
+            .filter_map(|element| element.attr("class"))
+            .flat_map(str::split_whitespace)
+            .find_map(|class| {
+                class
+                    .strip_prefix("language-")
+                    .or_else(|| class.strip_prefix("lang-"))
+            })
+            .unwrap_or_default();
+        let mut fence = "```".to_string();
+        while code.contains(&fence) {
+            fence.push('`');
+        }
+
+        start_block(out);
+        out.push_str(&fence);
+        out.push_str(language);
+        out.push('\n');
+        out.push_str(code);
+        out.push('\n');
+        out.push_str(&fence);
+        start_block(out);
+    }
+
+    fn render_table(&self, node: NodeRef<'_, scraper::Node>, out: &mut String) {
+        let is_element = |node: &NodeRef<'_, scraper::Node>, names: &[&str]| {
+            node.value()
+                .as_element()
+                .is_some_and(|element| names.contains(&element.name().to_lowercase().as_str()))
+        };
+        let mut rows = Vec::new();
+        for row in node.descendants().filter(|node| is_element(node, &["tr"])) {
+            let cells = row
+                .children()
+                .filter(|node| is_element(node, &["th", "td"]))
+                .map(|cell| self.inline_text(cell).replace('|', "\\|"))
+                .collect::<Vec<_>>();
+            if !cells.is_empty() {
+                rows.push(cells);
+            }
+        }
+        let columns = rows.iter().map(Vec::len).max().unwrap_or_default();
+        if columns == 0 {
+            return;
+        }
+
+        start_block(out);
+        for (i, row) in rows.iter_mut().enumerate() {
+            row.resize(columns, String::new());
+            if i > 0 {
+                out.push('\n');
+            }
+            out.push_str("| ");
+            out.push_str(&row.join(" | "));
+            out.push_str(" |");
+            // The first row is used as the header of the table
+            if i == 0 {
+                out.push('\n');
+                out.push('|');
+                out.push_str(&" --- |".repeat(columns));
+            }
+        }
+        start_block(out);
+    }
+}
+
+/// Get the text inside a node without collapsing whitespace.
+fn raw_text(node: NodeRef<'_, scraper::Node>) -> String {
+    node.descendants()
+        .filter_map(|node| node.value().as_text())
+        .map(|text| &**text)
+        .collect()
+}
+
+/// Push text to the output, collapsing runs of whitespace into a single space.
+fn push_text(out: &mut String, text: &str) {
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !out.is_empty() && !out.ends_with(char::is_whitespace) {
+                out.push(' ');
+            }
+        } else {
+            out.push(c);
+        }
+    }
+}
+
+/// Push rendered Markdown for an inline element, keeping the whitespace around the text of the element.
+fn push_inline(out: &mut String, node: NodeRef<'_, scraper::Node>, markdown: &str) {
+    let text = raw_text(node);
+    if text.starts_with(char::is_whitespace) {
+        push_text(out, " ");
+    }
+    out.push_str(markdown);
+    if text.ends_with(char::is_whitespace) {
+        push_text(out, " ");
+    }
+}
+
+fn trim_trailing_spaces(out: &mut String) {
+    let trimmed = out.trim_end_matches([' ', '\t']).len();
+    out.truncate(trimmed);
+}
+
+/// Separate the next block from the previous one with an empty line.
+fn start_block(out: &mut String) {
+    trim_trailing_spaces(out);
+    if out.is_empty() {
+        return;
+    }
+    while !out.ends_with("\n\n") {
+        out.push('\n');
+    }
+}
+
 #[test]
 fn scripts_removed() {
     let html =
@@ -462,3 +846,74 @@ fn non_important_attributes_removed() {
         "<html><p>Hello world!</p></html>"
     );
 }
+
+#[test]
+fn markdown_keeps_structure() {
+    let html = r#"<html><head><title>Ignored</title></head><body>
+<h2>Install  <em>Kalosm</em></h2>
+<p>Add it with <code>cargo add kalosm</code>. See the <a href="https://docs.rs/kalosm">docs</a><img src="logo.png" alt="Logo">.</p>
+<ol start="3"><li>First</li><li>Second<ul><li>Nested</li></ul></li></ol>
+<blockquote><p>Quoted</p><p>Twice</p></blockquote>
+<pre><code class="language-rust">fn main() {
+    println!("Hello");
+}</code></pre>
+<table><thead><tr><th>Name</th><th>Kind</th></tr></thead><tbody><tr><td>Bert</td><td>Embedding | text</td></tr><tr><td>Phi</td></tr></tbody></table>
+<script>ignored()</script><p style="display: none">Hidden</p>
+</body></html>"#;
+    let html = Html::parse_document(html);
+
+    let simplifier = HtmlSimplifier::default();
+    assert_eq!(
+        simplifier.markdown(&html),
+        r#"## Install *Kalosm*
+
+Add it with `cargo add kalosm`. See the docs.
+
+3. First
+4. Second
+
+   - Nested
+
+> Quoted
+>
+> Twice
+
+```rust
+fn main() {
+    println!("Hello");
+}
+```
+
+| Name | Kind |
+| --- | --- |
+| Bert | Embedding \| text |
+| Phi |  |"#
+    );
+
+    let mut simplifier = HtmlSimplifier::default();
+    simplifier.include_links();
+    simplifier.include_images();
+    let markdown = simplifier.markdown(&html);
+    assert!(markdown.contains("See the [docs](https://docs.rs/kalosm)![Logo](logo.png)."));
+}
+
+#[test]
+fn malformed_html_is_simplified() {
+    let fixture = include_str!("../../context/fixtures/blog/second.html");
+
+    let markdown = HtmlSimplifier::default().markdown(&Html::parse_document(fixture));
+    assert!(markdown.starts_with("Browsers are forgiving"));
+    assert!(markdown.contains("still has to be read"));
+    assert!(markdown.contains("nested paragraph inside a div"));
+    // The unclosed paragraphs are still separate blocks, and nothing from the head or the markup leaks into the text
+    assert!(markdown.split("\n\n").count() >= 3);
+    assert!(!markdown.contains("broken markup"));
+    assert!(!markdown.contains('<'));
+
+    let mut html = Html::parse_document(fixture);
+    HtmlSimplifier::default().simplify(&mut html);
+    let text = html.root_element().text().collect::<String>();
+    assert!(text.contains("Browsers are forgiving"));
+    assert!(text.contains("nested paragraph inside a div"));
+    assert!(!text.contains("broken markup"));
+}
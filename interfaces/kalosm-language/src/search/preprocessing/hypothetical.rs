@@ -203,10 +203,11 @@ where
                 }
             }
             remaining_embeddings -= 1;
-            chunks.push(Chunk {
-                byte_range: byte_chunk.clone(),
-                embeddings: vec![embedding],
-            });
+            chunks.push(Chunk::from_document(
+                document,
+                byte_chunk.clone(),
+                vec![embedding],
+            ));
         }
 
         Ok(chunks)
@@ -0,0 +1,446 @@
+use kalosm_language_model::Embedder;
+use pulldown_cmark::{Event, Options, Tag};
+use std::ops::Range;
+
+use super::Chunker;
+use crate::context::{Document, Provenance};
+use crate::search::{estimate_tokens, Chunk};
+
+/// A [`Chunker`] that splits markdown along its heading hierarchy.
+///
+/// Every section under a heading starts a new chunk. Sections that are larger than the token budget are split between blocks, but tables
+/// and fenced code blocks are never split. Small neighboring sections are merged together until they reach the token budget. The heading
+/// breadcrumb of each chunk (like `Install > Features`) is embedded along with the text of the chunk and recorded in the [`Provenance`]
+/// of the chunk, while the byte range of the chunk still points to the original markdown.
+///
+/// Tokens are counted with [`Embedder::count_tokens`]. Embedders that don't know their tokenizer fall back to one token for every four bytes
+/// of text.
+///
+/// # Example
+/// ```rust, no_run
+/// use kalosm_language::prelude::*;
+/// use std::path::PathBuf;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let document = MdDocument::try_from(PathBuf::from("./README.md"))
+///         .unwrap()
+///         .with_format(ArticleFormat::Markdown)
+///         .into_document()
+///         .await
+///         .unwrap();
+///     let bert = Bert::new().await.unwrap();
+///     let chunks = MarkdownChunker::new()
+///         .with_max_tokens(128)
+///         .chunk(&document, &bert)
+///         .await
+///         .unwrap();
+///     for chunk in chunks {
+///         println!("{:?}: {}", chunk.provenance, &document.body()[chunk.byte_range]);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MarkdownChunker {
+    max_tokens: usize,
+}
+
+impl Default for MarkdownChunker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A span of markdown created by a [`MarkdownChunker`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownSpan {
+    /// The byte range of the span in the markdown.
+    pub byte_range: Range<usize>,
+    /// The headings the span is under, from the top level heading to the closest heading.
+    pub heading_path: Vec<String>,
+}
+
+impl MarkdownChunker {
+    /// Create a new [`MarkdownChunker`]. (default max tokens: 256)
+    pub const fn new() -> Self {
+        Self { max_tokens: 256 }
+    }
+
+    /// Set the maximum number of tokens in a chunk. Tables and code blocks that are larger than this are kept in one chunk. (default: 256)
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = max_tokens.max(1);
+        self
+    }
+
+    /// Split markdown into spans along its heading hierarchy with at most the maximum number of tokens, as counted by `count_tokens`.
+    pub fn split(&self, markdown: &str, count_tokens: impl Fn(&str) -> usize) -> Vec<MarkdownSpan> {
+        let fits = |range: Range<usize>| count_tokens(markdown[range].trim()) <= self.max_tokens;
+
+        // First split the sections that are too large between their blocks
+        let mut pieces = Vec::new();
+        for section in MarkdownSection::parse(markdown) {
+            if fits(section.byte_range.clone()) {
+                pieces.push(MarkdownSpan {
+                    byte_range: section.byte_range,
+                    heading_path: section.heading_path,
+                });
+                continue;
+            }
+            let mut current: Option<Range<usize>> = None;
+            for block in &section.blocks {
+                if let Some(range) = &current {
+                    if !fits(range.start..block.byte_range.end) {
+                        pieces.push(MarkdownSpan {
+                            byte_range: range.clone(),
+                            heading_path: section.heading_path.clone(),
+                        });
+                        current = None;
+                    }
+                }
+                if block.kind == BlockKind::Text && !fits(block.byte_range.clone()) {
+                    for byte_range in
+                        self.split_lines(markdown, block.byte_range.clone(), &count_tokens)
+                    {
+                        pieces.push(MarkdownSpan {
+                            byte_range,
+                            heading_path: section.heading_path.clone(),
+                        });
+                    }
+                    continue;
+                }
+                current = Some(match current {
+                    Some(range) => range.start..block.byte_range.end,
+                    None => block.byte_range.clone(),
+                });
+            }
+            if let Some(byte_range) = current {
+                pieces.push(MarkdownSpan {
+                    byte_range,
+                    heading_path: section.heading_path,
+                });
+            }
+        }
+
+        // Then merge small neighboring sections. A section can be merged into a chunk if it is a sibling or a child of the sections in the
+        // chunk, and the chunk keeps the headings the sections have in common
+        let mut spans: Vec<MarkdownSpan> = Vec::new();
+        for piece in pieces {
+            if let Some(last) = spans.last_mut() {
+                let parent = &last.heading_path[..last.heading_path.len().saturating_sub(1)];
+                if piece.heading_path.starts_with(parent)
+                    && fits(last.byte_range.start..piece.byte_range.end)
+                {
+                    let common = last
+                        .heading_path
+                        .iter()
+                        .zip(&piece.heading_path)
+                        .take_while(|(a, b)| a == b)
+                        .count();
+                    last.heading_path.truncate(common);
+                    last.byte_range.end = piece.byte_range.end;
+                    continue;
+                }
+            }
+            spans.push(piece);
+        }
+
+        spans
+            .into_iter()
+            .filter_map(|span| {
+                let byte_range = trim_range(markdown, span.byte_range)?;
+                Some(MarkdownSpan {
+                    byte_range,
+                    heading_path: span.heading_path,
+                })
+            })
+            .collect()
+    }
+
+    /// Split a block that is too large for one chunk along lines.
+    fn split_lines(
+        &self,
+        markdown: &str,
+        range: Range<usize>,
+        count_tokens: impl Fn(&str) -> usize,
+    ) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let mut start = range.start;
+        let mut end = range.start;
+        for line in markdown[range].split_inclusive('\n') {
+            if end > start
+                && count_tokens(markdown[start..end + line.len()].trim()) > self.max_tokens
+            {
+                ranges.push(start..end);
+                start = end;
+            }
+            end += line.len();
+        }
+        ranges.push(start..end);
+        ranges
+    }
+}
+
+impl Chunker for MarkdownChunker {
+    async fn chunk<E: Embedder + Send>(
+        &self,
+        document: &Document,
+        embedder: &E,
+    ) -> anyhow::Result<Vec<Chunk<E::VectorSpace>>> {
+        let body = document.body();
+        let spans = self.split(body, |text| {
+            embedder
+                .count_tokens(text)
+                .unwrap_or_else(|| estimate_tokens(text))
+        });
+
+        // Embed each chunk with the breadcrumb of headings above it so the embedding has the context of the chunk
+        let texts = spans
+            .iter()
+            .map(|span| {
+                let text = &body[span.byte_range.clone()];
+                if span.heading_path.is_empty() {
+                    text.to_string()
+                } else {
+                    format!("{}\n\n{text}", span.heading_path.join(" > "))
+                }
+            })
+            .collect();
+        let embeddings = embedder.embed_vec(texts).await?;
+
+        Ok(spans
+            .into_iter()
+            .zip(embeddings)
+            .map(|(span, embedding)| {
+                let mut provenance = document.provenance(span.byte_range.clone());
+                if !span.heading_path.is_empty() {
+                    let heading_path = Provenance::heading_path(span.heading_path);
+                    if !provenance.contains(&heading_path) {
+                        provenance.push(heading_path);
+                    }
+                }
+                Chunk::new(span.byte_range, vec![embedding])
+                    .with_provenance(provenance)
+                    .with_metadata(document.metadata().clone())
+            })
+            .collect())
+    }
+}
+
+fn trim_range(text: &str, range: Range<usize>) -> Option<Range<usize>> {
+    let slice = &text[range.clone()];
+    let trimmed = slice.trim_start();
+    let start = range.start + slice.len() - trimmed.len();
+    let end = start + trimmed.trim_end().len();
+    (start < end).then_some(start..end)
+}
+
+/// A heading and the blocks under it in a markdown document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownSection {
+    /// The byte range of the section in the markdown, including the heading.
+    pub byte_range: Range<usize>,
+    /// The level of the heading of the section, from 1 to 6. Text before the first heading is in a section without a heading.
+    pub heading_level: Option<usize>,
+    /// The headings the section is under, from the top level heading to the heading of the section.
+    pub heading_path: Vec<String>,
+    blocks: Vec<MarkdownBlock>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MarkdownBlock {
+    byte_range: Range<usize>,
+    kind: BlockKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    /// A table or code block that should not be split
+    Atomic,
+    Text,
+}
+
+impl MarkdownSection {
+    /// Split markdown into the sections under each heading.
+    pub fn parse(markdown: &str) -> Vec<Self> {
+        let mut sections = Vec::new();
+        let mut current = Self::empty();
+        // The headings above the current position with their level
+        let mut headings: Vec<(usize, String)> = Vec::new();
+        // The level and text of the heading we are currently inside of
+        let mut heading: Option<(usize, String)> = None;
+        let mut depth = 0;
+
+        let parser =
+            pulldown_cmark::Parser::new_ext(markdown, Options::ENABLE_TABLES).into_offset_iter();
+        for (event, byte_range) in parser {
+            match event {
+                Event::Start(tag) => {
+                    if depth == 0 {
+                        match tag {
+                            Tag::Heading(level, ..) => {
+                                current.finish(&mut sections);
+                                heading = Some((level as usize, String::new()));
+                                current.byte_range = byte_range.clone();
+                                current.blocks.push(MarkdownBlock {
+                                    byte_range: byte_range.clone(),
+                                    kind: BlockKind::Text,
+                                });
+                            }
+                            Tag::Table(_) | Tag::CodeBlock(_) => {
+                                current.push(byte_range.clone(), BlockKind::Atomic)
+                            }
+                            _ => current.push(byte_range.clone(), BlockKind::Text),
+                        }
+                    }
+                    depth += 1;
+                }
+                Event::End(Tag::Heading(..)) if depth == 1 => {
+                    depth -= 1;
+                    if let Some((level, text)) = heading.take() {
+                        headings.retain(|(parent, _)| *parent < level);
+                        headings.push((level, text.trim().to_string()));
+                        current.heading_level = Some(level);
+                        current.heading_path =
+                            headings.iter().map(|(_, text)| text.clone()).collect();
+                        current.byte_range.end = byte_range.end;
+                    }
+                }
+                Event::End(_) => depth -= 1,
+                Event::Text(text) | Event::Code(text) => {
+                    if let Some((_, heading)) = &mut heading {
+                        heading.push_str(&text);
+                    }
+                }
+                // Blocks without children like html and rules
+                _ if depth == 0 => current.push(byte_range, BlockKind::Text),
+                _ => {}
+            }
+        }
+        current.finish(&mut sections);
+
+        sections
+    }
+
+    fn empty() -> Self {
+        Self {
+            byte_range: 0..0,
+            heading_level: None,
+            heading_path: Vec::new(),
+            blocks: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, byte_range: Range<usize>, kind: BlockKind) {
+        if self.byte_range.is_empty() && self.heading_level.is_none() {
+            self.byte_range.start = byte_range.start;
+        }
+        self.byte_range.end = byte_range.end;
+        self.blocks.push(MarkdownBlock { byte_range, kind });
+    }
+
+    fn finish(&mut self, sections: &mut Vec<Self>) {
+        let section = std::mem::replace(self, Self::empty());
+        if !section.blocks.is_empty() {
+            sections.push(section);
+        }
+    }
+}
+
+#[test]
+fn markdown_is_split_into_sections() {
+    let markdown = "Preface\n\n# Kalosm\n\nIntro text\n\n## Install\n\nRun `cargo add kalosm`\n\n### Features\n\n- language\n- sound\n\n## Usage\n\nCall the model\n";
+    let sections = MarkdownSection::parse(markdown)
+        .into_iter()
+        .map(|section| {
+            (
+                markdown[section.byte_range].trim().to_string(),
+                section.heading_path.join(" > "),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sections,
+        [
+            ("Preface".to_string(), "".to_string()),
+            ("# Kalosm\n\nIntro text".to_string(), "Kalosm".to_string()),
+            (
+                "## Install\n\nRun `cargo add kalosm`".to_string(),
+                "Kalosm > Install".to_string()
+            ),
+            (
+                "### Features\n\n- language\n- sound".to_string(),
+                "Kalosm > Install > Features".to_string()
+            ),
+            (
+                "## Usage\n\nCall the model".to_string(),
+                "Kalosm > Usage".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn markdown_chunks_keep_tables_and_code_intact() {
+    let markdown = r#"# Guide
+
+Short intro.
+
+## Models
+
+| Model | Size |
+| ----- | ---- |
+| Llama | 7B   |
+| Phi   | 2B   |
+
+```rust
+fn main() {
+
+    println!("a code block with an empty line");
+}
+```
+
+Some closing words about the models that are long enough to need their own chunk.
+
+## Tasks
+
+A small section.
+
+### Extraction
+
+Another small section.
+"#;
+    let spans = MarkdownChunker::new()
+        .with_max_tokens(25)
+        .split(markdown, estimate_tokens);
+    let spans = spans
+        .iter()
+        .map(|span| {
+            (
+                span.heading_path.join(" > "),
+                &markdown[span.byte_range.clone()],
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        spans,
+        [
+            ("Guide".to_string(), "# Guide\n\nShort intro."),
+            (
+                "Guide > Models".to_string(),
+                "## Models\n\n| Model | Size |\n| ----- | ---- |\n| Llama | 7B   |\n| Phi   | 2B   |"
+            ),
+            (
+                "Guide > Models".to_string(),
+                "```rust\nfn main() {\n\n    println!(\"a code block with an empty line\");\n}\n```"
+            ),
+            (
+                "Guide > Models".to_string(),
+                "Some closing words about the models that are long enough to need their own chunk."
+            ),
+            (
+                "Guide > Tasks".to_string(),
+                "## Tasks\n\nA small section.\n\n### Extraction\n\nAnother small section."
+            ),
+        ]
+    );
+}
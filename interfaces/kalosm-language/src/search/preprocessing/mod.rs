@@ -20,6 +20,8 @@ use super::Chunk;
 
 mod chunking;
 pub use chunking::*;
+mod code;
+pub use code::*;
 mod hypothetical;
 pub use hypothetical::*;
 mod summary;
@@ -30,6 +32,10 @@ mod semantic;
 pub use semantic::*;
 mod html;
 pub use html::*;
+mod markdown;
+pub use markdown::*;
+mod recursive;
+pub use recursive::*;
 
 /// A strategy for chunking a document into smaller pieces.
 pub trait Chunker {
@@ -0,0 +1,339 @@
+use kalosm_language_model::Embedder;
+use std::ops::Range;
+
+use super::{Chunker, SentenceChunker};
+use crate::{
+    prelude::Document,
+    search::{estimate_tokens, Chunk},
+};
+
+/// A [`Chunker`] that splits text into chunks with a maximum number of tokens.
+///
+/// Unlike [`ChunkStrategy`](super::ChunkStrategy), which counts paragraphs, sentences or words, this chunker measures chunks with the tokenizer
+/// of the embedder (see [`Embedder::count_tokens`]) so chunks fit in the context of the model. Text that is too large is split recursively: first
+/// into paragraphs, then into sentences with a [`SentenceChunker`], then into words, and finally into characters for text without spaces.
+/// The pieces are then merged back together into chunks that are as large as possible, with a configurable number of overlapping tokens
+/// between neighboring chunks.
+///
+/// If the embedder does not know its tokenizer, tokens are estimated as one token for every four bytes of text.
+///
+/// # Example
+/// ```rust, no_run
+/// use kalosm_language::prelude::*;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let document = Document::from_parts("Kalosm", "Kalosm is a library for local AI. ".repeat(100));
+///     let bert = Bert::new().await.unwrap();
+///     let chunks = RecursiveChunker::new(128)
+///         .with_overlap(16)
+///         .chunk(&document, &bert)
+///         .await
+///         .unwrap();
+///     println!("{} chunks", chunks.len());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RecursiveChunker {
+    max_tokens: usize,
+    overlap: usize,
+}
+
+impl Default for RecursiveChunker {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+/// The units text is split into, from the largest to the smallest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    Paragraph,
+    Sentence,
+    Word,
+    Character,
+}
+
+impl Level {
+    fn smaller(self) -> Option<Self> {
+        match self {
+            Self::Paragraph => Some(Self::Sentence),
+            Self::Sentence => Some(Self::Word),
+            Self::Word => Some(Self::Character),
+            Self::Character => None,
+        }
+    }
+}
+
+impl RecursiveChunker {
+    /// Create a new [`RecursiveChunker`] with chunks of at most `max_tokens` tokens and no overlap.
+    pub fn new(max_tokens: usize) -> Self {
+        Self {
+            max_tokens: max_tokens.max(1),
+            overlap: 0,
+        }
+    }
+
+    /// Set the maximum number of tokens neighboring chunks share. The overlap is always smaller than the maximum number of tokens in a chunk. (default: 0)
+    pub fn with_overlap(mut self, overlap: usize) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    /// Split text into chunks with at most the maximum number of tokens, as counted by `count_tokens`.
+    ///
+    /// A single character that is larger than the maximum number of tokens is kept as its own chunk.
+    pub fn split(&self, text: &str, count_tokens: impl Fn(&str) -> usize) -> Vec<Range<usize>> {
+        let count = |range: &Range<usize>| count_tokens(&text[range.clone()]);
+        let sentences = SentenceChunker::default();
+
+        let mut pieces = Vec::new();
+        self.split_range(
+            text,
+            0..text.len(),
+            Level::Paragraph,
+            &sentences,
+            &count,
+            &mut pieces,
+        );
+
+        // Merge the pieces back together into the largest chunks that fit
+        let mut chunks = Vec::new();
+        let mut first = 0;
+        while first < pieces.len() {
+            let mut last = first;
+            while last + 1 < pieces.len()
+                && count(&(pieces[first].start..pieces[last + 1].end)) <= self.max_tokens
+            {
+                last += 1;
+            }
+            chunks.push(pieces[first].start..pieces[last].end);
+            if last + 1 >= pieces.len() {
+                break;
+            }
+
+            // Start the next chunk with the pieces at the end of this chunk that fit in the overlap
+            let mut next = last + 1;
+            while next - 1 > first
+                && count(&(pieces[next - 1].start..pieces[last].end)) <= self.overlap
+                && count(&(pieces[next - 1].start..pieces[last + 1].end)) <= self.max_tokens
+            {
+                next -= 1;
+            }
+            first = next;
+        }
+
+        chunks
+            .into_iter()
+            .filter_map(|range| trim_range(text, range))
+            .collect()
+    }
+
+    /// Split a range of text into pieces that each fit in a chunk.
+    fn split_range(
+        &self,
+        text: &str,
+        range: Range<usize>,
+        level: Level,
+        sentences: &SentenceChunker,
+        count: &impl Fn(&Range<usize>) -> usize,
+        pieces: &mut Vec<Range<usize>>,
+    ) {
+        if count(&range) <= self.max_tokens {
+            pieces.push(range);
+            return;
+        }
+        let units = split_units(text, range.clone(), level, sentences);
+        match level.smaller() {
+            // If the text can't be split at this level, try the next level
+            Some(smaller) if units.len() <= 1 => {
+                self.split_range(text, range, smaller, sentences, count, pieces)
+            }
+            Some(smaller) => {
+                for unit in units {
+                    self.split_range(text, unit, smaller, sentences, count, pieces);
+                }
+            }
+            None => pieces.extend(units),
+        }
+    }
+}
+
+/// Split a range of text into neighboring units at a level. The units cover the whole range.
+fn split_units(
+    text: &str,
+    range: Range<usize>,
+    level: Level,
+    sentences: &SentenceChunker,
+) -> Vec<Range<usize>> {
+    let slice = &text[range.clone()];
+    let mut starts = vec![0];
+    match level {
+        Level::Paragraph => {
+            // A paragraph ends after one or more empty lines
+            let mut offset = 0;
+            let mut after_empty_line = false;
+            for line in slice.split_inclusive('\n') {
+                let empty = line.trim().is_empty();
+                if after_empty_line && !empty {
+                    starts.push(offset);
+                }
+                after_empty_line = empty && offset > 0;
+                offset += line.len();
+            }
+        }
+        Level::Sentence => {
+            starts.extend(
+                sentences
+                    .split_sentences(slice)
+                    .into_iter()
+                    .map(|sentence| sentence.start),
+            );
+        }
+        Level::Word => {
+            let mut previous_whitespace = false;
+            for (i, c) in slice.char_indices() {
+                if previous_whitespace && !c.is_whitespace() {
+                    starts.push(i);
+                }
+                previous_whitespace = c.is_whitespace();
+            }
+        }
+        Level::Character => starts.extend(slice.char_indices().map(|(i, _)| i)),
+    }
+    starts.sort_unstable();
+    starts.dedup();
+    starts.retain(|&start| start < slice.len());
+
+    let mut units = Vec::with_capacity(starts.len());
+    for (i, &start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).copied().unwrap_or(slice.len());
+        units.push(range.start + start..range.start + end);
+    }
+    units
+}
+
+fn trim_range(text: &str, range: Range<usize>) -> Option<Range<usize>> {
+    let slice = &text[range.clone()];
+    let trimmed = slice.trim_start();
+    let start = range.start + slice.len() - trimmed.len();
+    let end = start + trimmed.trim_end().len();
+    (start < end).then_some(start..end)
+}
+
+impl Chunker for RecursiveChunker {
+    async fn chunk<E: Embedder + Send>(
+        &self,
+        document: &Document,
+        embedder: &E,
+    ) -> anyhow::Result<Vec<Chunk<E::VectorSpace>>> {
+        let body = document.body();
+        let ranges = self.split(body, |text| {
+            embedder
+                .count_tokens(text)
+                .unwrap_or_else(|| estimate_tokens(text))
+        });
+        let texts = ranges
+            .iter()
+            .map(|range| body[range.clone()].to_string())
+            .collect();
+        let embeddings = embedder.embed_vec(texts).await?;
+
+        Ok(ranges
+            .into_iter()
+            .zip(embeddings)
+            .map(|(byte_range, embedding)| {
+                Chunk::from_document(document, byte_range, vec![embedding])
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+fn check_chunks(chunker: &RecursiveChunker, text: &str) -> Vec<String> {
+    let count = |text: &str| text.chars().count();
+    let chunks = chunker.split(text, count);
+    for chunk in &chunks {
+        assert!(count(&text[chunk.clone()]) <= chunker.max_tokens);
+    }
+    // Without overlap, the chunks only leave out whitespace between them
+    if chunker.overlap == 0 {
+        let without_whitespace = |text: &str| text.split_whitespace().collect::<String>();
+        let chunked = chunks
+            .iter()
+            .map(|chunk| &text[chunk.clone()])
+            .collect::<String>();
+        assert_eq!(without_whitespace(&chunked), without_whitespace(text));
+    }
+    chunks
+        .into_iter()
+        .map(|chunk| text[chunk].to_string())
+        .collect()
+}
+
+#[test]
+fn chunks_fit_in_the_token_budget() {
+    // Count one token per character to keep the test independent of any model
+    let text = "Kalosm is a library for local AI.\n\nIt has language models, audio models and image models. The models run on your own hardware.\n\nThe end.";
+    let chunks = check_chunks(&RecursiveChunker::new(60), text);
+    assert_eq!(
+        chunks,
+        [
+            "Kalosm is a library for local AI.",
+            "It has language models, audio models and image models.",
+            "The models run on your own hardware.\n\nThe end."
+        ]
+    );
+
+    // Long sentences are split into words
+    let chunks = check_chunks(&RecursiveChunker::new(20), text);
+    assert_eq!(chunks[0], "Kalosm is a library");
+}
+
+#[test]
+fn chunks_overlap() {
+    let text = "one two three four five six seven eight nine ten";
+    let chunks = check_chunks(&RecursiveChunker::new(14).with_overlap(6), text);
+    assert_eq!(
+        chunks,
+        [
+            "one two three",
+            "three four",
+            "four five six",
+            "six seven",
+            "seven eight",
+            "eight nine ten"
+        ]
+    );
+}
+
+#[test]
+fn multilingual_text_is_split_along_sentences() {
+    let german = "Das Wetter ist heute schön. Wir gehen in den Park. Danach essen wir ein Eis.";
+    let chunks = check_chunks(&RecursiveChunker::new(40), german);
+    assert_eq!(
+        chunks,
+        [
+            "Das Wetter ist heute schön.",
+            "Wir gehen in den Park.",
+            "Danach essen wir ein Eis."
+        ]
+    );
+
+    let french =
+        "L'été dernier, nous sommes allés à la plage. C'était très agréable ! Nous y retournerons.";
+    let chunks = check_chunks(&RecursiveChunker::new(50), french);
+    assert_eq!(
+        chunks,
+        [
+            "L'été dernier, nous sommes allés à la plage.",
+            "C'était très agréable ! Nous y retournerons."
+        ]
+    );
+
+    // Text without spaces is split into characters if the sentences are too long
+    let japanese = "今日は天気がいいです。公園に行きましょう。その後でアイスクリームを食べます。";
+    let chunks = check_chunks(&RecursiveChunker::new(12), japanese);
+    assert!(chunks.len() >= 4);
+    assert_eq!(chunks.concat(), japanese);
+}
@@ -30,6 +30,7 @@ struct SemanticChunk<S: VectorSpace> {
 /// A chunker that tries to create chunks of wroughly the same size while grouping together chunks with a similar meaning.
 ///
 /// It starts by embedding the text and then merges chunks together while trying to create chunks with one coherent meaning without too many sentences.
+#[derive(Debug, Clone)]
 pub struct SemanticChunker {
     /// The score we are trying to achieve when merging chunks together. Once we reach this score, we stop merging chunks together.
     target_score: f32,
@@ -228,10 +229,7 @@ impl Chunker for SemanticChunker {
             let SemanticChunk {
                 range, embedding, ..
             } = chunk;
-            final_chunks.push(Chunk {
-                byte_range: range,
-                embeddings: vec![embedding],
-            });
+            final_chunks.push(Chunk::from_document(document, range, vec![embedding]));
         }
 
         Ok(final_chunks)
@@ -78,8 +78,11 @@ impl Chunker for SentenceChunker {
         let mut initial_chunks = Vec::new();
         let body = document.body();
         let ranges = self.split_sentences(document.body());
+        let metadata = document.metadata().clone();
+        let mut provenance = Vec::with_capacity(ranges.len());
         for chunk in &ranges {
             initial_chunks.push(body[chunk.clone()].to_string());
+            provenance.push(document.provenance(chunk.clone()));
         }
 
         async move {
@@ -88,11 +91,12 @@ impl Chunker for SentenceChunker {
 
             // Now merge the embeddings and ranges into chunks
             let mut chunks = Vec::new();
-            for (embedding, chunk) in embeddings.into_iter().zip(ranges) {
-                let chunk = Chunk {
-                    byte_range: chunk,
-                    embeddings: vec![embedding],
-                };
+            for ((embedding, chunk), provenance) in
+                embeddings.into_iter().zip(ranges).zip(provenance)
+            {
+                let chunk = Chunk::new(chunk, vec![embedding])
+                    .with_provenance(provenance)
+                    .with_metadata(metadata.clone());
                 chunks.push(chunk);
             }
 
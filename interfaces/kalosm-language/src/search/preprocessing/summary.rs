@@ -100,10 +100,11 @@ where
                 byte_chunk = byte_chunks.next().unwrap();
             }
             remaining_embeddings -= 1;
-            chunks.push(Chunk {
-                byte_range: byte_chunk.clone(),
-                embeddings: vec![embedding],
-            });
+            chunks.push(Chunk::from_document(
+                document,
+                byte_chunk.clone(),
+                vec![embedding],
+            ));
         }
         Ok(chunks)
     }
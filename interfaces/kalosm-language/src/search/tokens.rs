@@ -0,0 +1,133 @@
+/// Estimate the number of tokens in text when the tokenizer is not known. Most tokenizers average about four bytes per token in English text.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
+/// Truncate the text at a word boundary so that it contains at most `max_tokens` tokens according to `count_tokens`.
+///
+/// Returns an empty string if the first word of the text does not fit in the budget.
+pub fn truncate_to_tokens(
+    text: &str,
+    max_tokens: usize,
+    count_tokens: impl Fn(&str) -> usize,
+) -> &str {
+    if count_tokens(text) <= max_tokens {
+        return text;
+    }
+
+    // The end of every word in the text
+    let word_ends: Vec<usize> = text
+        .split_whitespace()
+        .map(|word| word.as_ptr() as usize - text.as_ptr() as usize + word.len())
+        .collect();
+
+    let words = longest_prefix(word_ends.len(), |words| {
+        count_tokens(&text[..word_ends[words - 1]]) <= max_tokens
+    });
+    match words {
+        0 => "",
+        words => &text[..word_ends[words - 1]],
+    }
+}
+
+/// Split text into sections that each contain at most `max_tokens` tokens according to `count_tokens`.
+///
+/// Sections end at a line break or between words when possible. Words that do not fit in the budget on their own are split between characters.
+/// A budget of zero tokens is treated as one token.
+pub fn split_to_token_budget(
+    text: &str,
+    max_tokens: usize,
+    count_tokens: impl Fn(&str) -> usize,
+) -> Vec<&str> {
+    let max_tokens = max_tokens.max(1);
+    let mut sections = Vec::new();
+    let mut remaining = text.trim();
+    while !remaining.is_empty() {
+        let mut section = truncate_to_tokens(remaining, max_tokens, &count_tokens);
+        if section.len() < remaining.len() {
+            if let Some(line_end) = section.rfind('\n').filter(|&end| end > 0) {
+                section = &section[..line_end];
+            }
+        }
+        if section.is_empty() {
+            section = truncate_to_chars(remaining, max_tokens, &count_tokens);
+        }
+        sections.push(section.trim_end());
+        remaining = remaining[section.len()..].trim_start();
+    }
+    if sections.is_empty() {
+        sections.push("");
+    }
+    sections
+}
+
+/// Truncate the text between characters so that it contains at most `max_tokens` tokens. At least one character is always kept so splitting makes progress.
+fn truncate_to_chars(text: &str, max_tokens: usize, count_tokens: impl Fn(&str) -> usize) -> &str {
+    let char_ends: Vec<usize> = text
+        .char_indices()
+        .map(|(start, char)| start + char.len_utf8())
+        .collect();
+    let chars = longest_prefix(char_ends.len(), |chars| {
+        count_tokens(&text[..char_ends[chars - 1]]) <= max_tokens
+    });
+    match char_ends.get(chars.max(1) - 1) {
+        Some(&end) => &text[..end],
+        None => "",
+    }
+}
+
+/// Find the largest `n` in `0..=len` where `fits(n)` is true, assuming `fits` is true for every smaller `n` above zero.
+fn longest_prefix(len: usize, fits: impl Fn(usize) -> bool) -> usize {
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let mid = (low + high + 1) / 2;
+        if fits(mid) {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    low
+}
+
+#[test]
+fn text_is_truncated_at_word_boundaries() {
+    let count_words = |text: &str| text.split_whitespace().count();
+    let text = "one two  three\nfour five";
+    assert_eq!(truncate_to_tokens(text, 10, count_words), text);
+    assert_eq!(truncate_to_tokens(text, 5, count_words), text);
+    assert_eq!(truncate_to_tokens(text, 3, count_words), "one two  three");
+    assert_eq!(truncate_to_tokens(text, 1, count_words), "one");
+    assert_eq!(truncate_to_tokens(text, 0, count_words), "");
+
+    // Token counters that count more than one token per word are respected
+    let count_chars = |text: &str| text.chars().count();
+    assert_eq!(truncate_to_tokens(text, 8, count_chars), "one two");
+    assert_eq!(truncate_to_tokens(text, 2, count_chars), "");
+}
+
+#[test]
+fn text_is_split_to_the_token_budget() {
+    let text = "one two three\nfour five six seven";
+    let sections = split_to_token_budget(text, 4, estimate_tokens);
+    assert_eq!(sections, ["one two three", "four five six", "seven"]);
+    assert!(sections.iter().all(|section| estimate_tokens(section) <= 4));
+
+    // Line breaks are preferred over spaces
+    let count_words = |text: &str| text.split_whitespace().count();
+    assert_eq!(
+        split_to_token_budget("one\ntwo three", 2, count_words),
+        ["one", "two three"]
+    );
+
+    // Multi-byte characters are never split, even with a budget of zero
+    assert_eq!(
+        split_to_token_budget("ééééé", 1, estimate_tokens),
+        ["éé", "éé", "é"]
+    );
+    assert_eq!(
+        split_to_token_budget("ééé", 0, estimate_tokens),
+        ["éé", "é"]
+    );
+    assert_eq!(split_to_token_budget("  ", 4, estimate_tokens), [""]);
+}
@@ -0,0 +1,606 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use kalosm_sample::{CreateParserState, Either, LiteralParser, ParserExt, RegexParser};
+use url::Url;
+
+use crate::context::{ArticleFormat, Node, Tab};
+use crate::search::{estimate_tokens, split_to_token_budget};
+use crate::tool::Tool;
+
+/// A browser tab shared between the browser tools.
+///
+/// Every browser tool created from the same session acts on the same tab, so an agent can open a page with [`OpenUrlTool`]
+/// and then read it, click on it and type into it with the other tools.
+///
+/// # Example
+/// ```rust, no_run
+/// use kalosm_language::prelude::*;
+///
+/// let session = BrowserSession::new();
+/// let tools = ToolManager::from(session.tools());
+/// println!("{}", tools.prompt("Who maintains Floneum?"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct BrowserSession {
+    tab: Arc<Mutex<Option<Tab>>>,
+    headless: bool,
+}
+
+impl Default for BrowserSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<Tab> for BrowserSession {
+    fn from(tab: Tab) -> Self {
+        Self {
+            tab: Arc::new(Mutex::new(Some(tab))),
+            headless: true,
+        }
+    }
+}
+
+impl BrowserSession {
+    /// Create a new session. The tab is opened the first time a URL is opened.
+    pub fn new() -> Self {
+        Self {
+            tab: Arc::new(Mutex::new(None)),
+            headless: true,
+        }
+    }
+
+    /// Set whether the tab is opened in a headless browser. (default: true)
+    pub fn with_headless(mut self, headless: bool) -> Self {
+        self.headless = headless;
+        self
+    }
+
+    /// Create all of the browser tools for this session.
+    pub fn tools(
+        &self,
+    ) -> (
+        OpenUrlTool,
+        ReadPageTool,
+        ClickTool,
+        TypeTool,
+        ExtractLinksTool,
+    ) {
+        (
+            OpenUrlTool::new(self.clone()),
+            ReadPageTool::new(self.clone()),
+            ClickTool::new(self.clone()),
+            TypeTool::new(self.clone()),
+            ExtractLinksTool::new(self.clone()),
+        )
+    }
+
+    /// Open a URL in the tab, opening the tab if it isn't open yet.
+    async fn open(&self, url: Url) -> anyhow::Result<String> {
+        let tab = self.tab.clone();
+        let headless = self.headless;
+        tokio::task::spawn_blocking(move || -> anyhow::Result<String> {
+            let mut tab = tab.lock().unwrap();
+            match tab.as_ref() {
+                Some(tab) => tab.goto(url.as_str())?,
+                None => *tab = Some(Tab::new(url, headless)?),
+            }
+            let tab = tab.as_ref().unwrap();
+            let _ = tab.wait_for_network_idle(Duration::from_millis(500), Duration::from_secs(10));
+            Ok(describe_page(tab))
+        })
+        .await?
+    }
+
+    /// Run a blocking action on the open tab.
+    async fn with_tab<R: Send + 'static>(
+        &self,
+        action: impl FnOnce(&Tab) -> anyhow::Result<R> + Send + 'static,
+    ) -> anyhow::Result<R> {
+        let tab = self.tab.clone();
+        tokio::task::spawn_blocking(move || {
+            let tab = tab.lock().unwrap();
+            let tab = tab
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("No page is open. Open a URL first"))?;
+            action(tab)
+        })
+        .await?
+    }
+}
+
+/// Describe the page the tab is on with its title and URL.
+fn describe_page(tab: &Tab) -> String {
+    let title = tab.title().unwrap_or_default();
+    format!("{} ({})", title.trim(), tab.url())
+}
+
+/// Truncate text to a token budget, noting how much of the text was cut off.
+fn truncate_with_note(text: &str, max_tokens: usize) -> String {
+    let sections = split_to_token_budget(text, max_tokens, estimate_tokens);
+    let mut truncated = sections[0].to_string();
+    if sections.len() > 1 {
+        truncated.push_str(&format!(
+            "\n[Truncated to about {max_tokens} of {} tokens]",
+            estimate_tokens(text)
+        ));
+    }
+    truncated
+}
+
+/// A parser for one non-empty line of text. The line break is not included in the output.
+fn line_parser(
+) -> impl CreateParserState<Output = String, PartialState: Send + Sync + 'static> + Send + Sync + 'static
+{
+    RegexParser::new(r"[^\n]+\n")
+        .unwrap()
+        .map_output(|line| line.trim().to_string())
+}
+
+/// The element a browser tool acts on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ElementTarget {
+    /// The first element matching a CSS selector.
+    Selector(String),
+    /// The visible element with the closest matching text, label, placeholder or value.
+    Text(String),
+}
+
+impl std::fmt::Display for ElementTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ElementTarget::Selector(selector) => write!(f, "the element matching {selector}"),
+            ElementTarget::Text(text) => write!(f, "the element with the text \"{text}\""),
+        }
+    }
+}
+
+impl ElementTarget {
+    /// Create a parser for `selector: <css selector>` or `text: <visible text>` on one line.
+    fn parser(
+    ) -> impl CreateParserState<Output = Self, PartialState: Send + Sync + 'static> + Send + Sync + 'static
+    {
+        LiteralParser::from("selector: ")
+            .then(line_parser())
+            .otherwise(LiteralParser::from("text: ").then(line_parser()))
+            .map_output(|target| match target {
+                Either::Left((_, selector)) => ElementTarget::Selector(selector),
+                Either::Right((_, text)) => ElementTarget::Text(text),
+            })
+    }
+
+    /// Find the element in the tab. `candidates` is the selector of the elements that can match a text target.
+    fn find<'a>(&self, tab: &'a Tab, candidates: &str) -> anyhow::Result<Node<'a>> {
+        const MARKER: &str = "data-kalosm-target";
+        let selector = match self {
+            ElementTarget::Selector(selector) => selector.clone(),
+            ElementTarget::Text(text) => {
+                // Mark the best matching element so it can be found with a selector
+                let found: bool = tab.evaluate(&format!(
+                    r#"(() => {{
+                        const wanted = {text}.trim().toLowerCase();
+                        for (const old of document.querySelectorAll("[{MARKER}]")) old.removeAttribute("{MARKER}");
+                        const label = (element) => [
+                            element.innerText,
+                            element.value,
+                            element.placeholder,
+                            element.getAttribute("aria-label"),
+                            element.title,
+                            element.labels && [...element.labels].map((label) => label.innerText).join(" "),
+                        ].filter((text) => typeof text === "string" && text.length > 0).join(" ").trim().toLowerCase();
+                        let best = null;
+                        for (const element of document.querySelectorAll({candidates})) {{
+                            const rect = element.getBoundingClientRect();
+                            if (rect.width === 0 && rect.height === 0) continue;
+                            const text = label(element);
+                            if (!text.includes(wanted)) continue;
+                            const score = (text === wanted ? 0 : 1e6) + text.length;
+                            if (best === null || score < best.score) best = {{ element, score }};
+                        }}
+                        if (best === null) return false;
+                        best.element.setAttribute("{MARKER}", "");
+                        return true;
+                    }})()"#,
+                    text = serde_json::to_string(text)?,
+                    candidates = serde_json::to_string(candidates)?,
+                ))?;
+                if !found {
+                    anyhow::bail!("No visible element contains the text \"{text}\"");
+                }
+                format!("[{MARKER}]")
+            }
+        };
+        tab.wait_for(&selector, Duration::from_secs(5))
+    }
+}
+
+/// The elements a [`ClickTool`] can click by text.
+const CLICKABLE_ELEMENTS: &str = "a, button, input, select, textarea, summary, label, [role=button], [role=link], [role=tab], [role=menuitem], [role=checkbox], [role=option], [onclick]";
+
+/// The elements a [`TypeTool`] can type into by text.
+const TYPEABLE_ELEMENTS: &str =
+    "input:not([type=hidden]):not([type=submit]):not([type=button]), textarea, [contenteditable]";
+
+/// A tool that opens a URL in a [`BrowserSession`]
+pub struct OpenUrlTool {
+    session: BrowserSession,
+}
+
+impl OpenUrlTool {
+    /// Create a new tool that opens URLs in a session
+    pub fn new(session: BrowserSession) -> Self {
+        Self { session }
+    }
+}
+
+impl Tool for OpenUrlTool {
+    type Input = String;
+
+    fn input_parser(
+        &self,
+    ) -> impl CreateParserState<Output = Self::Input, PartialState: Send + Sync + 'static>
+           + Send
+           + Sync
+           + 'static {
+        RegexParser::new(r"https?://[!-~]+\n")
+            .unwrap()
+            .map_output(|url| url.trim().to_string())
+    }
+
+    fn name(&self) -> String {
+        "Open URL".to_string()
+    }
+
+    fn input_prompt(&self) -> String {
+        "URL: ".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Open a web page in the browser. Use Read Page to read it after it is open.\nUse tool with:\nAction: Open URL\nURL: the URL of the page\nExample:\n\nQuestion: What is the latest version of Kalosm?\nThought: The crates.io page of Kalosm lists the latest version.\nAction: Open URL\nURL: https://crates.io/crates/kalosm\nObservation: Opened kalosm - crates.io (https://crates.io/crates/kalosm)\n".to_string()
+    }
+
+    async fn run<'a>(&'a mut self, url: &'a Self::Input) -> String {
+        let url = match Url::parse(url) {
+            Ok(url) => url,
+            Err(err) => return format!("Failed to open {url}: {err}"),
+        };
+        match self.session.open(url.clone()).await {
+            Ok(page) => format!("Opened {page}"),
+            Err(err) => format!("Failed to open {url}: {err}"),
+        }
+    }
+}
+
+/// A tool that reads the page open in a [`BrowserSession`] as markdown
+///
+/// Pages longer than the token budget are split into sections that can be read one at a time.
+pub struct ReadPageTool {
+    session: BrowserSession,
+    max_tokens: usize,
+}
+
+impl ReadPageTool {
+    /// Create a new tool that reads the page open in a session
+    pub fn new(session: BrowserSession) -> Self {
+        Self {
+            session,
+            max_tokens: 1000,
+        }
+    }
+
+    /// Set the maximum number of tokens in one section of the page. Budgets below one token are raised to one. (default: 1000)
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = max_tokens.max(1);
+        self
+    }
+}
+
+impl Tool for ReadPageTool {
+    type Input = usize;
+
+    fn input_parser(
+        &self,
+    ) -> impl CreateParserState<Output = Self::Input, PartialState: Send + Sync + 'static>
+           + Send
+           + Sync
+           + 'static {
+        RegexParser::new(r"[1-9][0-9]{0,3}\n")
+            .unwrap()
+            .map_output(|section| section.trim().parse::<usize>().unwrap())
+    }
+
+    fn name(&self) -> String {
+        "Read Page".to_string()
+    }
+
+    fn input_prompt(&self) -> String {
+        "Section: ".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Read the page open in the browser as markdown. Long pages are split into numbered sections, starting at 1.\nUse tool with:\nAction: Read Page\nSection: the number of the section to read\nExample:\n\nThought: I should read the page I opened.\nAction: Read Page\nSection: 1\nObservation: # Kalosm\n...\n(Section 1 of 3. Read section 2 to continue.)\n".to_string()
+    }
+
+    async fn run<'a>(&'a mut self, section: &'a Self::Input) -> String {
+        let page = self.session.with_tab(|tab| {
+            let article = tab.article_with_format(ArticleFormat::Markdown)?;
+            Ok((describe_page(tab), article.body().to_string()))
+        });
+        let (page, body) = match page.await {
+            Ok(page) => page,
+            Err(err) => return format!("Failed to read the page: {err}"),
+        };
+        let sections = split_to_token_budget(&body, self.max_tokens, estimate_tokens);
+        let Some(text) = sections.get(section - 1) else {
+            return format!(
+                "The page only has {} sections. Read a section from 1 to {}.",
+                sections.len(),
+                sections.len()
+            );
+        };
+        let mut output = format!("{page}\n\n{text}");
+        if sections.len() > 1 {
+            output.push_str(&format!("\n(Section {section} of {}.", sections.len()));
+            if *section < sections.len() {
+                output.push_str(&format!(" Read section {} to continue.", section + 1));
+            }
+            output.push(')');
+        }
+        output
+    }
+}
+
+/// A tool that clicks an element on the page open in a [`BrowserSession`]
+pub struct ClickTool {
+    session: BrowserSession,
+}
+
+impl ClickTool {
+    /// Create a new tool that clicks elements in a session
+    pub fn new(session: BrowserSession) -> Self {
+        Self { session }
+    }
+}
+
+impl Tool for ClickTool {
+    type Input = ElementTarget;
+
+    fn input_parser(
+        &self,
+    ) -> impl CreateParserState<Output = Self::Input, PartialState: Send + Sync + 'static>
+           + Send
+           + Sync
+           + 'static {
+        ElementTarget::parser()
+    }
+
+    fn name(&self) -> String {
+        "Click".to_string()
+    }
+
+    fn input_prompt(&self) -> String {
+        "Element ".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Click an element on the page open in the browser, found by its visible text or a CSS selector.\nUse tool with:\nAction: Click\nElement text: the visible text of the element\nor\nAction: Click\nElement selector: the CSS selector of the element\nExample:\n\nThought: The documentation link should explain how to get started.\nAction: Click\nElement text: Documentation\nObservation: Clicked the element with the text \"Documentation\". The page is now Docs (https://floneum.com/docs)\n".to_string()
+    }
+
+    async fn run<'a>(&'a mut self, target: &'a Self::Input) -> String {
+        let action_target = target.clone();
+        let result = self.session.with_tab(move |tab| {
+            action_target.find(tab, CLICKABLE_ELEMENTS)?.click()?;
+            let _ = tab.wait_for_network_idle(Duration::from_millis(500), Duration::from_secs(10));
+            Ok(describe_page(tab))
+        });
+        match result.await {
+            Ok(page) => format!("Clicked {target}. The page is now {page}"),
+            Err(err) => format!("Failed to click {target}: {err}"),
+        }
+    }
+}
+
+/// The input to a [`TypeTool`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeInput {
+    /// The field to type into
+    pub target: ElementTarget,
+    /// The text to type
+    pub text: String,
+}
+
+/// A tool that types text into a field on the page open in a [`BrowserSession`]
+pub struct TypeTool {
+    session: BrowserSession,
+}
+
+impl TypeTool {
+    /// Create a new tool that types into fields in a session
+    pub fn new(session: BrowserSession) -> Self {
+        Self { session }
+    }
+}
+
+impl Tool for TypeTool {
+    type Input = TypeInput;
+
+    fn input_parser(
+        &self,
+    ) -> impl CreateParserState<Output = Self::Input, PartialState: Send + Sync + 'static>
+           + Send
+           + Sync
+           + 'static {
+        ElementTarget::parser()
+            .then(LiteralParser::from("Text: "))
+            .then(line_parser())
+            .map_output(|((target, _), text)| TypeInput { target, text })
+    }
+
+    fn name(&self) -> String {
+        "Type".to_string()
+    }
+
+    fn input_prompt(&self) -> String {
+        "Field ".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Type text into a field on the page open in the browser, found by its label, placeholder or a CSS selector. Any text already in the field is replaced.\nUse tool with:\nAction: Type\nField text: the label or placeholder of the field\nText: the text to type\nor\nAction: Type\nField selector: the CSS selector of the field\nText: the text to type\nExample:\n\nThought: I should search the documentation for tools.\nAction: Type\nField text: Search\nText: tools\nObservation: Typed \"tools\" into the element with the text \"Search\"\n".to_string()
+    }
+
+    async fn run<'a>(&'a mut self, input: &'a Self::Input) -> String {
+        let TypeInput { target, text } = input.clone();
+        let result = self.session.with_tab(move |tab| {
+            let node = target.find(tab, TYPEABLE_ELEMENTS)?;
+            let _: bool = node.call_js_fn(
+                "function() { if ('value' in this) { this.value = ''; } else { this.textContent = ''; } return true; }",
+                Vec::new(),
+                false,
+            )?;
+            node.send_keys(&text)
+        });
+        match result.await {
+            Ok(()) => format!("Typed \"{}\" into {}", input.text, input.target),
+            Err(err) => format!("Failed to type into {}: {err}", input.target),
+        }
+    }
+}
+
+/// A tool that lists the links on the page open in a [`BrowserSession`]
+pub struct ExtractLinksTool {
+    session: BrowserSession,
+    max_tokens: usize,
+}
+
+impl ExtractLinksTool {
+    /// Create a new tool that lists the links in a session
+    pub fn new(session: BrowserSession) -> Self {
+        Self {
+            session,
+            max_tokens: 500,
+        }
+    }
+
+    /// Set the maximum number of tokens in the list of links. Budgets below one token are raised to one. (default: 500)
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = max_tokens.max(1);
+        self
+    }
+}
+
+impl Tool for ExtractLinksTool {
+    type Input = String;
+
+    fn input_parser(
+        &self,
+    ) -> impl CreateParserState<Output = Self::Input, PartialState: Send + Sync + 'static>
+           + Send
+           + Sync
+           + 'static {
+        RegexParser::new(r"[^\n]*\n")
+            .unwrap()
+            .map_output(|filter| filter.trim().to_string())
+    }
+
+    fn name(&self) -> String {
+        "Extract Links".to_string()
+    }
+
+    fn input_prompt(&self) -> String {
+        "Filter: ".to_string()
+    }
+
+    fn description(&self) -> String {
+        "List the links on the page open in the browser. Only links with the filter in their text or URL are listed. Leave the filter empty to list every link.\nUse tool with:\nAction: Extract Links\nFilter: text to filter the links by\nExample:\n\nThought: I should find the link to the releases.\nAction: Extract Links\nFilter: release\nObservation: - [Releases](https://github.com/floneum/floneum/releases)\n".to_string()
+    }
+
+    async fn run<'a>(&'a mut self, filter: &'a Self::Input) -> String {
+        let links = self.session.with_tab(|tab| {
+            let links: Vec<(String, String)> = tab.evaluate(
+                r#"[...document.querySelectorAll("a[href]")].map((link) => [link.innerText.trim().replace(/\s+/g, " "), link.href])"#,
+            )?;
+            Ok(links)
+        });
+        let links = match links.await {
+            Ok(links) => links,
+            Err(err) => return format!("Failed to extract links: {err}"),
+        };
+        let filter = filter.to_lowercase();
+        let mut seen = std::collections::HashSet::new();
+        let mut list = String::new();
+        for (text, href) in links {
+            if !(href.starts_with("http://") || href.starts_with("https://")) {
+                continue;
+            }
+            if !text.to_lowercase().contains(&filter) && !href.to_lowercase().contains(&filter) {
+                continue;
+            }
+            if seen.insert(href.clone()) {
+                list.push_str(&format!("- [{text}]({href})\n"));
+            }
+        }
+        if list.is_empty() {
+            return "No links found.".to_string();
+        }
+        truncate_with_note(&list, self.max_tokens)
+    }
+}
+
+#[test]
+fn browser_tool_inputs_are_constrained() {
+    use kalosm_sample::{ParseStatus, Parser};
+
+    fn parse<P: CreateParserState>(parser: &P, input: &str) -> Option<P::Output> {
+        match parser.parse(&parser.create_parser_state(), input.as_bytes()) {
+            Ok(ParseStatus::Finished { result, .. }) => Some(result),
+            _ => None,
+        }
+    }
+
+    let session = BrowserSession::new();
+    let (open, read, click, type_into, _) = session.tools();
+
+    let url = open.input_parser();
+    assert_eq!(
+        parse(&url, "https://floneum.com/docs?page=1\n"),
+        Some("https://floneum.com/docs?page=1".to_string())
+    );
+    assert_eq!(parse(&url, "floneum docs\n"), None);
+
+    assert_eq!(parse(&read.input_parser(), "12\n"), Some(12));
+    assert_eq!(parse(&read.input_parser(), "0\n"), None);
+
+    let element = click.input_parser();
+    assert_eq!(
+        parse(&element, "text: Sign in now\n"),
+        Some(ElementTarget::Text("Sign in now".to_string()))
+    );
+    assert_eq!(
+        parse(&element, "selector: nav > a.docs\n"),
+        Some(ElementTarget::Selector("nav > a.docs".to_string()))
+    );
+    assert_eq!(parse(&element, "button\n"), None);
+
+    assert_eq!(
+        parse(
+            &type_into.input_parser(),
+            "selector: #search\nText: kalosm tools\n"
+        ),
+        Some(TypeInput {
+            target: ElementTarget::Selector("#search".to_string()),
+            text: "kalosm tools".to_string(),
+        })
+    );
+}
+
+#[test]
+fn output_is_truncated_to_the_token_budget() {
+    assert_eq!(truncate_with_note("short", 4), "short");
+    let truncated = truncate_with_note("one two three\nfour five six seven", 4);
+    assert!(truncated.starts_with("one two three\n[Truncated to about 4 of 9 tokens]"));
+
+    // A budget of zero still reads the page one piece at a time
+    let tool = ReadPageTool::new(BrowserSession::new()).with_max_tokens(0);
+    assert_eq!(tool.max_tokens, 1);
+}
@@ -18,6 +18,8 @@ use kalosm_sample::{
 pub use search::*;
 mod calculator;
 pub use calculator::*;
+mod browser;
+pub use browser::*;
 
 /// A tool that can be used by a [`kalosm_language_model::Model`]
 // TODO: Add example
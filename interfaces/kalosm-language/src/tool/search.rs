@@ -1,7 +1,6 @@
 use kalosm_sample::CreateParserState;
 
-use crate::context::IntoDocuments;
-use crate::context::SearchQuery;
+use crate::context::{SearchProvider, Serper};
 use crate::tool::Tool;
 
 use super::OneLine;
@@ -9,12 +8,21 @@ use super::OneLine;
 /// A tool that can search the web
 pub struct WebSearchTool {
     top_n: usize,
+    provider: Box<dyn SearchProvider>,
 }
 
 impl WebSearchTool {
-    /// Create a new web search tool
+    /// Create a new web search tool that searches with [Serper](https://serper.dev). The API key is read from the `SERPER_API_KEY` environment variable.
     pub fn new(top_n: usize) -> Self {
-        Self { top_n }
+        Self::with_provider(Serper::from_env(), top_n)
+    }
+
+    /// Create a new web search tool that searches with any [`SearchProvider`].
+    pub fn with_provider(provider: impl SearchProvider + 'static, top_n: usize) -> Self {
+        Self {
+            top_n,
+            provider: Box::new(provider),
+        }
     }
 }
 
@@ -43,10 +51,13 @@ impl Tool for WebSearchTool {
     }
 
     async fn run<'a>(&'a mut self, query: &'a Self::Input) -> String {
-        let api_key =
-            std::env::var("SERPER_API_KEY").expect("SERPER_API_KEY environment variable not set");
-        let search_query = SearchQuery::new(query, &api_key, self.top_n);
-        let documents = search_query.into_documents().await.unwrap();
+        let documents = match self.provider.search_documents(query, self.top_n).await {
+            Ok(documents) => documents,
+            Err(err) => return format!("Failed to search the web: {err}"),
+        };
+        if documents.is_empty() {
+            return "No search results found.".to_string();
+        }
         let mut text = String::new();
         for document in documents {
             for word in document.body().split(' ').take(300) {
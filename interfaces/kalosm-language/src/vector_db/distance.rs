@@ -0,0 +1,118 @@
+use arroy::distances::{Angular, DotProduct, Euclidean, Manhattan};
+use arroy::Database as ArroyDatabase;
+use heed::RwTxn;
+use serde::{Deserialize, Serialize};
+
+/// The metric a [`VectorDB`](super::VectorDB) uses to measure the distance between embeddings.
+///
+/// The metric is chosen when the database is created with [`VectorDBBuilder::with_distance`](super::VectorDBBuilder::with_distance) and stored with the database.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DistanceMetric {
+    /// The cosine distance between the embeddings. This ignores the length of the embeddings, and is the right choice for most embedding models.
+    #[default]
+    Angular,
+    /// The straight line (L2) distance between the embeddings.
+    Euclidean,
+    /// The negated dot product of the embeddings. Use this for models trained for dot product similarity.
+    DotProduct,
+    /// The sum of the absolute differences (L1 distance) between the embeddings.
+    Manhattan,
+}
+
+impl std::fmt::Display for DistanceMetric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Angular => write!(f, "angular"),
+            Self::Euclidean => write!(f, "euclidean"),
+            Self::DotProduct => write!(f, "dot product"),
+            Self::Manhattan => write!(f, "manhattan"),
+        }
+    }
+}
+
+impl DistanceMetric {
+    /// Measure how similar two embeddings are with this metric. Larger values are more similar.
+    pub(crate) fn similarity(&self, a: &[f32], b: &[f32]) -> f32 {
+        let pairs = a.iter().zip(b);
+        match self {
+            Self::Angular => {
+                let dot: f32 = pairs.map(|(a, b)| a * b).sum();
+                let norm_a = a.iter().map(|a| a * a).sum::<f32>().sqrt();
+                let norm_b = b.iter().map(|b| b * b).sum::<f32>().sqrt();
+                if norm_a == 0.0 || norm_b == 0.0 {
+                    0.0
+                } else {
+                    dot / (norm_a * norm_b)
+                }
+            }
+            Self::Euclidean => -pairs.map(|(a, b)| (a - b) * (a - b)).sum::<f32>().sqrt(),
+            Self::DotProduct => pairs.map(|(a, b)| a * b).sum(),
+            Self::Manhattan => -pairs.map(|(a, b)| (a - b).abs()).sum::<f32>(),
+        }
+    }
+}
+
+/// The arroy database behind a [`VectorDB`](super::VectorDB), typed by the [`DistanceMetric`] it was created with.
+#[derive(Debug, Clone, Copy)]
+pub enum RawDatabase {
+    /// A database using the [`DistanceMetric::Angular`] distance.
+    Angular(ArroyDatabase<Angular>),
+    /// A database using the [`DistanceMetric::Euclidean`] distance.
+    Euclidean(ArroyDatabase<Euclidean>),
+    /// A database using the [`DistanceMetric::DotProduct`] distance.
+    DotProduct(ArroyDatabase<DotProduct>),
+    /// A database using the [`DistanceMetric::Manhattan`] distance.
+    Manhattan(ArroyDatabase<Manhattan>),
+}
+
+impl RawDatabase {
+    /// Create (or open) the unnamed database in the environment with the given metric.
+    pub(crate) fn create(
+        env: &heed::Env,
+        wtxn: &mut RwTxn,
+        metric: DistanceMetric,
+    ) -> heed::Result<Self> {
+        Ok(match metric {
+            DistanceMetric::Angular => Self::Angular(env.create_database(wtxn, None)?),
+            DistanceMetric::Euclidean => Self::Euclidean(env.create_database(wtxn, None)?),
+            DistanceMetric::DotProduct => Self::DotProduct(env.create_database(wtxn, None)?),
+            DistanceMetric::Manhattan => Self::Manhattan(env.create_database(wtxn, None)?),
+        })
+    }
+
+    /// Get the metric of the database.
+    pub fn metric(&self) -> DistanceMetric {
+        match self {
+            Self::Angular(_) => DistanceMetric::Angular,
+            Self::Euclidean(_) => DistanceMetric::Euclidean,
+            Self::DotProduct(_) => DistanceMetric::DotProduct,
+            Self::Manhattan(_) => DistanceMetric::Manhattan,
+        }
+    }
+}
+
+/// Run the body with the typed arroy database and the arroy distance type of a [`RawDatabase`].
+macro_rules! with_distance {
+    ($database:expr, |$db:ident, $distance:ident| $body:expr) => {
+        match $database {
+            $crate::vector_db::RawDatabase::Angular($db) => {
+                type $distance = arroy::distances::Angular;
+                $body
+            }
+            $crate::vector_db::RawDatabase::Euclidean($db) => {
+                type $distance = arroy::distances::Euclidean;
+                $body
+            }
+            $crate::vector_db::RawDatabase::DotProduct($db) => {
+                type $distance = arroy::distances::DotProduct;
+                $body
+            }
+            $crate::vector_db::RawDatabase::Manhattan($db) => {
+                type $distance = arroy::distances::Manhattan;
+                $body
+            }
+        }
+    };
+}
+
+pub(crate) use with_distance;
@@ -0,0 +1,1150 @@
+//! A vector database that can be used to store embeddings and search for similar embeddings.
+
+use std::fmt::Debug;
+
+use arroy::distances::Angular;
+use arroy::{Database as ArroyDatabase, Reader, Writer};
+use candle_core::Tensor;
+use heed::types::{SerdeBincode, Str};
+use heed::{EnvOpenOptions, RoTxn, RwTxn};
+use kalosm_language_model::*;
+use kalosm_llama::accelerated_device_if_available;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use roaring::RoaringBitmap;
+use serde::{Deserialize, Serialize};
+
+mod distance;
+use distance::with_distance;
+pub use distance::*;
+mod payload;
+pub use payload::*;
+
+/// The number of named databases other indexes (like a [`Bm25Index`](crate::search::Bm25Index)) can create in the environment of a [`VectorDB`].
+const MAX_NAMED_DATABASES: u32 = 16;
+
+/// The key the [`VectorDBMetadata`] is stored under.
+const METADATA_KEY: &str = "metadata";
+
+/// The state of a vector database that is stored in the environment next to the embeddings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VectorDBMetadata {
+    distance: DistanceMetric,
+    dimensions: Option<usize>,
+    max_id: EmbeddingId,
+    recycled_ids: Vec<EmbeddingId>,
+}
+
+impl Default for VectorDBMetadata {
+    fn default() -> Self {
+        Self {
+            distance: DistanceMetric::default(),
+            dimensions: None,
+            max_id: EmbeddingId(0),
+            recycled_ids: Vec::new(),
+        }
+    }
+}
+
+impl VectorDBMetadata {
+    /// Rebuild the metadata from the items in the index. Databases created before the metadata was stored always use the angular distance.
+    fn recover(rtxn: &RoTxn, database: ArroyDatabase<Angular>) -> heed::Result<Self> {
+        let mut metadata = Self::default();
+        let reader = match Reader::<Angular>::open(rtxn, 0, database) {
+            Ok(reader) => reader,
+            // The unnamed database also holds the names of the other databases in the environment, so it is never empty.
+            // Arroy only writes its metadata once embeddings were added, so a missing index means there is nothing to recover.
+            Err(arroy::Error::MissingMetadata { .. }) => return Ok(metadata),
+            Err(arroy::Error::Heed(err)) => return Err(err),
+            Err(err) => {
+                return Err(heed::Error::Io(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    err,
+                )))
+            }
+        };
+        metadata.dimensions = Some(reader.dimensions());
+        let items = reader.item_ids();
+        if let Some(max) = items.max() {
+            metadata.max_id = EmbeddingId(max + 1);
+            metadata.recycled_ids = (0..max)
+                .filter(|id| !items.contains(*id))
+                .map(EmbeddingId)
+                .collect();
+        }
+        Ok(metadata)
+    }
+
+    fn check_dimensions(&mut self, dims: usize) -> anyhow::Result<()> {
+        if dims == 0 {
+            anyhow::bail!("Dimension cannot be 0");
+        }
+        match self.dimensions {
+            Some(expected) if expected != dims => anyhow::bail!(
+                "Embedding has {dims} dimensions, but the vector database contains {expected} dimensional embeddings"
+            ),
+            _ => self.dimensions = Some(dims),
+        }
+        Ok(())
+    }
+
+    fn take_id(&mut self) -> EmbeddingId {
+        self.recycled_ids.pop().unwrap_or_else(|| {
+            let id = self.max_id;
+            self.max_id.0 += 1;
+            id
+        })
+    }
+
+    fn recycle_id(&mut self, id: EmbeddingId) {
+        self.recycled_ids.push(id);
+    }
+}
+
+/// A vector database that can be used to store embeddings and search for similar embeddings.
+///
+/// It uses an in memory database with fast lookups for nearest neighbors and points within a certain distance.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// # use kalosm_language::prelude::*;
+/// # use kalosm_language_model::*;
+/// # use rbert::*;
+/// # use std::collections::HashMap;
+/// # #[tokio::main]
+/// # async fn main() {
+/// // Create a good default Bert model for search
+/// let bert = Bert::new_for_search().await.unwrap();
+/// let sentences = [
+///     "Kalosm can be used to build local AI applications",
+///     "With private LLMs data never leaves your computer",
+///     "The quick brown fox jumps over the lazy dog",
+/// ];
+/// // Embed sentences into the vector space
+/// let embeddings = bert.embed_batch(sentences).await.unwrap();
+/// println!("embeddings {:?}", embeddings);
+///
+/// // Create a vector database from the embeddings along with a map between the embedding ids and the sentences
+/// let db = VectorDB::new().unwrap();
+/// let embeddings = db.add_embeddings(embeddings).unwrap();
+/// let embedding_id_to_sentence: HashMap<EmbeddingId, &str> =
+///     HashMap::from_iter(embeddings.into_iter().zip(sentences));
+///
+/// // Find the closest sentence to "What is Kalosm?"
+/// let query = "What is Kalosm?";
+/// // Embed the query into the vector space. We use `embed_query` instead of `embed` because some models embed queries differently than normal text.
+/// let embedding = bert.embed_query(query).await.unwrap();
+/// let closest = db.get_closest(embedding, 1).unwrap();
+/// if let [closest] = closest.as_slice() {
+///     let distance = closest.distance;
+///     let text = embedding_id_to_sentence.get(&closest.value).unwrap();
+///     println!("distance: {distance}");
+///     println!("closest:  {text}");
+/// }
+/// # }
+/// ```
+#[doc(alias = "VectorDatabase")]
+#[doc(alias = "Vector Database")]
+pub struct VectorDB<S = UnknownVectorSpace> {
+    database: RawDatabase,
+    /// The same unnamed database as `database`, typed for the angular distance for [`VectorDB::raw`].
+    angular: ArroyDatabase<Angular>,
+    payloads: PayloadStore,
+    metadata: heed::Database<Str, SerdeBincode<VectorDBMetadata>>,
+    env: heed::Env,
+    _phantom: std::marker::PhantomData<S>,
+}
+
+impl<S: VectorSpace + Sync> Default for VectorDB<S> {
+    fn default() -> Self {
+        Self::new().unwrap()
+    }
+}
+
+impl<S: VectorSpace + Sync> VectorDB<S> {
+    fn read_metadata(&self, rtxn: &RoTxn) -> heed::Result<VectorDBMetadata> {
+        Ok(self.metadata.get(rtxn, METADATA_KEY)?.unwrap_or_default())
+    }
+
+    fn write_metadata(&self, wtxn: &mut RwTxn, metadata: &VectorDBMetadata) -> heed::Result<()> {
+        self.metadata.put(wtxn, METADATA_KEY, metadata)
+    }
+
+    fn contains_embedding(&self, rtxn: &RoTxn, embedding_id: EmbeddingId) -> anyhow::Result<bool> {
+        // The index is only created once the first embedding is added
+        if self.read_metadata(rtxn)?.dimensions.is_none() {
+            return Ok(false);
+        }
+        Ok(with_distance!(self.database, |database, D| {
+            let reader = Reader::<D>::open(rtxn, 0, database)?;
+            reader.item_vector(rtxn, embedding_id.0)?.is_some()
+        }))
+    }
+
+    /// Create a new temporary vector database.
+    #[tracing::instrument]
+    pub fn new() -> heed::Result<Self> {
+        VectorDBBuilder::new().build()
+    }
+
+    /// Create a new vector database at the given path. If a vector database already exists at the path, it will be reused.
+    pub fn new_at(path: impl AsRef<std::path::Path>) -> heed::Result<Self> {
+        VectorDBBuilder::new().at(path).build()
+    }
+
+    /// Create a [`VectorDBBuilder`] to configure the vector database.
+    ///
+    /// # Example
+    ///
+    /// ```rust, no_run
+    /// # use kalosm_language::prelude::*;
+    /// let db: VectorDB = VectorDB::builder()
+    ///     .with_distance(DistanceMetric::DotProduct)
+    ///     .at("./db")
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn builder() -> VectorDBBuilder<S> {
+        VectorDBBuilder::new()
+    }
+
+    fn open_env(path: &std::path::Path, distance: Option<DistanceMetric>) -> heed::Result<Self> {
+        const TWENTY_HUNDRED_MIB: usize = 2 * 1024 * 1024 * 1024;
+
+        std::fs::create_dir_all(path)?;
+
+        let env = unsafe {
+            EnvOpenOptions::new()
+                .map_size(TWENTY_HUNDRED_MIB)
+                .max_dbs(MAX_NAMED_DATABASES)
+                .open(path)
+        }?;
+
+        let mut wtxn = env.write_txn()?;
+        let payloads = PayloadStore::new(&env, &mut wtxn)?;
+        let metadata: heed::Database<Str, SerdeBincode<VectorDBMetadata>> =
+            env.create_database(&mut wtxn, Some("vector-db-metadata"))?;
+        let angular: ArroyDatabase<Angular> = env.create_database(&mut wtxn, None)?;
+        let stored = match metadata.get(&wtxn, METADATA_KEY)? {
+            Some(stored) => stored,
+            // Databases created before the id allocation state was stored in the environment need to recover it from the index
+            None => {
+                let mut recovered = VectorDBMetadata::recover(&wtxn, angular)?;
+                // An empty database has no index yet, so any metric can be used
+                if recovered.dimensions.is_none() {
+                    recovered.distance = distance.unwrap_or_default();
+                }
+                metadata.put(&mut wtxn, METADATA_KEY, &recovered)?;
+                recovered
+            }
+        };
+        if let Some(distance) = distance {
+            if distance != stored.distance {
+                return Err(heed::Error::Io(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "The vector database at {} uses the {} distance, but it was opened with the {} distance",
+                        path.display(),
+                        stored.distance,
+                        distance
+                    ),
+                )));
+            }
+        }
+        let database = RawDatabase::create(&env, &mut wtxn, stored.distance)?;
+        wtxn.commit()?;
+
+        Ok(Self {
+            database,
+            angular,
+            payloads,
+            metadata,
+            env,
+            _phantom: std::marker::PhantomData,
+        })
+    }
+
+    /// Open an existing vector database at the given path with the distance metric it was created with.
+    ///
+    /// Unlike [`VectorDB::new_at`], this will return an error if there is no vector database at the path or if the stored
+    /// dimensions or distance type of the database do not match the index.
+    pub fn open(path: impl AsRef<std::path::Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        if !path.join("data.mdb").exists() {
+            anyhow::bail!("No vector database exists at {}", path.display());
+        }
+
+        let db = Self::open_env(path, None)?;
+        let rtxn = db.env.read_txn()?;
+        let metadata = db.read_metadata(&rtxn)?;
+        with_distance!(db.database, |database, D| {
+            // Opening the reader checks the distance arroy stored in the index
+            match Reader::<D>::open(&rtxn, 0, database) {
+                Ok(reader) => {
+                    if metadata.dimensions != Some(reader.dimensions()) {
+                        anyhow::bail!(
+                            "The vector database at {} has {} dimensional embeddings, but the stored metadata expects {:?} dimensions",
+                            path.display(),
+                            reader.dimensions(),
+                            metadata.dimensions
+                        );
+                    }
+                }
+                // No embeddings were added to the database yet
+                Err(arroy::Error::MissingMetadata { .. }) => {}
+                Err(err) => return Err(err.into()),
+            }
+        });
+        drop(rtxn);
+
+        Ok(db)
+    }
+
+    /// Get the distance metric of the vector database.
+    pub fn distance(&self) -> DistanceMetric {
+        self.database.metric()
+    }
+
+    /// Get the underlying database.
+    ///
+    /// The database is typed for the [`DistanceMetric::Angular`] distance. Arroy refuses to read a database created with another metric through it, so use [`VectorDB::raw_database`] for those.
+    pub fn raw(&self) -> (&ArroyDatabase<Angular>, &heed::Env) {
+        (&self.angular, &self.env)
+    }
+
+    /// Get the underlying database, typed by the distance metric of the vector database.
+    pub fn raw_database(&self) -> &RawDatabase {
+        &self.database
+    }
+
+    /// Get the environment the vector database is stored in. Other indexes can create named databases in the same environment.
+    pub fn env(&self) -> &heed::Env {
+        &self.env
+    }
+
+    /// Clear the vector database.
+    pub async fn clear(&self) -> anyhow::Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+        let mut metadata = self.read_metadata(&wtxn)?;
+        if let Some(dims) = metadata.dimensions {
+            with_distance!(self.database, |database, D| {
+                let writer = Writer::<D>::new(database, 0, dims);
+                writer.clear(&mut wtxn)?;
+            });
+        }
+        self.payloads.clear(&mut wtxn)?;
+
+        // Reset the ids and dimensions
+        metadata.dimensions = None;
+        metadata.max_id = EmbeddingId(0);
+        metadata.recycled_ids.clear();
+        self.write_metadata(&mut wtxn, &metadata)?;
+
+        wtxn.commit()?;
+
+        Ok(())
+    }
+
+    /// Remove an embedding from the vector database.
+    pub fn remove_embedding(&self, embedding_id: EmbeddingId) -> anyhow::Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+
+        let mut metadata = self.read_metadata(&wtxn)?;
+        let dims = metadata.dimensions.ok_or_else(|| {
+            anyhow::anyhow!("The vector database does not contain any embeddings")
+        })?;
+
+        with_distance!(self.database, |database, D| {
+            let writer = Writer::<D>::new(database, 0, dims);
+
+            // Only recycle ids that were actually in use so removing an embedding twice cannot hand out the same id twice
+            if writer.del_item(&mut wtxn, embedding_id.0)? {
+                metadata.recycle_id(embedding_id);
+                self.write_metadata(&mut wtxn, &metadata)?;
+            }
+            self.payloads.delete(&mut wtxn, embedding_id)?;
+
+            let mut rng = StdRng::from_entropy();
+
+            writer.build(&mut wtxn, &mut rng, None)?;
+        });
+
+        wtxn.commit()?;
+
+        Ok(())
+    }
+
+    /// Add a new embedding to the vector database.
+    ///
+    /// Note: Adding embeddings in a batch with [`VectorDB::add_embeddings`] will be faster.
+    pub fn add_embedding(&self, embedding: Embedding<S>) -> anyhow::Result<EmbeddingId> {
+        let embedding = embedding.vector().to_vec1()?;
+
+        let mut wtxn = self.env.write_txn()?;
+
+        let mut metadata = self.read_metadata(&wtxn)?;
+        metadata.check_dimensions(embedding.len())?;
+
+        let id = metadata.take_id();
+
+        with_distance!(self.database, |database, D| {
+            let writer = Writer::<D>::new(database, 0, embedding.len());
+
+            writer.add_item(&mut wtxn, id.0, &embedding)?;
+            self.write_metadata(&mut wtxn, &metadata)?;
+
+            let mut rng = StdRng::from_entropy();
+
+            writer.build(&mut wtxn, &mut rng, None)?;
+        });
+
+        wtxn.commit()?;
+
+        Ok(id)
+    }
+
+    /// Add a new batch of embeddings to the vector database.
+    pub fn add_embeddings(
+        &self,
+        embedding: impl IntoIterator<Item = Embedding<S>>,
+    ) -> anyhow::Result<Vec<EmbeddingId>> {
+        self.add_embeddings_inner(embedding.into_iter().map(|e| (e, None)))
+    }
+
+    /// Add a new embedding with a filterable [`Payload`] to the vector database.
+    pub fn add_embedding_with_payload(
+        &self,
+        embedding: Embedding<S>,
+        payload: Payload,
+    ) -> anyhow::Result<EmbeddingId> {
+        let ids = self.add_embeddings_inner(std::iter::once((embedding, Some(payload))))?;
+        Ok(ids[0])
+    }
+
+    /// Add a new batch of embeddings with filterable [`Payload`]s to the vector database.
+    pub fn add_embeddings_with_payloads(
+        &self,
+        embeddings: impl IntoIterator<Item = (Embedding<S>, Payload)>,
+    ) -> anyhow::Result<Vec<EmbeddingId>> {
+        self.add_embeddings_inner(
+            embeddings
+                .into_iter()
+                .map(|(embedding, payload)| (embedding, Some(payload))),
+        )
+    }
+
+    fn add_embeddings_inner(
+        &self,
+        embeddings: impl IntoIterator<Item = (Embedding<S>, Option<Payload>)>,
+    ) -> anyhow::Result<Vec<EmbeddingId>> {
+        let mut embeddings = embeddings
+            .into_iter()
+            .map(|(e, payload)| e.vector().to_vec1().map(|e| (e, payload)));
+        let (first_embedding, first_payload) = match embeddings.next() {
+            Some(e) => e?,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut wtxn = self.env.write_txn()?;
+        let mut metadata = self.read_metadata(&wtxn)?;
+        metadata.check_dimensions(first_embedding.len())?;
+        let dims = first_embedding.len();
+
+        let mut ids: Vec<_> = Vec::with_capacity(embeddings.size_hint().0 + 1);
+
+        with_distance!(self.database, |database, D| {
+            let writer = Writer::<D>::new(database, 0, dims);
+
+            {
+                let first_id = metadata.take_id();
+                writer.add_item(&mut wtxn, first_id.0, &first_embedding)?;
+                if let Some(payload) = first_payload {
+                    self.payloads.put(&mut wtxn, first_id, &payload)?;
+                }
+                ids.push(first_id);
+            }
+
+            for embedding in embeddings {
+                let (embedding, payload) = embedding?;
+                metadata.check_dimensions(embedding.len())?;
+                let id = metadata.take_id();
+                writer.add_item(&mut wtxn, id.0, &embedding)?;
+                if let Some(payload) = payload {
+                    self.payloads.put(&mut wtxn, id, &payload)?;
+                }
+                ids.push(id);
+            }
+
+            // The id allocation state is written in the same transaction as the embeddings so they can never get out of sync
+            self.write_metadata(&mut wtxn, &metadata)?;
+
+            let mut rng = StdRng::from_entropy();
+
+            writer.build(&mut wtxn, &mut rng, None)?;
+        });
+
+        wtxn.commit()?;
+
+        Ok(ids)
+    }
+
+    /// Set the filterable [`Payload`] of an embedding, replacing any existing payload. Returns an error if the embedding is not in the database.
+    pub fn set_payload(&self, embedding_id: EmbeddingId, payload: Payload) -> anyhow::Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+        // A payload for an id without an embedding would be inherited by the next embedding that is given the id
+        if !self.contains_embedding(&wtxn, embedding_id)? {
+            anyhow::bail!("Embedding {embedding_id:?} not found");
+        }
+        self.payloads.put(&mut wtxn, embedding_id, &payload)?;
+        wtxn.commit()?;
+
+        Ok(())
+    }
+
+    /// Get the [`Payload`] of an embedding if it has one.
+    pub fn get_payload(&self, embedding_id: EmbeddingId) -> anyhow::Result<Option<Payload>> {
+        let rtxn = self.env.read_txn()?;
+        self.payloads.get(&rtxn, embedding_id)
+    }
+
+    /// Get the embedding for an embedding id.
+    pub fn get_embedding(&self, embedding_id: EmbeddingId) -> anyhow::Result<Embedding<S>> {
+        let rtxn = self.env.read_txn()?;
+        let embedding = with_distance!(self.database, |database, D| {
+            let reader = Reader::<D>::open(&rtxn, 0, database)?;
+            reader.item_vector(&rtxn, embedding_id.0)?
+        })
+        .ok_or_else(|| anyhow::anyhow!("Embedding not found"))?;
+
+        let shape = (embedding.len(),);
+        Ok(Embedding::new(Tensor::from_vec(
+            embedding,
+            shape,
+            &accelerated_device_if_available()?,
+        )?))
+    }
+
+    /// Get the closest N embeddings to the given embedding.
+    pub fn get_closest(
+        &self,
+        embedding: Embedding<S>,
+        n: usize,
+    ) -> anyhow::Result<Vec<VectorDBSearchResult>> {
+        let rtxn = self.env.read_txn()?;
+
+        let vector = embedding.vector().to_vec1()?;
+        let arroy_results = with_distance!(self.database, |database, D| {
+            let reader = Reader::<D>::open(&rtxn, 0, database)?;
+            reader.nns_by_vector(&rtxn, &vector, n, None, None)?
+        });
+
+        Ok(arroy_results
+            .into_iter()
+            .map(|(id, distance)| {
+                let value = EmbeddingId(id);
+                VectorDBSearchResult { distance, value }
+            })
+            .collect::<Vec<_>>())
+    }
+
+    /// Get the closest N embeddings to the given embedding that have a [`Payload`] matching the filter.
+    ///
+    /// The filter is resolved with an index over the payloads before the search, so only matching embeddings are considered.
+    ///
+    /// # Example
+    ///
+    /// ```rust, no_run
+    /// # use kalosm_language::prelude::*;
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let bert = Bert::new_for_search().await.unwrap();
+    /// let db = VectorDB::new().unwrap();
+    /// db.add_embedding_with_payload(
+    ///     bert.embed("Kalosm invoice #1").await.unwrap(),
+    ///     Payload::new().with("tenant", "acme").with("year", 2024),
+    /// )
+    /// .unwrap();
+    /// db.add_embedding_with_payload(
+    ///     bert.embed("Kalosm invoice #2").await.unwrap(),
+    ///     Payload::new().with("tenant", "globex").with("year", 2023),
+    /// )
+    /// .unwrap();
+    ///
+    /// let filter = PayloadFilter::eq("tenant", "acme").and(PayloadFilter::range("year", 2024..));
+    /// let query = bert.embed_query("invoice").await.unwrap();
+    /// let closest = db.get_closest_filtered(query, 5, &filter).unwrap();
+    /// assert_eq!(closest.len(), 1);
+    /// # }
+    /// ```
+    pub fn get_closest_filtered(
+        &self,
+        embedding: Embedding<S>,
+        n: usize,
+        filter: &PayloadFilter,
+    ) -> anyhow::Result<Vec<VectorDBSearchResult>> {
+        let rtxn = self.env.read_txn()?;
+        let candidates = self.payloads.candidates(&rtxn, filter)?;
+        if candidates.is_empty() {
+            return Ok(Vec::new());
+        }
+        let vector = embedding.vector().to_vec1()?;
+        let arroy_results = with_distance!(self.database, |database, D| {
+            let reader = Reader::<D>::open(&rtxn, 0, database)?;
+            reader.nns_by_vector(&rtxn, &vector, n, None, Some(&candidates))?
+        });
+
+        Ok(arroy_results
+            .into_iter()
+            .map(|(id, distance)| {
+                let value = EmbeddingId(id);
+                VectorDBSearchResult { distance, value }
+            })
+            .collect::<Vec<_>>())
+    }
+
+    /// Get the closest N embeddings to the given embedding out of a set of candidate embeddings.
+    ///
+    /// This is useful when the embeddings that should be searched are found with an external index, like a query against the records the embeddings belong to.
+    pub fn get_closest_among(
+        &self,
+        embedding: Embedding<S>,
+        n: usize,
+        candidates: impl IntoIterator<Item = EmbeddingId>,
+    ) -> anyhow::Result<Vec<VectorDBSearchResult>> {
+        let candidates: RoaringBitmap = candidates.into_iter().map(|id| id.0).collect();
+        if candidates.is_empty() {
+            return Ok(Vec::new());
+        }
+        let rtxn = self.env.read_txn()?;
+        let vector = embedding.vector().to_vec1()?;
+        let arroy_results = with_distance!(self.database, |database, D| {
+            let reader = Reader::<D>::open(&rtxn, 0, database)?;
+            reader.nns_by_vector(&rtxn, &vector, n, None, Some(&candidates))?
+        });
+
+        Ok(arroy_results
+            .into_iter()
+            .map(|(id, distance)| {
+                let value = EmbeddingId(id);
+                VectorDBSearchResult { distance, value }
+            })
+            .collect::<Vec<_>>())
+    }
+
+    /// Get K embeddings close to the given embedding that are also different from each other with maximal marginal relevance (MMR).
+    ///
+    /// The `fetch_k` closest embeddings are fetched from the index and then selected one at a time, balancing the similarity to the query and
+    /// the similarity to the embeddings that were already selected. A `lambda` of 1.0 only considers the similarity to the query while a `lambda`
+    /// of 0.0 only considers the diversity of the results. Similarity is measured with the [`DistanceMetric`] of the database.
+    ///
+    /// This is useful to avoid filling a prompt with near duplicate chunks.
+    ///
+    /// # Example
+    ///
+    /// ```rust, no_run
+    /// # use kalosm_language::prelude::*;
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let bert = Bert::new_for_search().await.unwrap();
+    /// let db = VectorDB::new().unwrap();
+    /// db.add_embeddings(
+    ///     bert.embed_batch([
+    ///         "Kalosm is a library for local AI",
+    ///         "Kalosm is a library for local AI applications",
+    ///         "Kalosm supports structured generation",
+    ///     ])
+    ///     .await
+    ///     .unwrap(),
+    /// )
+    /// .unwrap();
+    ///
+    /// let query = bert.embed_query("What is Kalosm?").await.unwrap();
+    /// let diverse = db.get_closest_mmr(query, 2, 0.5, 10).unwrap();
+    /// # }
+    /// ```
+    pub fn get_closest_mmr(
+        &self,
+        embedding: Embedding<S>,
+        k: usize,
+        lambda: f32,
+        fetch_k: usize,
+    ) -> anyhow::Result<Vec<VectorDBSearchResult>> {
+        let rtxn = self.env.read_txn()?;
+
+        let query = embedding.vector().to_vec1::<f32>()?;
+        let metric = self.distance();
+        let mut candidates = with_distance!(self.database, |database, D| {
+            let reader = Reader::<D>::open(&rtxn, 0, database)?;
+            let mut candidates = Vec::new();
+            let closest = reader.nns_by_vector(&rtxn, &query, fetch_k.max(k), None, None)?;
+            for (id, distance) in closest {
+                let vector = reader
+                    .item_vector(&rtxn, id)?
+                    .ok_or_else(|| anyhow::anyhow!("Embedding not found"))?;
+                let relevance = metric.similarity(&query, &vector);
+                candidates.push(MmrCandidate {
+                    result: VectorDBSearchResult {
+                        distance,
+                        value: EmbeddingId(id),
+                    },
+                    vector,
+                    relevance,
+                });
+            }
+            candidates
+        });
+
+        let mut selected: Vec<MmrCandidate> = Vec::with_capacity(k);
+        while selected.len() < k && !candidates.is_empty() {
+            let score = |candidate: &MmrCandidate| {
+                let redundancy = selected
+                    .iter()
+                    .map(|other| metric.similarity(&candidate.vector, &other.vector))
+                    .reduce(f32::max)
+                    .unwrap_or(0.0);
+                lambda * candidate.relevance - (1.0 - lambda) * redundancy
+            };
+            let best = candidates
+                .iter()
+                .enumerate()
+                .max_by(|(_, a), (_, b)| score(a).total_cmp(&score(b)))
+                .map(|(index, _)| index)
+                .unwrap();
+            selected.push(candidates.swap_remove(best));
+        }
+
+        Ok(selected
+            .into_iter()
+            .map(|candidate| candidate.result)
+            .collect())
+    }
+
+    /// Get all embeddings within the given distance of the given embedding, sorted from closest to furthest.
+    ///
+    /// Like the rest of the index, this search is approximate and may miss some embeddings that are within the distance.
+    pub fn get_within_distance(
+        &self,
+        embedding: Embedding<S>,
+        max_distance: f32,
+    ) -> anyhow::Result<Vec<VectorDBSearchResult>> {
+        const INITIAL_SEARCH_SIZE: usize = 16;
+
+        let rtxn = self.env.read_txn()?;
+
+        let vector = embedding.vector().to_vec1()?;
+        let arroy_results = with_distance!(self.database, |database, D| {
+            let reader = Reader::<D>::open(&rtxn, 0, database)?;
+            let total = reader.item_ids().len() as usize;
+            // Keep doubling the number of results until the furthest result is outside of the distance
+            let mut n = INITIAL_SEARCH_SIZE.min(total);
+            loop {
+                let results = reader.nns_by_vector(&rtxn, &vector, n, None, None)?;
+                let exhausted = n >= total || results.len() < n;
+                let outside = results
+                    .last()
+                    .map_or(true, |(_, distance)| *distance > max_distance);
+                if exhausted || outside {
+                    break results;
+                }
+                n = (n * 2).min(total);
+            }
+        });
+
+        Ok(arroy_results
+            .into_iter()
+            .take_while(|(_, distance)| *distance <= max_distance)
+            .map(|(id, distance)| {
+                let value = EmbeddingId(id);
+                VectorDBSearchResult { distance, value }
+            })
+            .collect::<Vec<_>>())
+    }
+}
+
+struct MmrCandidate {
+    result: VectorDBSearchResult,
+    vector: Vec<f32>,
+    relevance: f32,
+}
+
+/// A builder for a [`VectorDB`].
+#[derive(Debug)]
+pub struct VectorDBBuilder<S = UnknownVectorSpace> {
+    location: Option<std::path::PathBuf>,
+    distance: Option<DistanceMetric>,
+    _phantom: std::marker::PhantomData<S>,
+}
+
+impl<S: VectorSpace + Sync> Default for VectorDBBuilder<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: VectorSpace + Sync> VectorDBBuilder<S> {
+    /// Create a new builder for a temporary vector database with the default distance metric.
+    pub fn new() -> Self {
+        Self {
+            location: None,
+            distance: None,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Set the location of the vector database. If a vector database already exists at the path, it will be reused.
+    pub fn at(mut self, path: impl AsRef<std::path::Path>) -> Self {
+        self.location = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set the distance metric of the vector database. Defaults to [`DistanceMetric::Angular`] for new databases and the stored metric for existing databases.
+    ///
+    /// If a vector database with a different metric already exists at the location, [`VectorDBBuilder::build`] will return an error.
+    pub fn with_distance(mut self, distance: DistanceMetric) -> Self {
+        self.distance = Some(distance);
+        self
+    }
+
+    /// Build the vector database.
+    #[tracing::instrument(skip(self))]
+    pub fn build(self) -> heed::Result<VectorDB<S>> {
+        match self.location {
+            Some(location) => VectorDB::open_env(&location, self.distance),
+            None => {
+                let dir = tempfile::tempdir()?;
+
+                VectorDB::open_env(dir.path(), self.distance)
+            }
+        }
+    }
+}
+
+/// A resulting point from a search.
+#[derive(Debug, Clone)]
+pub struct VectorDBSearchResult {
+    /// The distance from the searched point.
+    pub distance: f32,
+    /// The value of the point.
+    pub value: EmbeddingId,
+}
+
+/// A unique identifier for an embedding. If you delete an embedding, the id will be recycled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct EmbeddingId(pub u32);
+
+#[test]
+fn filtered_search_only_returns_matching_embeddings() {
+    let db: VectorDB<UnknownVectorSpace> = VectorDB::new().unwrap();
+    let ids = db
+        .add_embeddings_with_payloads([
+            (
+                Embedding::from([1.0, 0.0, 0.0]),
+                Payload::new().with("tenant", "acme").with("year", 2023),
+            ),
+            (
+                Embedding::from([0.9, 0.1, 0.0]),
+                Payload::new().with("tenant", "globex").with("year", 2024),
+            ),
+            (
+                Embedding::from([0.0, 1.0, 0.0]),
+                Payload::new().with("tenant", "acme").with("year", 2024),
+            ),
+        ])
+        .unwrap();
+
+    let query = Embedding::from([1.0, 0.0, 0.0]);
+    let closest = db
+        .get_closest_filtered(query.clone(), 1, &PayloadFilter::eq("tenant", "globex"))
+        .unwrap();
+    assert_eq!(closest.len(), 1);
+    assert_eq!(closest[0].value, ids[1]);
+
+    let filter = PayloadFilter::eq("tenant", "acme").and(PayloadFilter::range("year", 2024..));
+    let closest = db.get_closest_filtered(query.clone(), 3, &filter).unwrap();
+    assert_eq!(closest.len(), 1);
+    assert_eq!(closest[0].value, ids[2]);
+
+    let filter = PayloadFilter::one_of("year", [2023, 2024]);
+    let closest = db.get_closest_filtered(query.clone(), 3, &filter).unwrap();
+    assert_eq!(closest.len(), 3);
+
+    // Removing an embedding removes its payload from the index
+    db.remove_embedding(ids[1]).unwrap();
+    assert!(db.get_payload(ids[1]).unwrap().is_none());
+    let closest = db
+        .get_closest_filtered(query.clone(), 3, &PayloadFilter::eq("tenant", "globex"))
+        .unwrap();
+    assert!(closest.is_empty());
+
+    // Payloads can only be set for embeddings in the database, so a recycled id never inherits a stray payload
+    assert!(db
+        .set_payload(ids[1], Payload::new().with("tenant", "globex"))
+        .is_err());
+    let recycled = db.add_embedding(Embedding::from([0.9, 0.1, 0.0])).unwrap();
+    assert!(db.get_payload(recycled).unwrap().is_none());
+    db.set_payload(ids[0], Payload::new().with("tenant", "globex"))
+        .unwrap();
+    let closest = db
+        .get_closest_filtered(query, 3, &PayloadFilter::eq("tenant", "globex"))
+        .unwrap();
+    assert_eq!(closest.len(), 1);
+    assert_eq!(closest[0].value, ids[0]);
+}
+
+#[test]
+fn new_databases_can_be_created_and_reopened() {
+    VectorDB::<UnknownVectorSpace>::new().unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    {
+        let db: VectorDB<UnknownVectorSpace> = VectorDB::builder()
+            .with_distance(DistanceMetric::Euclidean)
+            .at(dir.path())
+            .build()
+            .unwrap();
+        assert_eq!(db.distance(), DistanceMetric::Euclidean);
+    }
+
+    // A database without any embeddings can be opened again
+    let db: VectorDB<UnknownVectorSpace> = VectorDB::open(dir.path()).unwrap();
+    assert_eq!(db.distance(), DistanceMetric::Euclidean);
+    let id = db.add_embedding(Embedding::from([1.0, 0.0])).unwrap();
+    assert_eq!(id, EmbeddingId(0));
+}
+
+#[test]
+fn legacy_databases_recover_their_ids() {
+    let dir = tempfile::tempdir().unwrap();
+
+    // Databases created before the metadata was stored only contain the arroy index in the unnamed database
+    {
+        let env = unsafe {
+            EnvOpenOptions::new()
+                .map_size(10 * 1024 * 1024)
+                .open(dir.path())
+        }
+        .unwrap();
+        let mut wtxn = env.write_txn().unwrap();
+        let database: ArroyDatabase<Angular> = env.create_database(&mut wtxn, None).unwrap();
+        let writer = Writer::<Angular>::new(database, 0, 2);
+        writer.add_item(&mut wtxn, 0, &[1.0, 0.0]).unwrap();
+        writer.add_item(&mut wtxn, 2, &[0.0, 1.0]).unwrap();
+        writer
+            .build(&mut wtxn, &mut StdRng::seed_from_u64(0), None)
+            .unwrap();
+        wtxn.commit().unwrap();
+    }
+
+    let db: VectorDB<UnknownVectorSpace> = VectorDB::new_at(dir.path()).unwrap();
+    assert_eq!(db.distance(), DistanceMetric::Angular);
+    assert_eq!(
+        db.get_embedding(EmbeddingId(2)).unwrap().to_vec(),
+        vec![0.0, 1.0]
+    );
+    // The gap in the ids is reused before new ids are allocated
+    let ids = db
+        .add_embeddings([Embedding::from([1.0, 1.0]), Embedding::from([1.0, -1.0])])
+        .unwrap();
+    assert_eq!(ids, [EmbeddingId(1), EmbeddingId(3)]);
+}
+
+#[test]
+fn reopening_keeps_id_allocation_state() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let (kept, removed) = {
+        let db: VectorDB<UnknownVectorSpace> = VectorDB::new_at(dir.path()).unwrap();
+        let ids = db
+            .add_embeddings([
+                Embedding::from([1.0, 0.0, 0.0]),
+                Embedding::from([0.0, 1.0, 0.0]),
+                Embedding::from([0.0, 0.0, 1.0]),
+            ])
+            .unwrap();
+        db.remove_embedding(ids[1]).unwrap();
+        // Removing the same embedding twice must not recycle the id twice
+        db.remove_embedding(ids[1]).unwrap();
+        ((ids[0], ids[2]), ids[1])
+    };
+
+    let db: VectorDB<UnknownVectorSpace> = VectorDB::open(dir.path()).unwrap();
+    assert_eq!(
+        db.get_embedding(kept.0).unwrap().to_vec(),
+        vec![1.0, 0.0, 0.0]
+    );
+    assert_eq!(
+        db.get_embedding(kept.1).unwrap().to_vec(),
+        vec![0.0, 0.0, 1.0]
+    );
+
+    let new_ids = db
+        .add_embeddings([
+            Embedding::from([1.0, 1.0, 0.0]),
+            Embedding::from([0.0, 1.0, 1.0]),
+        ])
+        .unwrap();
+    assert_eq!(new_ids[0], removed);
+    assert!(!new_ids.contains(&kept.0) && !new_ids.contains(&kept.1));
+    assert_ne!(new_ids[0], new_ids[1]);
+}
+
+#[test]
+fn reopening_after_clear_restarts_ids() {
+    let dir = tempfile::tempdir().unwrap();
+
+    {
+        let db: VectorDB<UnknownVectorSpace> = VectorDB::new_at(dir.path()).unwrap();
+        db.add_embeddings([Embedding::from([1.0, 0.0]), Embedding::from([0.0, 1.0])])
+            .unwrap();
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(db.clear())
+            .unwrap();
+    }
+
+    let db: VectorDB<UnknownVectorSpace> = VectorDB::open(dir.path()).unwrap();
+    let id = db.add_embedding(Embedding::from([1.0, 0.0])).unwrap();
+    assert_eq!(id, EmbeddingId(0));
+}
+
+#[test]
+fn open_validates_the_database() {
+    let dir = tempfile::tempdir().unwrap();
+    assert!(VectorDB::<UnknownVectorSpace>::open(dir.path()).is_err());
+
+    {
+        let db: VectorDB<UnknownVectorSpace> = VectorDB::new_at(dir.path()).unwrap();
+        db.add_embedding(Embedding::from([1.0, 0.0, 0.0])).unwrap();
+    }
+
+    let db: VectorDB<UnknownVectorSpace> = VectorDB::open(dir.path()).unwrap();
+    // Embeddings with a different number of dimensions are rejected instead of corrupting the index
+    assert!(db.add_embedding(Embedding::from([1.0, 0.0])).is_err());
+}
+
+#[test]
+fn distance_metric_is_stored_with_the_database() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let (far_same_direction, near) = {
+        let db: VectorDB<UnknownVectorSpace> = VectorDB::builder()
+            .with_distance(DistanceMetric::Euclidean)
+            .at(dir.path())
+            .build()
+            .unwrap();
+        let ids = db
+            .add_embeddings([Embedding::from([10.0, 0.0]), Embedding::from([0.9, 0.1])])
+            .unwrap();
+        (ids[0], ids[1])
+    };
+
+    // Reopening with a different metric is an error
+    assert!(VectorDB::<UnknownVectorSpace>::builder()
+        .with_distance(DistanceMetric::Angular)
+        .at(dir.path())
+        .build()
+        .is_err());
+
+    // Opening without a metric uses the stored metric
+    let db: VectorDB<UnknownVectorSpace> = VectorDB::open(dir.path()).unwrap();
+    assert_eq!(db.distance(), DistanceMetric::Euclidean);
+    let closest = db.get_closest(Embedding::from([1.0, 0.0]), 1).unwrap();
+    assert_eq!(closest[0].value, near);
+    assert_ne!(closest[0].value, far_same_direction);
+}
+
+#[test]
+fn mmr_skips_near_duplicates() {
+    let db: VectorDB<UnknownVectorSpace> = VectorDB::new().unwrap();
+    let ids = db
+        .add_embeddings([
+            Embedding::from([1.0, 0.05, 0.0]),
+            Embedding::from([1.0, 0.0, 0.0]),
+            Embedding::from([0.6, 0.8, 0.0]),
+            Embedding::from([0.0, 0.0, 1.0]),
+        ])
+        .unwrap();
+
+    let query = Embedding::from([1.0, 0.3, 0.0]);
+    let closest = db.get_closest(query.clone(), 2).unwrap();
+    assert_eq!(
+        closest.iter().map(|r| r.value).collect::<Vec<_>>(),
+        vec![ids[0], ids[1]]
+    );
+
+    let diverse = db.get_closest_mmr(query.clone(), 2, 0.5, 4).unwrap();
+    assert_eq!(
+        diverse.iter().map(|r| r.value).collect::<Vec<_>>(),
+        vec![ids[0], ids[2]]
+    );
+
+    // A lambda of 1.0 only considers relevance
+    let relevant = db.get_closest_mmr(query, 2, 1.0, 4).unwrap();
+    assert_eq!(
+        relevant.iter().map(|r| r.value).collect::<Vec<_>>(),
+        vec![ids[0], ids[1]]
+    );
+}
+
+#[test]
+fn mmr_uses_the_distance_metric_of_the_database() {
+    let db: VectorDB<UnknownVectorSpace> = VectorDB::builder()
+        .with_distance(DistanceMetric::Euclidean)
+        .build()
+        .unwrap();
+    let ids = db
+        .add_embeddings([Embedding::from([0.9, 0.3]), Embedding::from([5.0, 0.0])])
+        .unwrap();
+
+    // The second embedding points in the same direction as the query, but it is further away
+    let query = Embedding::from([1.0, 0.0]);
+    let closest = db.get_closest(query.clone(), 1).unwrap();
+    assert_eq!(closest[0].value, ids[0]);
+    let relevant = db.get_closest_mmr(query, 1, 1.0, 2).unwrap();
+    assert_eq!(relevant[0].value, ids[0]);
+}
+
+#[test]
+fn within_distance_returns_every_close_embedding() {
+    let db: VectorDB<UnknownVectorSpace> = VectorDB::builder()
+        .with_distance(DistanceMetric::Euclidean)
+        .build()
+        .unwrap();
+    db.add_embeddings((0..100).map(|i| Embedding::from([i as f32, 0.0])))
+        .unwrap();
+
+    let within = db
+        .get_within_distance(Embedding::from([0.0, 0.0]), 39.5)
+        .unwrap();
+    assert_eq!(within.len(), 40);
+    assert!(within.iter().all(|r| r.distance <= 39.5));
+    assert!(within.windows(2).all(|w| w[0].distance <= w[1].distance));
+}
+
+#[test]
+fn closest_among_only_searches_the_candidates() {
+    let db: VectorDB<UnknownVectorSpace> = VectorDB::new().unwrap();
+    let ids = db
+        .add_embeddings([
+            Embedding::from([1.0, 0.0, 0.0]),
+            Embedding::from([0.9, 0.1, 0.0]),
+            Embedding::from([0.0, 1.0, 0.0]),
+        ])
+        .unwrap();
+
+    let query = Embedding::from([1.0, 0.0, 0.0]);
+    let closest = db
+        .get_closest_among(query.clone(), 3, [ids[1], ids[2]])
+        .unwrap();
+    assert_eq!(
+        closest
+            .iter()
+            .map(|result| result.value)
+            .collect::<Vec<_>>(),
+        [ids[1], ids[2]]
+    );
+
+    let closest = db.get_closest_among(query, 3, []).unwrap();
+    assert!(closest.is_empty());
+}
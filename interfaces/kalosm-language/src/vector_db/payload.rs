@@ -0,0 +1,573 @@
+use std::collections::BTreeMap;
+use std::ops::{Bound, RangeBounds};
+
+use chrono::{DateTime, Utc};
+use heed::types::{Bytes, SerdeBincode, U32};
+use heed::{byteorder::BigEndian, Database, RoTxn, RwTxn};
+use roaring::RoaringBitmap;
+use serde::{Deserialize, Serialize};
+
+use super::EmbeddingId;
+
+/// A value in a [`Payload`] that can be filtered with a [`PayloadFilter`].
+///
+/// Values are only compared with values of the same type. For example, a range filter over integers will never match a float value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PayloadValue {
+    /// A boolean value.
+    Bool(bool),
+    /// An integer value.
+    Integer(i64),
+    /// A floating point value.
+    Float(f64),
+    /// A string value.
+    String(String),
+    /// A date and time.
+    DateTime(DateTime<Utc>),
+}
+
+impl PayloadValue {
+    fn tag(&self) -> u8 {
+        match self {
+            Self::Bool(_) => 0,
+            Self::Integer(_) => 1,
+            Self::Float(_) => 2,
+            Self::String(_) => 3,
+            Self::DateTime(_) => 4,
+        }
+    }
+
+    /// Encode the value into bytes that sort in the same order as the values.
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![self.tag()];
+        match self {
+            Self::Bool(value) => bytes.push(*value as u8),
+            Self::Integer(value) => bytes.extend_from_slice(&encode_i64(*value)),
+            Self::Float(value) => {
+                let bits = value.to_bits();
+                // Flip the sign bit of positive numbers and every bit of negative numbers so the bytes sort in numeric order
+                let bits = if bits >> 63 == 0 {
+                    bits ^ (1 << 63)
+                } else {
+                    !bits
+                };
+                bytes.extend_from_slice(&bits.to_be_bytes());
+            }
+            Self::String(value) => bytes.extend_from_slice(value.as_bytes()),
+            Self::DateTime(value) => bytes.extend_from_slice(&encode_i64(value.timestamp_micros())),
+        }
+        bytes
+    }
+}
+
+fn encode_i64(value: i64) -> [u8; 8] {
+    ((value as u64) ^ (1 << 63)).to_be_bytes()
+}
+
+impl From<bool> for PayloadValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i64> for PayloadValue {
+    fn from(value: i64) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl From<i32> for PayloadValue {
+    fn from(value: i32) -> Self {
+        Self::Integer(value as i64)
+    }
+}
+
+impl From<u32> for PayloadValue {
+    fn from(value: u32) -> Self {
+        Self::Integer(value as i64)
+    }
+}
+
+impl From<f64> for PayloadValue {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<f32> for PayloadValue {
+    fn from(value: f32) -> Self {
+        Self::Float(value as f64)
+    }
+}
+
+impl From<String> for PayloadValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<&str> for PayloadValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<DateTime<Utc>> for PayloadValue {
+    fn from(value: DateTime<Utc>) -> Self {
+        Self::DateTime(value)
+    }
+}
+
+/// A set of filterable fields attached to an embedding in a [`VectorDB`](super::VectorDB).
+///
+/// # Example
+///
+/// ```rust
+/// # use kalosm_language::prelude::*;
+/// let payload = Payload::new()
+///     .with("tenant", "acme")
+///     .with("document_type", "invoice")
+///     .with("pages", 3);
+/// assert_eq!(payload.get("pages"), Some(&PayloadValue::Integer(3)));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Payload {
+    fields: BTreeMap<String, PayloadValue>,
+}
+
+impl Payload {
+    /// Create a new empty payload.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a field to the payload.
+    pub fn with(mut self, field: impl Into<String>, value: impl Into<PayloadValue>) -> Self {
+        self.insert(field, value);
+        self
+    }
+
+    /// Insert a field into the payload. Returns the old value of the field if it existed.
+    pub fn insert(
+        &mut self,
+        field: impl Into<String>,
+        value: impl Into<PayloadValue>,
+    ) -> Option<PayloadValue> {
+        self.fields.insert(field.into(), value.into())
+    }
+
+    /// Get the value of a field.
+    pub fn get(&self, field: &str) -> Option<&PayloadValue> {
+        self.fields.get(field)
+    }
+
+    /// Remove a field from the payload.
+    pub fn remove(&mut self, field: &str) -> Option<PayloadValue> {
+        self.fields.remove(field)
+    }
+
+    /// Iterate over the fields in the payload.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &PayloadValue)> {
+        self.fields.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Get the number of fields in the payload.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Check if the payload has no fields.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+impl<K: Into<String>, V: Into<PayloadValue>> FromIterator<(K, V)> for Payload {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        Self {
+            fields: iter
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        }
+    }
+}
+
+/// A filter over the [`Payload`]s of embeddings in a [`VectorDB`](super::VectorDB).
+///
+/// # Example
+///
+/// ```rust
+/// # use kalosm_language::prelude::*;
+/// # use chrono::{TimeZone, Utc};
+/// let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+/// let filter = PayloadFilter::eq("tenant", "acme")
+///     .and(PayloadFilter::range("created_at", start..))
+///     .and(PayloadFilter::one_of("document_type", ["invoice", "receipt"]));
+///
+/// let payload = Payload::new()
+///     .with("tenant", "acme")
+///     .with("created_at", Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap())
+///     .with("document_type", "receipt");
+/// assert!(filter.matches(&payload));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum PayloadFilter {
+    /// Match payloads where the field is equal to the value.
+    Equals {
+        /// The field to compare.
+        field: String,
+        /// The value the field must be equal to.
+        value: PayloadValue,
+    },
+    /// Match payloads where the field is within the range.
+    Range {
+        /// The field to compare.
+        field: String,
+        /// The start of the range.
+        start: Bound<PayloadValue>,
+        /// The end of the range.
+        end: Bound<PayloadValue>,
+    },
+    /// Match payloads where the field is equal to any of the values.
+    OneOf {
+        /// The field to compare.
+        field: String,
+        /// The values the field may be equal to.
+        values: Vec<PayloadValue>,
+    },
+    /// Match payloads that match all of the filters.
+    And(Vec<PayloadFilter>),
+    /// Match payloads that match any of the filters.
+    Or(Vec<PayloadFilter>),
+}
+
+impl PayloadFilter {
+    /// Create a filter that matches payloads where the field is equal to the value.
+    pub fn eq(field: impl Into<String>, value: impl Into<PayloadValue>) -> Self {
+        Self::Equals {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Create a filter that matches payloads where the field is within the range.
+    pub fn range<V: Into<PayloadValue> + Clone>(
+        field: impl Into<String>,
+        range: impl RangeBounds<V>,
+    ) -> Self {
+        Self::Range {
+            field: field.into(),
+            start: range.start_bound().cloned().map(Into::into),
+            end: range.end_bound().cloned().map(Into::into),
+        }
+    }
+
+    /// Create a filter that matches payloads where the field is equal to any of the values.
+    pub fn one_of<V: Into<PayloadValue>>(
+        field: impl Into<String>,
+        values: impl IntoIterator<Item = V>,
+    ) -> Self {
+        Self::OneOf {
+            field: field.into(),
+            values: values.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Combine this filter with another filter that must also match.
+    pub fn and(self, other: Self) -> Self {
+        match self {
+            Self::And(mut filters) => {
+                filters.push(other);
+                Self::And(filters)
+            }
+            this => Self::And(vec![this, other]),
+        }
+    }
+
+    /// Combine this filter with another filter that may match instead.
+    pub fn or(self, other: Self) -> Self {
+        match self {
+            Self::Or(mut filters) => {
+                filters.push(other);
+                Self::Or(filters)
+            }
+            this => Self::Or(vec![this, other]),
+        }
+    }
+
+    /// Check if a payload matches the filter.
+    pub fn matches(&self, payload: &Payload) -> bool {
+        match self {
+            Self::Equals { field, value } => payload.get(field) == Some(value),
+            Self::Range { field, start, end } => {
+                let Some(value) = payload.get(field) else {
+                    return false;
+                };
+                let Ok((start, end)) = range_keys(&[], start, end) else {
+                    return false;
+                };
+                (start.as_ref(), end.as_ref()).contains(&value.encode())
+            }
+            Self::OneOf { field, values } => payload
+                .get(field)
+                .map(|value| values.contains(value))
+                .unwrap_or(false),
+            Self::And(filters) => filters.iter().all(|filter| filter.matches(payload)),
+            Self::Or(filters) => filters.iter().any(|filter| filter.matches(payload)),
+        }
+    }
+}
+
+/// Get the bounds of the encoded keys for a range of values. The keys are prefixed with `prefix`.
+fn range_keys(
+    prefix: &[u8],
+    start: &Bound<PayloadValue>,
+    end: &Bound<PayloadValue>,
+) -> anyhow::Result<(Bound<Vec<u8>>, Bound<Vec<u8>>)> {
+    let tag = match (start, end) {
+        (
+            Bound::Included(start) | Bound::Excluded(start),
+            Bound::Included(end) | Bound::Excluded(end),
+        ) => {
+            if start.tag() != end.tag() {
+                anyhow::bail!("The start and end of a range filter must have the same type");
+            }
+            Some(start.tag())
+        }
+        (Bound::Included(value) | Bound::Excluded(value), Bound::Unbounded)
+        | (Bound::Unbounded, Bound::Included(value) | Bound::Excluded(value)) => Some(value.tag()),
+        (Bound::Unbounded, Bound::Unbounded) => None,
+    };
+    let key = |value: &PayloadValue| [prefix, &value.encode()].concat();
+
+    let start = match start {
+        Bound::Included(value) => Bound::Included(key(value)),
+        Bound::Excluded(value) => Bound::Excluded(key(value)),
+        Bound::Unbounded => Bound::Included([prefix, tag.as_slice()].concat()),
+    };
+    let end = match end {
+        Bound::Included(value) => Bound::Included(key(value)),
+        Bound::Excluded(value) => Bound::Excluded(key(value)),
+        Bound::Unbounded => match tag {
+            Some(tag) => Bound::Excluded([prefix, &[tag + 1]].concat()),
+            // Without a type, the range includes every value of the field
+            None => Bound::Excluded(next_prefix(prefix)),
+        },
+    };
+
+    Ok((start, end))
+}
+
+/// Get the smallest key that is larger than every key starting with the prefix.
+fn next_prefix(prefix: &[u8]) -> Vec<u8> {
+    let mut next = prefix.to_vec();
+    while let Some(last) = next.pop() {
+        if last < u8::MAX {
+            next.push(last + 1);
+            return next;
+        }
+    }
+    // Every key starts with an empty prefix
+    vec![u8::MAX; 9]
+}
+
+/// The prefix of every key in the payload index for a field.
+fn field_prefix(field: &str) -> Vec<u8> {
+    let mut prefix = field.as_bytes().to_vec();
+    prefix.push(0);
+    prefix
+}
+
+/// Storage for the payloads of a vector database along with an inverted index from field values to embedding ids.
+pub(crate) struct PayloadStore {
+    payloads: Database<U32<BigEndian>, SerdeBincode<Payload>>,
+    index: Database<Bytes, Bytes>,
+}
+
+impl PayloadStore {
+    pub(crate) fn new(env: &heed::Env, wtxn: &mut RwTxn) -> heed::Result<Self> {
+        Ok(Self {
+            payloads: env.create_database(wtxn, Some("payloads"))?,
+            index: env.create_database(wtxn, Some("payload-index"))?,
+        })
+    }
+
+    pub(crate) fn get(&self, rtxn: &RoTxn, id: EmbeddingId) -> anyhow::Result<Option<Payload>> {
+        Ok(self.payloads.get(rtxn, &id.0)?)
+    }
+
+    /// Set the payload of an embedding, replacing any existing payload.
+    pub(crate) fn put(
+        &self,
+        wtxn: &mut RwTxn,
+        id: EmbeddingId,
+        payload: &Payload,
+    ) -> anyhow::Result<()> {
+        if let Some((field, _)) = payload.iter().find(|(field, _)| field.contains('\0')) {
+            anyhow::bail!("Payload field names cannot contain null characters: {field:?}");
+        }
+        self.delete(wtxn, id)?;
+        for (field, value) in payload.iter() {
+            let key = [field_prefix(field), value.encode()].concat();
+            self.update_bitmap(wtxn, &key, |bitmap| {
+                bitmap.insert(id.0);
+            })?;
+        }
+        self.payloads.put(wtxn, &id.0, payload)?;
+        Ok(())
+    }
+
+    /// Remove the payload of an embedding.
+    pub(crate) fn delete(&self, wtxn: &mut RwTxn, id: EmbeddingId) -> anyhow::Result<()> {
+        let Some(old) = self.payloads.get(wtxn, &id.0)? else {
+            return Ok(());
+        };
+        for (field, value) in old.iter() {
+            let key = [field_prefix(field), value.encode()].concat();
+            self.update_bitmap(wtxn, &key, |bitmap| {
+                bitmap.remove(id.0);
+            })?;
+        }
+        self.payloads.delete(wtxn, &id.0)?;
+        Ok(())
+    }
+
+    pub(crate) fn clear(&self, wtxn: &mut RwTxn) -> anyhow::Result<()> {
+        self.payloads.clear(wtxn)?;
+        self.index.clear(wtxn)?;
+        Ok(())
+    }
+
+    fn update_bitmap(
+        &self,
+        wtxn: &mut RwTxn,
+        key: &[u8],
+        update: impl FnOnce(&mut RoaringBitmap),
+    ) -> anyhow::Result<()> {
+        let mut bitmap = match self.index.get(wtxn, key)? {
+            Some(bytes) => RoaringBitmap::deserialize_from(bytes)?,
+            None => RoaringBitmap::new(),
+        };
+        update(&mut bitmap);
+        if bitmap.is_empty() {
+            self.index.delete(wtxn, key)?;
+        } else {
+            let mut bytes = Vec::with_capacity(bitmap.serialized_size());
+            bitmap.serialize_into(&mut bytes)?;
+            self.index.put(wtxn, key, &bytes)?;
+        }
+        Ok(())
+    }
+
+    fn bitmap(&self, rtxn: &RoTxn, key: &[u8]) -> anyhow::Result<RoaringBitmap> {
+        Ok(match self.index.get(rtxn, key)? {
+            Some(bytes) => RoaringBitmap::deserialize_from(bytes)?,
+            None => RoaringBitmap::new(),
+        })
+    }
+
+    /// Find the ids of every embedding with a payload that matches the filter.
+    pub(crate) fn candidates(
+        &self,
+        rtxn: &RoTxn,
+        filter: &PayloadFilter,
+    ) -> anyhow::Result<RoaringBitmap> {
+        match filter {
+            PayloadFilter::Equals { field, value } => {
+                self.bitmap(rtxn, &[field_prefix(field), value.encode()].concat())
+            }
+            PayloadFilter::OneOf { field, values } => {
+                let prefix = field_prefix(field);
+                let mut bitmap = RoaringBitmap::new();
+                for value in values {
+                    bitmap |= self.bitmap(rtxn, &[prefix.as_slice(), &value.encode()].concat())?;
+                }
+                Ok(bitmap)
+            }
+            PayloadFilter::Range { field, start, end } => {
+                let (start, end) = range_keys(&field_prefix(field), start, end)?;
+                let range = (
+                    start.as_ref().map(Vec::as_slice),
+                    end.as_ref().map(Vec::as_slice),
+                );
+                let mut bitmap = RoaringBitmap::new();
+                for entry in self.index.range(rtxn, &range)? {
+                    let (_, bytes) = entry?;
+                    bitmap |= RoaringBitmap::deserialize_from(bytes)?;
+                }
+                Ok(bitmap)
+            }
+            PayloadFilter::And(filters) => {
+                let mut filters = filters.iter();
+                let Some(first) = filters.next() else {
+                    anyhow::bail!("An and filter must contain at least one filter");
+                };
+                let mut bitmap = self.candidates(rtxn, first)?;
+                for filter in filters {
+                    if bitmap.is_empty() {
+                        break;
+                    }
+                    bitmap &= self.candidates(rtxn, filter)?;
+                }
+                Ok(bitmap)
+            }
+            PayloadFilter::Or(filters) => {
+                let mut bitmap = RoaringBitmap::new();
+                for filter in filters {
+                    bitmap |= self.candidates(rtxn, filter)?;
+                }
+                Ok(bitmap)
+            }
+        }
+    }
+}
+
+#[test]
+fn payload_values_sort_by_encoding() {
+    let values = [
+        PayloadValue::Integer(i64::MIN),
+        PayloadValue::Integer(-10),
+        PayloadValue::Integer(0),
+        PayloadValue::Integer(42),
+        PayloadValue::Integer(i64::MAX),
+    ];
+    for window in values.windows(2) {
+        assert!(window[0].encode() < window[1].encode());
+    }
+
+    let values = [
+        PayloadValue::Float(f64::NEG_INFINITY),
+        PayloadValue::Float(-1.5),
+        PayloadValue::Float(-0.25),
+        PayloadValue::Float(0.0),
+        PayloadValue::Float(0.25),
+        PayloadValue::Float(1e10),
+    ];
+    for window in values.windows(2) {
+        assert!(window[0].encode() < window[1].encode());
+    }
+}
+
+#[test]
+fn payload_filters_match() {
+    let payload = Payload::new()
+        .with("tenant", "acme")
+        .with("year", 2023)
+        .with("score", 0.5);
+
+    assert!(PayloadFilter::eq("tenant", "acme").matches(&payload));
+    assert!(!PayloadFilter::eq("tenant", "other").matches(&payload));
+    assert!(PayloadFilter::range("year", 2020..=2023).matches(&payload));
+    assert!(!PayloadFilter::range("year", 2020..2023).matches(&payload));
+    assert!(PayloadFilter::range("score", ..1.0).matches(&payload));
+    // Values of a different type never match
+    assert!(!PayloadFilter::range("score", ..1).matches(&payload));
+    assert!(PayloadFilter::one_of("tenant", ["acme", "other"]).matches(&payload));
+    assert!(PayloadFilter::eq("tenant", "other")
+        .or(PayloadFilter::eq("year", 2023))
+        .matches(&payload));
+    assert!(!PayloadFilter::eq("tenant", "acme")
+        .and(PayloadFilter::eq("missing", true))
+        .matches(&payload));
+}
@@ -35,38 +35,27 @@ async fn main() -> anyhow::Result<()> {
         document_table.add_context(context).await?;
     }
 
-    // Create a llama chat model
-    let model = Llama::new_chat().await?;
-    let mut chat = Chat::builder(model).with_system_prompt("The assistant help answer questions based on the context given by the user. The model knows that the information the user gives it is always true.").build();
+    // Create a llama model
+    let model = Llama::new().await?;
+
+    // Create a pipeline that answers questions with the chunks nearest to the question that fit in 1024 tokens
+    let pipeline = RagPipeline::new(document_table, model).with_context_tokens(1024);
 
     loop {
         // Ask the user for a question
         let user_question = prompt_input("\n> ")?;
 
-        // Search for relevant context in the document engine
-        let context = document_table
-            .select_nearest(&user_question, 1)
-            .await?
-            .into_iter()
-            .map(|document| {
-                format!(
-                    "Title: {}\nBody: {}\n",
-                    document.record.title(),
-                    document.record.body()
-                )
-            })
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        // Format a prompt with the question and context
-        let prompt = format!("{context}\n{user_question}");
-
-        // Display the prompt to the user for debugging purposes
-        println!("{}", prompt);
-
-        // And finally, respond to the user
-        let mut output_stream = chat.add_message(prompt);
-        print!("Bot: ");
-        output_stream.to_std_out().await?;
+        // Answer the question with citations to the documents
+        let answer = pipeline.answer(&user_question).await?;
+        println!("Bot: {}", answer.answer);
+
+        // Display the sources that were cited in the answer
+        for citation in answer.citations {
+            let source = citation.source;
+            println!(
+                "[{}] {} (bytes {:?})",
+                citation.number, source.title, source.byte_range
+            );
+        }
     }
 }
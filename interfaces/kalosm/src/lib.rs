@@ -26,6 +26,12 @@ pub mod language {
 
     #[cfg(feature = "surrealdb")]
     pub use crate::surrealdb_integration::document_table::*;
+    #[cfg(feature = "surrealdb")]
+    pub use crate::surrealdb_integration::folder_sync::*;
+    #[cfg(feature = "surrealdb")]
+    pub use crate::surrealdb_integration::rag::*;
+    #[cfg(feature = "surrealdb")]
+    pub use crate::tools::*;
 }
 #[cfg(feature = "sound")]
 pub mod sound {
@@ -53,6 +59,8 @@ pub use prompt_annealing::*;
 
 #[cfg(feature = "surrealdb")]
 mod surrealdb_integration;
+#[cfg(all(feature = "language", feature = "surrealdb"))]
+mod tools;
 #[cfg(feature = "surrealdb")]
 pub use ::surrealdb;
 #[cfg(feature = "surrealdb")]
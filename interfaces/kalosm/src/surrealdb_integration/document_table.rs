@@ -1,11 +1,18 @@
 use std::any::Any;
 use std::any::TypeId;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
 
-use super::{EmbeddingIndexedTable, EmbeddingIndexedTableSearchResult};
+use super::folder_sync::SyncedFile;
+use super::{
+    ChunkEmbeddingIds, EmbeddingIndexedTable, EmbeddingIndexedTableSearchResult, RecordFilter,
+};
 use kalosm_language::prelude::*;
 use serde::de::DeserializeOwned;
-use serde::Serialize;
-use surrealdb::sql::Id;
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::{Id, Thing};
 use surrealdb::Connection;
 use surrealdb::Surreal;
 
@@ -71,20 +78,50 @@ pub struct DocumentTable<
     embedding_model: M,
     chunker: K,
     table: EmbeddingIndexedTable<C, R, M::VectorSpace>,
+    keyword_index: Bm25Index,
+    stored_model: OnceLock<EmbeddingModelMetadata>,
+    /// Writes hold a read lock and [`DocumentTable::reembed_all`] holds the write lock, so records cannot change while they are migrated.
+    migration: tokio::sync::RwLock<()>,
+    /// Set once [`DocumentTable::reembed_all`] replaces the links of the table. The vector database of this table no longer matches the records.
+    retired: AtomicBool,
 }
 
+/// The embedding model the embeddings in a [`DocumentTable`] were created with.
+///
+/// This is stored in the `{table}-metadata` table when the first record is inserted. Queries with a different model are refused.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddingModelMetadata {
+    /// The id of the model. See [`Embedder::model_id`].
+    pub model_id: String,
+    /// The number of dimensions of the embeddings.
+    pub dimensions: usize,
+}
+
+/// The id of the [`EmbeddingModelMetadata`] record in the metadata table.
+const EMBEDDING_MODEL_METADATA_ID: &str = "embedding_model";
+
+/// The number of candidates fetched from each index per requested result in [`DocumentTable::select_hybrid`].
+const HYBRID_CANDIDATE_MULTIPLIER: usize = 4;
+
 impl<C: Connection, R, M: Embedder, K: Chunker> DocumentTable<C, R, M, K> {
-    /// Create a new document table.
+    /// Create a new document table. The keyword index is stored in the same environment as the vector database of the table.
+    ///
+    /// This returns an error if the keyword index cannot be opened. Before the keyword index was added, this function could not fail.
     pub fn new(
         embedding_model: M,
         table: EmbeddingIndexedTable<C, R, M::VectorSpace>,
         chunker: K,
-    ) -> Self {
-        Self {
+    ) -> anyhow::Result<Self> {
+        let keyword_index = Bm25Index::from_env(table.vector_db().env().clone())?;
+        Ok(Self {
             embedding_model,
             table,
             chunker,
-        }
+            keyword_index,
+            stored_model: OnceLock::new(),
+            migration: tokio::sync::RwLock::new(()),
+            retired: AtomicBool::new(false),
+        })
     }
 
     /// Get the raw table.
@@ -92,19 +129,148 @@ impl<C: Connection, R, M: Embedder, K: Chunker> DocumentTable<C, R, M, K> {
         &self.table
     }
 
+    /// Get the keyword index of the table.
+    pub fn keyword_index(&self) -> &Bm25Index {
+        &self.keyword_index
+    }
+
     /// Get the raw embedding model.
     pub fn embedding_model(&self) -> &M {
         &self.embedding_model
     }
 
+    /// Get the chunker of the table.
+    pub fn chunker(&self) -> &K {
+        &self.chunker
+    }
+
+    /// Get the name of the table the [`EmbeddingModelMetadata`] is stored in.
+    pub fn metadata_table(&self) -> String {
+        format!("{}-metadata", self.table.table())
+    }
+
+    /// Get the record the [`EmbeddingModelMetadata`] is stored in.
+    pub(crate) fn model_metadata_record(&self) -> Thing {
+        Thing {
+            tb: self.metadata_table(),
+            id: Id::String(EMBEDDING_MODEL_METADATA_ID.to_string()),
+        }
+    }
+
+    /// Get the embedding model the embeddings in the table were created with. Returns `None` if no records have been inserted yet.
+    pub async fn model_metadata(&self) -> anyhow::Result<Option<EmbeddingModelMetadata>> {
+        if let Some(metadata) = self.stored_model.get() {
+            return Ok(Some(metadata.clone()));
+        }
+        let metadata: Option<EmbeddingModelMetadata> =
+            self.table.db().select(self.model_metadata_record()).await?;
+        Ok(metadata.map(|metadata| self.stored_model.get_or_init(|| metadata).clone()))
+    }
+
+    /// Make sure the table was not replaced by [`DocumentTable::reembed_all`].
+    fn check_not_retired(&self) -> anyhow::Result<()> {
+        if self.retired.load(Ordering::SeqCst) {
+            anyhow::bail!(
+                "The table `{}` was migrated to a new embedding model with `DocumentTable::reembed_all`. Use the table returned by `reembed_all` instead.",
+                self.table.table()
+            );
+        }
+        Ok(())
+    }
+
+    /// Wait for a running [`DocumentTable::reembed_all`] to finish before writing to the table. The returned guard must be held until the write is done.
+    async fn start_write(&self) -> anyhow::Result<tokio::sync::RwLockReadGuard<'_, ()>> {
+        let guard = self.migration.read().await;
+        self.check_not_retired()?;
+        Ok(guard)
+    }
+
+    /// Block writes to the table until the returned guard is dropped. Used by [`DocumentTable::reembed_all`].
+    pub(crate) async fn start_migration(
+        &self,
+    ) -> anyhow::Result<tokio::sync::RwLockWriteGuard<'_, ()>> {
+        let guard = self.migration.write().await;
+        self.check_not_retired()?;
+        Ok(guard)
+    }
+
+    /// Refuse every query and write to the table after [`DocumentTable::reembed_all`] replaced its links.
+    pub(crate) fn retire(&self) {
+        self.retired.store(true, Ordering::SeqCst);
+    }
+
+    /// Make sure embeddings with the given number of dimensions from the embedding model of this table can be compared to the embeddings in the table.
+    pub(crate) async fn check_model(&self, dimensions: usize) -> anyhow::Result<()> {
+        self.check_not_retired()?;
+        let Some(metadata) = self.model_metadata().await? else {
+            return Ok(());
+        };
+        let model_id = self.embedding_model.model_id();
+        if metadata.model_id != model_id {
+            anyhow::bail!(
+                "The embeddings in the table `{}` were created with the model `{}`, but the table is being used with the model `{model_id}`. Use `DocumentTable::reembed_all` to migrate the table to the new model.",
+                self.table.table(),
+                metadata.model_id
+            );
+        }
+        if metadata.dimensions != dimensions {
+            anyhow::bail!(
+                "The table `{}` contains {} dimensional embeddings, but the embedding has {dimensions} dimensions",
+                self.table.table(),
+                metadata.dimensions
+            );
+        }
+        Ok(())
+    }
+
+    /// Check the model before embeddings with the given number of dimensions are inserted. If this is the first insert, the model is stored in the metadata table.
+    pub(crate) async fn check_or_store_model(&self, dimensions: usize) -> anyhow::Result<()> {
+        if self.model_metadata().await?.is_some() {
+            return self.check_model(dimensions).await;
+        }
+        let metadata = EmbeddingModelMetadata {
+            model_id: self.embedding_model.model_id(),
+            dimensions,
+        };
+        self.table
+            .db()
+            .update::<Option<EmbeddingModelMetadata>>(self.model_metadata_record())
+            .content(metadata.clone())
+            .await?;
+        self.stored_model.get_or_init(|| metadata);
+        Ok(())
+    }
+
     /// Delete the table from the database and clear the vector database. Returns the contents of the table.
     pub async fn delete_table(self) -> anyhow::Result<Vec<(R, Vec<Chunk<M::VectorSpace>>)>>
     where
         R: DeserializeOwned,
     {
+        self.keyword_index.clear()?;
+        let _: Vec<SyncedFile> = self.table.db().delete(self.files_table()).await?;
+        let _: Vec<EmbeddingModelMetadata> = self.table.db().delete(self.metadata_table()).await?;
         self.table.delete_table().await
     }
 
+    /// Add the text of each chunk to the keyword index. Chunks with multiple embeddings are indexed under their first embedding id.
+    pub(crate) fn index_keywords(
+        &self,
+        body: &str,
+        chunks: &ChunkEmbeddingIds,
+    ) -> anyhow::Result<()> {
+        self.keyword_index
+            .add_documents(chunks.iter().filter_map(|(byte_range, ids)| {
+                let id = ids.first()?;
+                Some((*id, &body[byte_range.clone()]))
+            }))
+    }
+
+    /// Remove the chunks from the keyword index.
+    fn remove_keywords(&self, chunks: &ChunkEmbeddingIds) -> anyhow::Result<()> {
+        self.keyword_index
+            .remove_documents(chunks.iter().filter_map(|(_, ids)| ids.first().copied()))
+    }
+
     /// Insert a new record into the table with pre-computed chunks.
     pub async fn insert_with_chunks(
         &self,
@@ -112,9 +278,17 @@ impl<C: Connection, R, M: Embedder, K: Chunker> DocumentTable<C, R, M, K> {
         chunks: impl IntoIterator<Item = Chunk<M::VectorSpace>>,
     ) -> anyhow::Result<Id>
     where
-        R: Serialize + DeserializeOwned,
+        R: AsRef<Document> + Serialize + DeserializeOwned,
     {
-        self.table.insert(chunks, value).await
+        let _writing = self.start_write().await?;
+        let chunks = chunks.into_iter().collect::<Vec<_>>();
+        if let Some(dimensions) = embedding_dimensions(&chunks) {
+            self.check_or_store_model(dimensions).await?;
+        }
+        let body = value.as_ref().body().to_string();
+        let (id, chunks) = self.table.insert_returning_chunks(chunks, value).await?;
+        self.index_keywords(&body, &chunks)?;
+        Ok(id)
     }
 
     /// Insert a new record into the table and return the id of the record.
@@ -143,18 +317,39 @@ impl<C: Connection, R, M: Embedder, K: Chunker> DocumentTable<C, R, M, K> {
             .await?;
         let mut ids = Vec::new();
         for (value, embeddings) in entries.into_iter().zip(embeddings) {
-            let id = self.table.insert(embeddings, value).await?;
+            let id = self.insert_with_chunks(value, embeddings).await?;
             ids.push(id);
         }
         Ok(ids)
     }
 
-    /// Update a record in the table with the given embedding id.
+    /// Update a record in the table with the given embedding id. The new record is re-chunked and re-embedded. Returns the old record if it existed.
+    ///
+    /// The record is replaced, not merged with the old record, so the chunks, embeddings and keywords always match the new body.
+    /// Use [`EmbeddingIndexedTable::update`] through [`DocumentTable::table`] to merge fields into a record without re-embedding it. The chunks and keywords of the record are not updated by a merge.
     pub async fn update(&self, id: Id, value: R) -> anyhow::Result<Option<R>>
     where
-        R: Serialize + DeserializeOwned,
+        R: AsRef<Document> + Serialize + DeserializeOwned,
     {
-        self.table.update(id, value).await
+        let chunks = self
+            .chunker
+            .chunk(value.as_ref(), &self.embedding_model)
+            .await?;
+        let _writing = self.start_write().await?;
+        if let Some(dimensions) = embedding_dimensions(&chunks) {
+            self.check_or_store_model(dimensions).await?;
+        }
+        let body = value.as_ref().body().to_string();
+        let Some((old, chunks)) = self
+            .table
+            .update_returning_chunks(id, chunks, value)
+            .await?
+        else {
+            return Ok(None);
+        };
+        self.remove_keywords(&old.chunks)?;
+        self.index_keywords(&body, &chunks)?;
+        Ok(Some(old.object))
     }
 
     /// Select a record from the table with the given embedding id.
@@ -170,10 +365,17 @@ impl<C: Connection, R, M: Embedder, K: Chunker> DocumentTable<C, R, M, K> {
     where
         R: Serialize + DeserializeOwned,
     {
-        self.table.delete(id).await
+        let _writing = self.start_write().await?;
+        let Some(old) = self.table.delete_returning_chunks(id).await? else {
+            return Ok(None);
+        };
+        self.remove_keywords(&old.chunks)?;
+        Ok(Some(old.object))
     }
 
     /// Select all records from the table.
+    ///
+    /// Use [`DocumentTable::query`] to only select records that match SurrealQL conditions.
     pub async fn select_all(&self) -> anyhow::Result<Vec<R>>
     where
         R: Serialize + DeserializeOwned,
@@ -181,6 +383,42 @@ impl<C: Connection, R, M: Embedder, K: Chunker> DocumentTable<C, R, M, K> {
         self.table.select_all().await
     }
 
+    /// Start a query that combines the vector search with SurrealQL conditions on the fields of the records.
+    ///
+    /// # Example
+    /// ```rust, no_run
+    /// use kalosm::language::*;
+    /// use surrealdb::{engine::local::RocksDb, Surreal};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let db = Surreal::new::<RocksDb>("./db/temp.db").await?;
+    ///     db.use_ns("test").use_db("test").await?;
+    ///     let document_table = db
+    ///         .document_table_builder("documents")
+    ///         .at("./db/embeddings.db")
+    ///         .build::<Document>()
+    ///         .await?;
+    ///
+    ///     let nearest = document_table
+    ///         .query()
+    ///         .filter("title CONTAINS $topic")
+    ///         .filter("created_at > <datetime> $after")
+    ///         .bind("topic", "Kalosm")
+    ///         .bind("after", "2024-01-01T00:00:00Z")
+    ///         .select_nearest("How do I use structured generation?", 5)
+    ///         .await?;
+    ///     println!("{:?}", nearest);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn query(&self) -> DocumentTableQuery<'_, C, R, M, K> {
+        DocumentTableQuery {
+            table: self,
+            filter: RecordFilter::default(),
+        }
+    }
+
     /// Select the top k records nearest records to the given item.
     ///
     /// NOTE: If your embedding model has a different query embedding and you pass in a raw embedding, that embedding will perform best if it was created with [`EmbedderExt::embed_query`].
@@ -193,8 +431,214 @@ impl<C: Connection, R, M: Embedder, K: Chunker> DocumentTable<C, R, M, K> {
         R: DeserializeOwned,
     {
         let embedding = embedding.into_embedding(&self.embedding_model).await?;
+        self.check_model(embedding.vector().elem_count()).await?;
         self.table.select_nearest(embedding, k).await
     }
+
+    /// Select k records near the given item that are also different from each other with maximal marginal relevance (MMR).
+    ///
+    /// See [`VectorDB::get_closest_mmr`] for more information about `lambda` and `fetch_k`.
+    pub async fn select_nearest_mmr(
+        &self,
+        embedding: impl IntoEmbedding<M::VectorSpace>,
+        k: usize,
+        lambda: f32,
+        fetch_k: usize,
+    ) -> anyhow::Result<Vec<EmbeddingIndexedTableSearchResult<R>>>
+    where
+        R: DeserializeOwned,
+    {
+        let embedding = embedding.into_embedding(&self.embedding_model).await?;
+        self.check_model(embedding.vector().elem_count()).await?;
+        self.table
+            .select_nearest_mmr(embedding, k, lambda, fetch_k)
+            .await
+    }
+
+    /// Select all records within the given distance of the given item, sorted from closest to furthest.
+    pub async fn select_within_distance(
+        &self,
+        embedding: impl IntoEmbedding<M::VectorSpace>,
+        max_distance: f32,
+    ) -> anyhow::Result<Vec<EmbeddingIndexedTableSearchResult<R>>>
+    where
+        R: DeserializeOwned,
+    {
+        let embedding = embedding.into_embedding(&self.embedding_model).await?;
+        self.check_model(embedding.vector().elem_count()).await?;
+        self.table
+            .select_within_distance(embedding, max_distance)
+            .await
+    }
+
+    /// Select the top k chunks for a query by combining keyword search with the [`Bm25Index`] and embedding similarity.
+    ///
+    /// `alpha` controls the weight of each search. An alpha of `1.0` only uses embedding similarity, and an alpha of `0.0` only uses keyword search.
+    /// The scores of both searches are normalized to the range `[0, 1]` before they are combined. The `distance` of each result is `1.0 - combined_score`.
+    pub async fn select_hybrid(
+        &self,
+        query: &str,
+        k: usize,
+        alpha: f32,
+    ) -> anyhow::Result<Vec<EmbeddingIndexedTableSearchResult<R>>>
+    where
+        R: DeserializeOwned,
+    {
+        let alpha = alpha.clamp(0.0, 1.0);
+        let candidates = k.saturating_mul(HYBRID_CANDIDATE_MULTIPLIER);
+
+        let embedding = self.embedding_model.embed_query(query).await?;
+        self.check_model(embedding.vector().elem_count()).await?;
+        let vector_results = self.table.vector_db().get_closest(embedding, candidates)?;
+        let keyword_results = self.keyword_index.search(query, candidates)?;
+
+        // Normalize the distances and scores so they can be combined
+        let (min_distance, max_distance) = vector_results
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(min, max), result| {
+                (min.min(result.distance), max.max(result.distance))
+            });
+        let max_keyword_score = keyword_results
+            .iter()
+            .fold(0.0f32, |max, result| max.max(result.score));
+
+        // Multiple embeddings can point to the same chunk, so we merge the scores by chunk
+        let mut chunks: HashMap<(Id, Range<usize>), HybridCandidate> = HashMap::new();
+        for result in vector_results {
+            let similarity = if max_distance > min_distance {
+                (max_distance - result.distance) / (max_distance - min_distance)
+            } else {
+                1.0
+            };
+            let link = self.table.select_link(result.value).await?;
+            let candidate = chunks
+                .entry((link.document_id, link.byte_range))
+                .or_insert_with(|| HybridCandidate::new(result.value));
+            candidate.vector_score = candidate.vector_score.max(similarity);
+        }
+        for result in keyword_results {
+            let score = if max_keyword_score > 0.0 {
+                result.score / max_keyword_score
+            } else {
+                0.0
+            };
+            let link = self.table.select_link(result.value).await?;
+            let candidate = chunks
+                .entry((link.document_id, link.byte_range))
+                .or_insert_with(|| HybridCandidate::new(result.value));
+            candidate.keyword_score = candidate.keyword_score.max(score);
+        }
+
+        let mut ranked = chunks
+            .into_iter()
+            .map(|(key, candidate)| {
+                let score =
+                    alpha * candidate.vector_score + (1.0 - alpha) * candidate.keyword_score;
+                (score, key, candidate.id)
+            })
+            .collect::<Vec<_>>();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        ranked.truncate(k);
+
+        let mut records = Vec::with_capacity(ranked.len());
+        for (score, (record_id, byte_range), id) in ranked {
+            let record = self.table.select(record_id.clone()).await?;
+            records.push(EmbeddingIndexedTableSearchResult {
+                distance: 1.0 - score,
+                id,
+                record_id,
+                byte_range,
+                record,
+            });
+        }
+        Ok(records)
+    }
+}
+
+/// Get the number of dimensions of the embeddings in the chunks.
+fn embedding_dimensions<S: VectorSpace>(chunks: &[Chunk<S>]) -> Option<usize> {
+    chunks
+        .iter()
+        .flat_map(|chunk| chunk.embeddings.first())
+        .map(|embedding| embedding.vector().elem_count())
+        .next()
+}
+
+/// A query on a [`DocumentTable`] that only matches records that meet SurrealQL conditions. Created with [`DocumentTable::query`].
+pub struct DocumentTableQuery<'a, C: Connection, R, M: Embedder, K: Chunker> {
+    table: &'a DocumentTable<C, R, M, K>,
+    filter: RecordFilter,
+}
+
+impl<'a, C: Connection, R, M: Embedder, K: Chunker> DocumentTableQuery<'a, C, R, M, K> {
+    /// Require that records match a SurrealQL condition like `author = $author`. Multiple conditions are combined with `AND`.
+    pub fn filter(mut self, condition: impl ToString) -> Self {
+        self.filter = self.filter.and(condition);
+        self
+    }
+
+    /// Bind a value to a parameter used in the conditions.
+    pub fn bind(mut self, name: impl ToString, value: impl Serialize) -> Self {
+        self.filter = self.filter.bind(name, value);
+        self
+    }
+
+    /// Replace the conditions of the query with a [`RecordFilter`].
+    pub fn with_filter(mut self, filter: RecordFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Select the top k chunks nearest to the given item out of the records that match the conditions.
+    ///
+    /// The conditions are resolved by the surreal database before the vector search, so only the embeddings of matching records are searched.
+    pub async fn select_nearest(
+        self,
+        embedding: impl IntoEmbedding<M::VectorSpace>,
+        k: usize,
+    ) -> anyhow::Result<Vec<EmbeddingIndexedTableSearchResult<R>>>
+    where
+        R: DeserializeOwned,
+    {
+        if self.filter.is_empty() {
+            return self.table.select_nearest(embedding, k).await;
+        }
+        let embedding = embedding
+            .into_embedding(&self.table.embedding_model)
+            .await?;
+        self.table
+            .check_model(embedding.vector().elem_count())
+            .await?;
+        self.table
+            .table
+            .select_nearest_filtered(embedding, k, &self.filter)
+            .await
+    }
+
+    /// Select all records that match the conditions.
+    pub async fn select_all(self) -> anyhow::Result<Vec<R>>
+    where
+        R: DeserializeOwned,
+    {
+        self.table.table.select_all_filtered(&self.filter).await
+    }
+}
+
+/// A chunk found by either the vector or keyword search in [`DocumentTable::select_hybrid`].
+struct HybridCandidate {
+    id: EmbeddingId,
+    vector_score: f32,
+    keyword_score: f32,
+}
+
+impl HybridCandidate {
+    fn new(id: EmbeddingId) -> Self {
+        Self {
+            id,
+            vector_score: 0.0,
+            keyword_score: 0.0,
+        }
+    }
 }
 
 impl<C: Connection, R, M: Embedder, K: Chunker> DocumentTable<C, R, M, K> {
@@ -290,7 +734,7 @@ impl<C: Connection, E, K: Chunker> DocumentTableBuilder<C, E, K> {
                 }
             }
         };
-        Ok(DocumentTable::new(embedding_model, table, self.chunker))
+        DocumentTable::new(embedding_model, table, self.chunker)
     }
 }
 
@@ -305,3 +749,97 @@ impl<C: Connection> DocumentTableSurrealExt<C> for Surreal<C> {
         DocumentTableBuilder::new(table, self.clone())
     }
 }
+
+#[tokio::test]
+async fn keyword_index_persists_across_reopening() {
+    use super::testing::{test_db, test_documents, test_table};
+
+    let (dir, db) = test_db().await;
+    let table = test_table(&dir, &db).await;
+    table.extend(test_documents()).await.unwrap();
+    let indexed = table.keyword_index().len().unwrap();
+    assert!(indexed > 0);
+    drop(table);
+
+    let table = test_table(&dir, &db).await;
+    assert_eq!(table.keyword_index().len().unwrap(), indexed);
+    let results = table.select_hybrid("borrow checker", 1, 0.0).await.unwrap();
+    assert_eq!(results[0].text(), "The borrow checker prevents data races.");
+}
+
+#[tokio::test]
+async fn hybrid_search_combines_keywords_and_embeddings() {
+    use super::testing::{test_db, test_documents, test_table};
+
+    let (dir, db) = test_db().await;
+    let table = test_table(&dir, &db).await;
+    table.extend(test_documents()).await.unwrap();
+
+    for alpha in [0.0, 0.5, 1.0] {
+        let results = table.select_hybrid("cat mat", 2, alpha).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].text(), "The cat sat on the mat.");
+        assert_eq!(results[0].record.title(), "Pets");
+        // Results are sorted from the closest to the furthest and the distances are normalized
+        assert!(results[0].distance <= results[1].distance);
+        for result in &results {
+            assert!((0.0..=1.0).contains(&result.distance));
+        }
+    }
+
+    // Keyword search alone finds the exact chunk with the best score
+    let results = table.select_hybrid("cat mat", 1, 0.0).await.unwrap();
+    assert_eq!(results[0].distance, 0.0);
+
+    // Updating a record replaces its keywords
+    let id = results[0].record_id.clone();
+    table
+        .update(
+            id,
+            Document::from_parts("Pets", "Parrots repeat every word they hear."),
+        )
+        .await
+        .unwrap();
+    let results = table.select_hybrid("parrots", 1, 0.0).await.unwrap();
+    assert_eq!(results[0].text(), "Parrots repeat every word they hear.");
+    let results = table.select_hybrid("cat mat", 3, 0.0).await.unwrap();
+    assert!(results.iter().all(|result| !result.text().contains("cat")));
+}
+
+#[tokio::test]
+async fn queries_filter_records() {
+    use super::testing::{test_db, test_documents, test_table};
+
+    let (dir, db) = test_db().await;
+    let table = test_table(&dir, &db).await;
+    table.extend(test_documents()).await.unwrap();
+
+    let pets = table
+        .query()
+        .filter("title = $title")
+        .bind("title", "Pets")
+        .select_all()
+        .await
+        .unwrap();
+    assert_eq!(pets.len(), 1);
+    assert_eq!(pets[0].title(), "Pets");
+
+    let nearest = table
+        .query()
+        .filter("title = $title")
+        .bind("title", "Programming")
+        .select_nearest("The cat sat on the mat.", 1)
+        .await
+        .unwrap();
+    assert_eq!(nearest[0].record.title(), "Programming");
+
+    // The name of the table cannot be replaced by a binding
+    assert!(table
+        .query()
+        .filter("title = $title")
+        .bind("title", "Pets")
+        .bind("table", "documents-links")
+        .select_all()
+        .await
+        .is_err());
+}
@@ -0,0 +1,108 @@
+use serde::Serialize;
+use surrealdb::sql::Value;
+
+/// The parameter the name of the table is bound to.
+const TABLE_PARAMETER: &str = "table";
+
+/// A SurrealQL `WHERE` condition on the records of an [`EmbeddingIndexedTable`](super::EmbeddingIndexedTable).
+///
+/// Conditions can refer to any field of the record type and use parameters that are bound with [`RecordFilter::bind`].
+/// The `$table` parameter is reserved for the name of the table.
+///
+/// # Example
+/// ```rust, no_run
+/// use kalosm::RecordFilter;
+///
+/// let filter = RecordFilter::new("author = $author")
+///     .and("created_at > <datetime> $after")
+///     .bind("author", "Jane Doe")
+///     .bind("after", "2024-01-01T00:00:00Z");
+/// ```
+#[derive(Debug, Default)]
+pub struct RecordFilter {
+    conditions: Vec<String>,
+    bindings: Vec<(String, Value)>,
+    error: Option<String>,
+}
+
+impl RecordFilter {
+    /// Create a new filter with a SurrealQL condition.
+    pub fn new(condition: impl ToString) -> Self {
+        Self {
+            conditions: vec![condition.to_string()],
+            bindings: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Require that records also match another SurrealQL condition.
+    pub fn and(mut self, condition: impl ToString) -> Self {
+        self.conditions.push(condition.to_string());
+        self
+    }
+
+    /// Check if the filter has no conditions. An empty filter matches every record.
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    /// Bind a value to a parameter used in the conditions.
+    ///
+    /// The `table` parameter is reserved for the name of the table. Binding it makes the query return an error.
+    pub fn bind(mut self, name: impl ToString, value: impl Serialize) -> Self {
+        let name = name.to_string();
+        if name == TABLE_PARAMETER {
+            self.error.get_or_insert(format!(
+                "The `${TABLE_PARAMETER}` parameter is reserved for the name of the table"
+            ));
+            return self;
+        }
+        match surrealdb::sql::to_value(value) {
+            Ok(value) => self.bindings.push((name, value)),
+            Err(err) => {
+                self.error.get_or_insert(err.to_string());
+            }
+        }
+        self
+    }
+
+    /// Select the given fields of every record in the table that matches the filter.
+    pub(crate) async fn select<C: surrealdb::Connection, T: serde::de::DeserializeOwned>(
+        &self,
+        db: &surrealdb::Surreal<C>,
+        fields: &str,
+        table: &str,
+    ) -> anyhow::Result<Vec<T>> {
+        if let Some(err) = &self.error {
+            anyhow::bail!("Failed to bind a parameter of the filter: {err}");
+        }
+        let mut sql = format!("SELECT {fields} FROM type::table($table)");
+        if !self.is_empty() {
+            let condition = self
+                .conditions
+                .iter()
+                .map(|condition| format!("({condition})"))
+                .collect::<Vec<_>>()
+                .join(" AND ");
+            sql.push_str(" WHERE ");
+            sql.push_str(&condition);
+        }
+        let mut query = db.query(sql).bind((TABLE_PARAMETER, table.to_string()));
+        for (name, value) in &self.bindings {
+            query = query.bind((name.clone(), value.clone()));
+        }
+        let mut response = query.await?.check()?;
+        Ok(response.take(0)?)
+    }
+}
+
+#[test]
+fn the_table_parameter_is_reserved() {
+    let filter = RecordFilter::new("title = $title").bind("title", "Pets");
+    assert!(filter.error.is_none());
+    assert_eq!(filter.bindings.len(), 1);
+
+    let filter = filter.bind("table", "other-table");
+    assert!(filter.error.unwrap().contains("reserved"));
+    assert_eq!(filter.bindings.len(), 1);
+}
@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use kalosm_language::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use surrealdb::sql::{Id, Thing};
+use surrealdb::Connection;
+
+use super::document_table::DocumentTable;
+
+/// A file that was synced into a [`DocumentTable`] with [`DocumentTable::sync_folder`].
+///
+/// These are stored in the `{table}-files` table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SyncedFile {
+    path: String,
+    modified: u64,
+    hash: String,
+    /// The records of the documents in the file. Most files are one document, but mailboxes are split into one document per email.
+    record_ids: Vec<Id>,
+}
+
+/// The changes made by [`DocumentTable::sync_folder`].
+#[derive(Debug, Default)]
+pub struct FolderSyncSummary {
+    /// The files that were added to the table.
+    pub added: Vec<PathBuf>,
+    /// The files that changed and were re-chunked and re-embedded.
+    pub updated: Vec<PathBuf>,
+    /// The files that were removed from the folder and deleted from the table.
+    pub removed: Vec<PathBuf>,
+    /// The number of files that did not change.
+    pub unchanged: usize,
+    /// The files that could not be read along with the error.
+    pub failed: Vec<(PathBuf, anyhow::Error)>,
+}
+
+/// Get the last modified time of a file in nanoseconds since the unix epoch.
+async fn modified_time(path: &Path) -> anyhow::Result<u64> {
+    let modified = tokio::fs::metadata(path).await?.modified()?;
+    Ok(modified.duration_since(UNIX_EPOCH)?.as_nanos() as u64)
+}
+
+/// Hash the contents of a file.
+async fn content_hash(path: &Path) -> anyhow::Result<String> {
+    let bytes = tokio::fs::read(path).await?;
+    let hash = Sha256::digest(bytes);
+    Ok(hash.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+impl<C: Connection, R, M: Embedder, K: Chunker> DocumentTable<C, R, M, K> {
+    /// Get the name of the table the synced files are stored in.
+    pub(crate) fn files_table(&self) -> String {
+        format!("{}-files", self.table().table())
+    }
+
+    /// Sync the documents in a folder into the table.
+    ///
+    /// The path, modification time and content hash of every file is stored in the `{table}-files` table. Only files that are new or
+    /// changed since the last sync are chunked and embedded, and the records of files that were removed from the folder are deleted.
+    ///
+    /// # Example
+    /// ```rust, no_run
+    /// use kalosm::language::*;
+    /// use surrealdb::{engine::local::RocksDb, Surreal};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let db = Surreal::new::<RocksDb>("./db/temp.db").await?;
+    ///     db.use_ns("test").use_db("test").await?;
+    ///     let document_table = db
+    ///         .document_table_builder("documents")
+    ///         .at("./db/embeddings.db")
+    ///         .build::<Document>()
+    ///         .await?;
+    ///
+    ///     let folder = DocumentFolder::new("./documents")?;
+    ///     let summary = document_table.sync_folder(&folder).await?;
+    ///     println!(
+    ///         "added {}, updated {}, removed {}",
+    ///         summary.added.len(),
+    ///         summary.updated.len(),
+    ///         summary.removed.len()
+    ///     );
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn sync_folder(&self, folder: &DocumentFolder) -> anyhow::Result<FolderSyncSummary>
+    where
+        R: From<Document> + AsRef<Document> + Serialize + DeserializeOwned,
+    {
+        let files_table = self.files_table();
+        let synced: Vec<SyncedFile> = self.table().db().select(&files_table).await?;
+        let mut synced: HashMap<String, SyncedFile> = synced
+            .into_iter()
+            .map(|file| (file.path.clone(), file))
+            .collect();
+
+        let mut summary = FolderSyncSummary::default();
+        for path in folder.files().await? {
+            let key = path.to_string_lossy().to_string();
+            let previous = synced.remove(&key);
+            match self.sync_file(&path, key, previous).await {
+                Ok(FileSync::Added) => summary.added.push(path),
+                Ok(FileSync::Updated) => summary.updated.push(path),
+                Ok(FileSync::Unchanged) => summary.unchanged += 1,
+                Err(err) => summary.failed.push((path, err)),
+            }
+        }
+
+        // Any file that was synced before, but is no longer in the folder was removed
+        for (key, file) in synced {
+            for record_id in file.record_ids {
+                self.delete(record_id).await?;
+            }
+            self.table()
+                .db()
+                .delete::<Option<SyncedFile>>(Thing {
+                    tb: files_table.clone(),
+                    id: Id::String(key),
+                })
+                .await?;
+            summary.removed.push(PathBuf::from(file.path));
+        }
+
+        Ok(summary)
+    }
+
+    async fn sync_file(
+        &self,
+        path: &Path,
+        key: String,
+        previous: Option<SyncedFile>,
+    ) -> anyhow::Result<FileSync>
+    where
+        R: From<Document> + AsRef<Document> + Serialize + DeserializeOwned,
+    {
+        let modified = modified_time(path).await?;
+        if let Some(previous) = &previous {
+            if previous.modified == modified {
+                return Ok(FileSync::Unchanged);
+            }
+        }
+
+        let hash = content_hash(path).await?;
+        let (record_ids, sync) = match previous {
+            // The file was touched, but the contents are the same
+            Some(previous) if previous.hash == hash => (previous.record_ids, FileSync::Unchanged),
+            previous => {
+                // Read the file the same way as DocumentFolder so mailboxes are split into one document per email
+                let documents = FsDocument::try_from(path.to_path_buf())?
+                    .into_documents()
+                    .await?;
+                let sync = match previous {
+                    Some(_) => FileSync::Updated,
+                    None => FileSync::Added,
+                };
+                let mut previous_ids = previous
+                    .map(|previous| previous.record_ids)
+                    .unwrap_or_default()
+                    .into_iter();
+                let mut record_ids = Vec::with_capacity(documents.len());
+                for document in documents {
+                    let updated = match previous_ids.next() {
+                        Some(record_id) => self
+                            .update(record_id.clone(), R::from(document.clone()))
+                            .await?
+                            .map(|_| record_id),
+                        None => None,
+                    };
+                    let record_id = match updated {
+                        Some(record_id) => record_id,
+                        // If the record was deleted from the table, insert it again
+                        None => self.insert(R::from(document)).await?,
+                    };
+                    record_ids.push(record_id);
+                }
+                // The file has fewer documents than before
+                for record_id in previous_ids {
+                    self.delete(record_id).await?;
+                }
+                (record_ids, sync)
+            }
+        };
+
+        self.table()
+            .db()
+            .update::<Option<SyncedFile>>(Thing {
+                tb: self.files_table(),
+                id: Id::String(key.clone()),
+            })
+            .content(SyncedFile {
+                path: key,
+                modified,
+                hash,
+                record_ids,
+            })
+            .await?;
+
+        Ok(sync)
+    }
+}
+
+enum FileSync {
+    Added,
+    Updated,
+    Unchanged,
+}
+
+#[cfg(feature = "notify")]
+pub use watcher::*;
+
+#[cfg(feature = "notify")]
+mod watcher {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use kalosm_language::prelude::*;
+    use notify::{RecursiveMode, Watcher};
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+    use surrealdb::Connection;
+
+    use super::DocumentTable;
+
+    /// How long the watcher waits for the file system to settle before syncing.
+    const DEBOUNCE: Duration = Duration::from_millis(500);
+
+    /// A handle to a folder that is kept in sync with a [`DocumentTable`]. The folder stops being watched when this is dropped.
+    pub struct FolderWatcher {
+        _watcher: notify::RecommendedWatcher,
+        task: tokio::task::JoinHandle<()>,
+    }
+
+    impl Drop for FolderWatcher {
+        fn drop(&mut self) {
+            self.task.abort();
+        }
+    }
+
+    impl<C, R, M, K> DocumentTable<C, R, M, K>
+    where
+        C: Connection,
+        R: From<Document> + AsRef<Document> + Serialize + DeserializeOwned + Send + Sync + 'static,
+        M: Embedder,
+        K: Chunker + Send + Sync + 'static,
+    {
+        /// Sync the folder into the table with [`DocumentTable::sync_folder`], and then sync it again every time a file in the folder
+        /// changes until the returned [`FolderWatcher`] is dropped.
+        pub fn watch_folder(
+            self: &Arc<Self>,
+            folder: DocumentFolder,
+        ) -> anyhow::Result<FolderWatcher> {
+            let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+            let mut watcher =
+                notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                    if event.is_ok() {
+                        _ = sender.send(());
+                    }
+                })?;
+            watcher.watch(folder.path(), RecursiveMode::Recursive)?;
+
+            let table = self.clone();
+            let task = tokio::spawn(async move {
+                loop {
+                    if let Err(err) = table.sync_folder(&folder).await {
+                        tracing::error!("Failed to sync {}: {err}", folder.path().display());
+                    }
+
+                    // Wait for the next change
+                    if receiver.recv().await.is_none() {
+                        return;
+                    }
+                    // Then wait until there are no more changes for a moment
+                    loop {
+                        match tokio::time::timeout(DEBOUNCE, receiver.recv()).await {
+                            Ok(Some(())) => continue,
+                            Ok(None) => return,
+                            Err(_) => break,
+                        }
+                    }
+                }
+            });
+
+            Ok(FolderWatcher {
+                _watcher: watcher,
+                task,
+            })
+        }
+    }
+}
+
+#[tokio::test]
+async fn folder_sync_only_embeds_changed_files() {
+    use super::testing::{test_db, test_table, KeywordEmbedder};
+    use std::time::{Duration, SystemTime};
+    use surrealdb::engine::local::Db;
+
+    let (dir, db) = test_db().await;
+    let table = test_table(&dir, &db).await;
+    let documents = tempfile::tempdir().unwrap();
+    let write = |name: &str, contents: &str| {
+        let path = documents.path().join(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    };
+    async fn bodies(
+        table: &DocumentTable<Db, Document, KeywordEmbedder, ChunkStrategy>,
+    ) -> Vec<String> {
+        let mut bodies = table
+            .select_all()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|document| document.body().to_string())
+            .collect::<Vec<_>>();
+        bodies.sort();
+        bodies
+    }
+
+    let first = write("first.txt", "The first file.");
+    let second = write("second.txt", "The second file.");
+    let folder = DocumentFolder::new(documents.path()).unwrap();
+    let mut summary = table.sync_folder(&folder).await.unwrap();
+    summary.added.sort();
+    assert_eq!(summary.added, [first.clone(), second.clone()]);
+    assert_eq!(
+        bodies(&table).await,
+        ["The first file.", "The second file."]
+    );
+
+    // Nothing changed
+    let summary = table.sync_folder(&folder).await.unwrap();
+    assert_eq!(summary.unchanged, 2);
+    assert!(summary.added.is_empty() && summary.updated.is_empty());
+
+    // Modify one file, delete another and add a new one. The modification time is moved forward in case the file system has a coarse clock.
+    write("first.txt", "The first file was edited.");
+    std::fs::File::options()
+        .write(true)
+        .open(&first)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(10))
+        .unwrap();
+    std::fs::remove_file(&second).unwrap();
+    let third = write("third.txt", "The third file.");
+
+    let summary = table.sync_folder(&folder).await.unwrap();
+    assert_eq!(summary.added, [third]);
+    assert_eq!(summary.updated, [first]);
+    assert_eq!(summary.removed, [second]);
+    assert_eq!(summary.unchanged, 0);
+    assert!(summary.failed.is_empty());
+    assert_eq!(
+        bodies(&table).await,
+        ["The first file was edited.", "The third file."]
+    );
+
+    // The keyword index follows the synced files
+    let results = table.select_hybrid("second", 5, 0.0).await.unwrap();
+    assert!(results
+        .iter()
+        .all(|result| !result.text().contains("second")));
+}
+
+#[tokio::test]
+async fn folder_sync_splits_mailboxes_into_emails() {
+    use super::testing::{test_db, test_table};
+    use std::time::{Duration, SystemTime};
+
+    let (dir, db) = test_db().await;
+    let table = test_table(&dir, &db).await;
+    let documents = tempfile::tempdir().unwrap();
+    let mailbox = documents.path().join("inbox.mbox");
+    std::fs::write(
+        &mailbox,
+        "From alice@example.com Mon Jan  1 00:00:00 2024\nSubject: First\n\nHello\n\nFrom bob@example.com Tue Jan  2 00:00:00 2024\nSubject: Second\n\nGoodbye\n",
+    )
+    .unwrap();
+    let folder = DocumentFolder::new(documents.path()).unwrap();
+
+    // The mailbox is stored the same way add_context stores it: one document per email
+    let summary = table.sync_folder(&folder).await.unwrap();
+    assert_eq!(summary.added, [mailbox.clone()]);
+    assert_eq!(table.select_all().await.unwrap().len(), 2);
+
+    // Removing an email from the mailbox deletes its record
+    std::fs::write(
+        &mailbox,
+        "From alice@example.com Mon Jan  1 00:00:00 2024\nSubject: First\n\nHello again\n",
+    )
+    .unwrap();
+    std::fs::File::options()
+        .write(true)
+        .open(&mailbox)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(10))
+        .unwrap();
+    let summary = table.sync_folder(&folder).await.unwrap();
+    assert_eq!(summary.updated, [mailbox.clone()]);
+    let emails = table.select_all().await.unwrap();
+    assert_eq!(emails.len(), 1);
+    assert!(emails[0].body().contains("Hello again"));
+
+    std::fs::remove_file(&mailbox).unwrap();
+    let summary = table.sync_folder(&folder).await.unwrap();
+    assert_eq!(summary.removed, [mailbox]);
+    assert!(table.select_all().await.unwrap().is_empty());
+}
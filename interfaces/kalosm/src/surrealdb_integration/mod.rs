@@ -5,8 +5,19 @@ use std::ops::Range;
 use surrealdb::sql::{Id, Thing};
 use surrealdb::{Connection, Surreal};
 
+mod filter;
+pub use filter::*;
+
 #[cfg(feature = "language")]
 pub(crate) mod document_table;
+#[cfg(feature = "language")]
+pub(crate) mod folder_sync;
+#[cfg(feature = "language")]
+pub(crate) mod rag;
+#[cfg(feature = "language")]
+pub(crate) mod reembed;
+#[cfg(all(test, feature = "language"))]
+mod testing;
 
 /// A link between a document and an embedding.
 ///
@@ -17,6 +28,9 @@ pub struct DocumentLink {
     byte_range: std::ops::Range<usize>,
 }
 
+/// The byte range of each chunk in a record along with the embedding ids of that chunk.
+pub(crate) type ChunkEmbeddingIds = Vec<(Range<usize>, Vec<EmbeddingId>)>;
+
 /// An object with associated embedding ids.
 ///
 /// This type is stored in the [`EmbeddingIndexedTable::table`] table.
@@ -24,7 +38,13 @@ pub struct DocumentLink {
 pub struct ObjectWithEmbeddingIds<T> {
     #[serde(flatten)]
     object: T,
-    chunks: Vec<(Range<usize>, Vec<EmbeddingId>)>,
+    chunks: ChunkEmbeddingIds,
+}
+
+/// The embedding ids of the chunks of a record.
+#[derive(Deserialize)]
+struct RecordChunks {
+    chunks: ChunkEmbeddingIds,
 }
 
 /// A table in a surreal database with a primary key tied to an embedding in a vector database.
@@ -74,10 +94,7 @@ impl<C: Connection, R, S: VectorSpace> EmbeddingIndexedTable<C, R, S> {
                     let embedding = self.vector_db.get_embedding(embedding_id)?;
                     embeddings.push(embedding);
                 }
-                chunks.push(Chunk {
-                    byte_range,
-                    embeddings,
-                });
+                chunks.push(Chunk::new(byte_range, embeddings));
             }
             documents.push((embedding.object, chunks));
         }
@@ -92,46 +109,96 @@ impl<C: Connection, R, S: VectorSpace> EmbeddingIndexedTable<C, R, S> {
         chunks: impl IntoIterator<Item = Chunk<S>>,
         value: R,
     ) -> anyhow::Result<Id>
+    where
+        R: Serialize + DeserializeOwned,
+    {
+        let (id, _) = self.insert_returning_chunks(chunks, value).await?;
+        Ok(id)
+    }
+
+    /// Insert a new record into the table and return the embedding ids of each chunk.
+    pub(crate) async fn insert_returning_chunks(
+        &self,
+        chunks: impl IntoIterator<Item = Chunk<S>>,
+        value: R,
+    ) -> anyhow::Result<(Id, ChunkEmbeddingIds)>
     where
         R: Serialize + DeserializeOwned,
     {
         let id = Id::uuid();
 
-        let mut embedding_ids = Vec::new();
         let thing = Thing {
             tb: self.table.clone(),
             id: id.clone(),
         };
 
+        let embedding_ids = self.add_chunks(&id, chunks).await?;
+
+        self.db
+            .create::<Option<ObjectWithEmbeddingIds<R>>>(thing)
+            .content(ObjectWithEmbeddingIds {
+                object: value,
+                chunks: embedding_ids.clone(),
+            })
+            .await?;
+
+        Ok((id, embedding_ids))
+    }
+
+    /// Add the embeddings of the chunks to the vector database and link them to the document.
+    async fn add_chunks(
+        &self,
+        document_id: &Id,
+        chunks: impl IntoIterator<Item = Chunk<S>>,
+    ) -> anyhow::Result<ChunkEmbeddingIds> {
+        let mut embedding_ids = Vec::new();
+
         for chunk in chunks {
             let chunk_embedding_ids = self.vector_db.add_embeddings(chunk.embeddings)?;
             for embedding_id in &chunk_embedding_ids {
-                let byte_range = chunk.byte_range.clone();
-
-                let link = Thing {
-                    tb: self.table_links(),
-                    id: Id::Number(embedding_id.0 as i64),
-                };
-                self.db
-                    .create::<Option<DocumentLink>>(link)
-                    .content(DocumentLink {
-                        document_id: id.clone(),
-                        byte_range,
-                    })
+                self.link_embedding(*embedding_id, document_id, chunk.byte_range.clone())
                     .await?;
             }
             embedding_ids.push((chunk.byte_range.clone(), chunk_embedding_ids));
         }
 
+        Ok(embedding_ids)
+    }
+
+    /// Link an embedding to the chunk of the document it was created from.
+    async fn link_embedding(
+        &self,
+        embedding_id: EmbeddingId,
+        document_id: &Id,
+        byte_range: Range<usize>,
+    ) -> anyhow::Result<()> {
+        let link = Thing {
+            tb: self.table_links(),
+            id: Id::Number(embedding_id.0 as i64),
+        };
         self.db
-            .create::<Option<ObjectWithEmbeddingIds<R>>>(thing)
-            .content(ObjectWithEmbeddingIds {
-                object: value,
-                chunks: embedding_ids,
+            .create::<Option<DocumentLink>>(link)
+            .content(DocumentLink {
+                document_id: document_id.clone(),
+                byte_range,
             })
             .await?;
+        Ok(())
+    }
 
-        Ok(id)
+    /// Remove the links and embeddings of the chunks from the database.
+    async fn remove_chunks(&self, chunks: &ChunkEmbeddingIds) -> anyhow::Result<()> {
+        for id in chunks.iter().flat_map(|(_, ids)| ids.iter()).copied() {
+            let link = Thing {
+                tb: self.table_links(),
+                id: Id::Number(id.0 as i64),
+            };
+            self.db.delete::<Option<DocumentLink>>(link).await?;
+            // Then delete the embedding from the vector db
+            self.vector_db.remove_embedding(id)?;
+        }
+
+        Ok(())
     }
 
     /// Update a record in the table with the given embedding id.
@@ -148,6 +215,56 @@ impl<C: Connection, R, S: VectorSpace> EmbeddingIndexedTable<C, R, S> {
         Ok(old)
     }
 
+    /// Replace a record in the table and the embeddings of its chunks. Returns the old record if it existed.
+    pub async fn update_with_chunks(
+        &self,
+        id: Id,
+        chunks: impl IntoIterator<Item = Chunk<S>>,
+        value: R,
+    ) -> anyhow::Result<Option<R>>
+    where
+        R: Serialize + DeserializeOwned,
+    {
+        let old = self.update_returning_chunks(id, chunks, value).await?;
+        Ok(old.map(|(old, _)| old.object))
+    }
+
+    /// Replace a record in the table and the embeddings of its chunks. Returns the old record and the embedding ids of the new chunks if the record existed.
+    pub(crate) async fn update_returning_chunks(
+        &self,
+        id: Id,
+        chunks: impl IntoIterator<Item = Chunk<S>>,
+        value: R,
+    ) -> anyhow::Result<Option<(ObjectWithEmbeddingIds<R>, ChunkEmbeddingIds)>>
+    where
+        R: Serialize + DeserializeOwned,
+    {
+        let thing = Thing {
+            tb: self.table.clone(),
+            id: id.clone(),
+        };
+        let Some(old) = self
+            .db
+            .select::<Option<ObjectWithEmbeddingIds<R>>>(thing.clone())
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        self.remove_chunks(&old.chunks).await?;
+        let embedding_ids = self.add_chunks(&id, chunks).await?;
+
+        self.db
+            .update::<Option<ObjectWithEmbeddingIds<R>>>(thing)
+            .content(ObjectWithEmbeddingIds {
+                object: value,
+                chunks: embedding_ids.clone(),
+            })
+            .await?;
+
+        Ok(Some((old, embedding_ids)))
+    }
+
     /// Select a record from the table with the given embedding id.
     pub async fn select(&self, id: Id) -> anyhow::Result<R>
     where
@@ -166,6 +283,18 @@ impl<C: Connection, R, S: VectorSpace> EmbeddingIndexedTable<C, R, S> {
 
     /// Delete a record from the table with the given embedding id.
     pub async fn delete(&self, id: Id) -> anyhow::Result<Option<R>>
+    where
+        R: Serialize + DeserializeOwned,
+    {
+        let old = self.delete_returning_chunks(id).await?;
+        Ok(old.map(|old| old.object))
+    }
+
+    /// Delete a record from the table and return the record with the embedding ids of its chunks.
+    pub(crate) async fn delete_returning_chunks(
+        &self,
+        id: Id,
+    ) -> anyhow::Result<Option<ObjectWithEmbeddingIds<R>>>
     where
         R: Serialize + DeserializeOwned,
     {
@@ -179,30 +308,12 @@ impl<C: Connection, R, S: VectorSpace> EmbeddingIndexedTable<C, R, S> {
             .delete::<Option<ObjectWithEmbeddingIds<R>>>(thing)
             .await?;
 
-        if let Some(old) = old {
-            let ObjectWithEmbeddingIds {
-                object,
-                chunks: embedding_ids,
-            } = old;
-            // Then delete the links from the links table
-            for id in embedding_ids
-                .iter()
-                .flat_map(|(_, ids)| ids.iter())
-                .copied()
-            {
-                let link = Thing {
-                    tb: self.table_links(),
-                    id: Id::Number(id.0 as i64),
-                };
-                self.db.delete::<Option<DocumentLink>>(link).await?;
-                // Then delete the embedding from the vector db
-                self.vector_db.remove_embedding(id)?;
-            }
-
-            Ok(Some(object))
-        } else {
-            Ok(None)
+        if let Some(old) = &old {
+            // Then delete the links and embeddings
+            self.remove_chunks(&old.chunks).await?;
         }
+
+        Ok(old)
     }
 
     /// Select all records from the table.
@@ -214,6 +325,35 @@ impl<C: Connection, R, S: VectorSpace> EmbeddingIndexedTable<C, R, S> {
         Ok(records)
     }
 
+    /// Select all records from the table that match the filter.
+    pub async fn select_all_filtered(&self, filter: &RecordFilter) -> anyhow::Result<Vec<R>>
+    where
+        R: DeserializeOwned,
+    {
+        filter.select(&self.db, "*", &self.table).await
+    }
+
+    /// Select the top k chunks nearest to the given embedding out of the records that match the filter.
+    ///
+    /// The filter is resolved by the surreal database before the vector search, so only the embeddings of matching records are searched.
+    pub async fn select_nearest_filtered(
+        &self,
+        embedding: Embedding<S>,
+        k: usize,
+        filter: &RecordFilter,
+    ) -> anyhow::Result<Vec<EmbeddingIndexedTableSearchResult<R>>>
+    where
+        R: DeserializeOwned,
+    {
+        let matching: Vec<RecordChunks> = filter.select(&self.db, "chunks", &self.table).await?;
+        let candidates = matching
+            .into_iter()
+            .flat_map(|record| record.chunks)
+            .flat_map(|(_, ids)| ids);
+        let ids = self.vector_db.get_closest_among(embedding, k, candidates)?;
+        self.select_results(ids).await
+    }
+
     /// Select the top k records nearest records to the given embedding.
     pub async fn select_nearest(
         &self,
@@ -224,27 +364,76 @@ impl<C: Connection, R, S: VectorSpace> EmbeddingIndexedTable<C, R, S> {
         R: DeserializeOwned,
     {
         let ids = self.vector_db.get_closest(embedding, k)?;
+        self.select_results(ids).await
+    }
+
+    /// Select K records with chunks close to the embedding that are also different from each other with maximal marginal relevance (MMR).
+    ///
+    /// See [`VectorDB::get_closest_mmr`] for more information about `lambda` and `fetch_k`.
+    pub async fn select_nearest_mmr(
+        &self,
+        embedding: Embedding<S>,
+        k: usize,
+        lambda: f32,
+        fetch_k: usize,
+    ) -> anyhow::Result<Vec<EmbeddingIndexedTableSearchResult<R>>>
+    where
+        R: DeserializeOwned,
+    {
+        let ids = self
+            .vector_db
+            .get_closest_mmr(embedding, k, lambda, fetch_k)?;
+        self.select_results(ids).await
+    }
+
+    /// Select all records with chunks within the given distance of the embedding, sorted from closest to furthest.
+    pub async fn select_within_distance(
+        &self,
+        embedding: Embedding<S>,
+        max_distance: f32,
+    ) -> anyhow::Result<Vec<EmbeddingIndexedTableSearchResult<R>>>
+    where
+        R: DeserializeOwned,
+    {
+        let ids = self
+            .vector_db
+            .get_within_distance(embedding, max_distance)?;
+        self.select_results(ids).await
+    }
+
+    /// Resolve the records of the results of a search in the vector database.
+    async fn select_results(
+        &self,
+        ids: Vec<VectorDBSearchResult>,
+    ) -> anyhow::Result<Vec<EmbeddingIndexedTableSearchResult<R>>>
+    where
+        R: DeserializeOwned,
+    {
         let mut records = Vec::new();
         for id in ids {
-            let main_table_id = self
-                .db
-                .select::<Option<DocumentLink>>(Thing {
-                    tb: self.table_links(),
-                    id: Id::Number(id.value.0 as i64),
-                })
-                .await?
-                .ok_or_else(|| anyhow::anyhow!("Record not found"))?;
-            let record = self.select(main_table_id.document_id.clone()).await?;
+            let link = self.select_link(id.value).await?;
+            let record = self.select(link.document_id.clone()).await?;
             records.push(EmbeddingIndexedTableSearchResult {
                 distance: id.distance,
                 id: id.value,
-                record_id: main_table_id.document_id,
-                byte_range: main_table_id.byte_range,
+                record_id: link.document_id,
+                byte_range: link.byte_range,
                 record,
             });
         }
         Ok(records)
     }
+
+    /// Select the link between an embedding id and the chunk of the record it was created from.
+    pub(crate) async fn select_link(&self, id: EmbeddingId) -> anyhow::Result<DocumentLink> {
+        self.db
+            .select::<Option<DocumentLink>>(Thing {
+                tb: self.table_links(),
+                id: Id::Number(id.0 as i64),
+            })
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Record not found"))
+    }
 }
 
 /// The result of a search in an embedding indexed table.
@@ -273,6 +462,14 @@ where
     {
         self.record.as_ref().body()[self.byte_range.clone()].to_string()
     }
+
+    /// Get where the text of the search result came from in the source of the document, like the pdf page or markdown section.
+    pub fn provenance(&self) -> Vec<Provenance>
+    where
+        R: AsRef<Document>,
+    {
+        self.record.as_ref().provenance(self.byte_range.clone())
+    }
 }
 
 /// A builder for creating a new document table.
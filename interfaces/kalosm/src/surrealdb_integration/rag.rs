@@ -0,0 +1,410 @@
+use std::future::Future;
+use std::ops::Range;
+use std::pin::Pin;
+
+use kalosm_language::prelude::*;
+use serde::de::DeserializeOwned;
+use surrealdb::sql::Id;
+use surrealdb::Connection;
+
+use super::document_table::DocumentTable;
+
+/// The default prompt template of a [`RagPipeline`].
+const DEFAULT_PROMPT_TEMPLATE: &str = "Answer the question using only the numbered sources below. Cite the sources that support each sentence with their number in brackets, like [1]. If the sources do not contain the answer, answer \"I don't know.\"\n\nSources:\n{context}\nQuestion: {question}\nAnswer: ";
+
+/// The answer given when the sources do not contain the answer to a question.
+const NO_ANSWER: &str = "I don't know.";
+
+/// The maximum number of characters of the text of all sentences in an answer. The answer regex is compiled into a DFA that grows with the
+/// number of characters it counts, so the length of each sentence shrinks as the number of sentences grows.
+const MAX_ANSWER_CHARS: usize = 2400;
+
+/// The maximum number of characters of the text of a single sentence in an answer.
+const MAX_SENTENCE_CHARS: usize = 300;
+
+/// The minimum number of characters a sentence in an answer is allowed to have, which bounds the number of sentences.
+const MIN_SENTENCE_CHARS: usize = 40;
+
+/// A chunk of a document retrieved by a [`RagPipeline`] to answer a question.
+#[derive(Debug, Clone)]
+pub struct RagSource {
+    /// The id of the record the chunk is from.
+    pub record_id: Id,
+    /// The title of the document the chunk is from.
+    pub title: String,
+    /// The text of the chunk.
+    pub text: String,
+    /// The byte range of the chunk in the body of the document.
+    pub byte_range: Range<usize>,
+    /// Where the chunk came from in the source of the document, like the pdf page or markdown section.
+    pub provenance: Vec<Provenance>,
+    /// The distance between the chunk and the question.
+    pub distance: f32,
+}
+
+/// A source cited in the answer of a [`RagPipeline`].
+#[derive(Debug, Clone)]
+pub struct RagCitation {
+    /// The number the source was cited with in the answer.
+    pub number: usize,
+    /// The source that was cited.
+    pub source: RagSource,
+    /// The byte ranges of the bracketed citations of the source in the answer.
+    pub mentions: Vec<Range<usize>>,
+}
+
+/// An answer generated by a [`RagPipeline`].
+#[derive(Debug, Clone)]
+pub struct RagAnswer {
+    /// The text of the answer including the bracketed citations.
+    pub answer: String,
+    /// The sources cited in the answer, ordered by their number.
+    pub citations: Vec<RagCitation>,
+    /// All of the sources that were given to the model as context, ordered by their number.
+    pub sources: Vec<RagSource>,
+}
+
+/// Reorders or filters the sources retrieved by a [`RagPipeline`] before they are added to the prompt.
+///
+/// Any function or closure with the signature `Fn(&str, Vec<RagSource>) -> Vec<RagSource>` can be used as a reranker.
+pub trait Reranker: Send + Sync + 'static {
+    /// Rerank the sources retrieved for the question. The sources are ordered from most to least relevant.
+    fn rerank<'a>(
+        &'a self,
+        question: &'a str,
+        sources: Vec<RagSource>,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<Vec<RagSource>>> + Send + 'a>>;
+}
+
+impl<F> Reranker for F
+where
+    F: Fn(&str, Vec<RagSource>) -> Vec<RagSource> + Send + Sync + 'static,
+{
+    fn rerank<'a>(
+        &'a self,
+        question: &'a str,
+        sources: Vec<RagSource>,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<Vec<RagSource>>> + Send + 'a>> {
+        let sources = self(question, sources);
+        Box::pin(async move { Ok(sources) })
+    }
+}
+
+/// A retrieval augmented generation pipeline that answers questions with citations from the documents in a [`DocumentTable`].
+///
+/// For each question, the pipeline:
+/// 1. Retrieves the chunks nearest to the question from the document table
+/// 2. Reranks the chunks if a [`Reranker`] is set
+/// 3. Adds as many chunks as fit in the context token budget to the prompt as numbered sources
+/// 4. Generates an answer that is constrained to cite the sources with bracketed numbers like `[1]`
+///
+/// # Example
+/// ```rust, no_run
+/// use kalosm::language::*;
+/// use surrealdb::{engine::local::RocksDb, Surreal};
+///
+/// #[tokio::main]
+/// async fn main() -> anyhow::Result<()> {
+///     let db = Surreal::new::<RocksDb>("./db/temp.db").await?;
+///     db.use_ns("rag").use_db("rag").await?;
+///     let document_table = db
+///         .document_table_builder("documents")
+///         .at("./db/embeddings.db")
+///         .build::<Document>()
+///         .await?;
+///
+///     let model = Llama::new().await?;
+///     let pipeline = RagPipeline::new(document_table, model).with_context_tokens(1024);
+///     let answer = pipeline.answer("What is Kalosm?").await?;
+///     println!("{}", answer.answer);
+///     for citation in answer.citations {
+///         let source = citation.source;
+///         println!("[{}] {} ({:?})", citation.number, source.title, source.byte_range);
+///     }
+///     Ok(())
+/// }
+/// ```
+pub struct RagPipeline<C: Connection, R, M: Embedder, K: Chunker, L: Model> {
+    table: DocumentTable<C, R, M, K>,
+    model: L,
+    reranker: Option<Box<dyn Reranker>>,
+    top_k: usize,
+    context_tokens: usize,
+    max_sentences: usize,
+    prompt_template: String,
+}
+
+impl<C, R, M, K, L> RagPipeline<C, R, M, K, L>
+where
+    C: Connection,
+    R: AsRef<Document> + DeserializeOwned,
+    M: Embedder,
+    K: Chunker,
+    L: Model,
+    L::TextStream: From<tokio::sync::mpsc::UnboundedReceiver<String>>,
+{
+    /// Create a new pipeline that answers questions about the documents in the table with the model.
+    pub fn new(table: DocumentTable<C, R, M, K>, model: L) -> Self {
+        Self {
+            table,
+            model,
+            reranker: None,
+            top_k: 8,
+            context_tokens: 2048,
+            max_sentences: 8,
+            prompt_template: DEFAULT_PROMPT_TEMPLATE.to_string(),
+        }
+    }
+
+    /// Set the reranker that reorders the retrieved chunks before they are added to the prompt.
+    pub fn with_reranker(mut self, reranker: impl Reranker) -> Self {
+        self.reranker = Some(Box::new(reranker));
+        self
+    }
+
+    /// Set the number of chunks retrieved from the table for each question. Defaults to 8.
+    pub fn with_top_k(mut self, top_k: usize) -> Self {
+        self.top_k = top_k;
+        self
+    }
+
+    /// Set the maximum number of tokens of the sources added to the prompt. Defaults to 2048.
+    pub fn with_context_tokens(mut self, context_tokens: usize) -> Self {
+        self.context_tokens = context_tokens;
+        self
+    }
+
+    /// Set the maximum number of sentences in an answer. Defaults to 8.
+    ///
+    /// The total length of the answer is bounded, so sentences get shorter as the number of sentences grows. At most 60 sentences are allowed.
+    pub fn with_max_sentences(mut self, max_sentences: usize) -> Self {
+        self.max_sentences = max_sentences.clamp(1, MAX_ANSWER_CHARS / MIN_SENTENCE_CHARS);
+        self
+    }
+
+    /// Set the prompt template. `{context}` is replaced with the numbered sources and `{question}` is replaced with the question.
+    pub fn with_prompt_template(mut self, prompt_template: impl ToString) -> Self {
+        self.prompt_template = prompt_template.to_string();
+        self
+    }
+
+    /// Get the document table the pipeline retrieves sources from.
+    pub fn table(&self) -> &DocumentTable<C, R, M, K> {
+        &self.table
+    }
+
+    /// Get the model the pipeline generates answers with.
+    pub fn model(&self) -> &L {
+        &self.model
+    }
+
+    /// Retrieve the sources for a question that fit in the context token budget.
+    pub async fn retrieve(&self, question: &str) -> anyhow::Result<Vec<RagSource>> {
+        let embedding = self.table.embedding_model().embed_query(question).await?;
+        let mut sources: Vec<_> = self
+            .table
+            .select_nearest(embedding, self.top_k)
+            .await?
+            .into_iter()
+            .map(|result| RagSource {
+                title: result.record.as_ref().title().to_string(),
+                text: result.text(),
+                provenance: result.provenance(),
+                record_id: result.record_id,
+                byte_range: result.byte_range,
+                distance: result.distance,
+            })
+            .collect();
+
+        if let Some(reranker) = &self.reranker {
+            sources = reranker.rerank(question, sources).await?;
+        }
+
+        let tokenizer = self.model.tokenizer();
+        let count_tokens = |text: &str| {
+            tokenizer
+                .encode(text, false)
+                .map(|encoding| encoding.len())
+                .map_err(|err| anyhow::anyhow!(err))
+        };
+        let mut remaining_tokens = self.context_tokens;
+        let mut fitting = Vec::with_capacity(sources.len());
+        for source in sources {
+            let tokens = count_tokens(&format_source(fitting.len() + 1, &source))?;
+            if tokens > remaining_tokens {
+                break;
+            }
+            remaining_tokens -= tokens;
+            fitting.push(source);
+        }
+
+        Ok(fitting)
+    }
+
+    /// Answer a question with citations to the documents in the table.
+    pub async fn answer(&self, question: &str) -> anyhow::Result<RagAnswer> {
+        let sources = self.retrieve(question).await?;
+        if sources.is_empty() {
+            return Ok(RagAnswer {
+                answer: NO_ANSWER.to_string(),
+                citations: Vec::new(),
+                sources,
+            });
+        }
+
+        let context = sources
+            .iter()
+            .enumerate()
+            .map(|(index, source)| format_source(index + 1, source))
+            .collect::<String>();
+        let prompt = self
+            .prompt_template
+            .replace("{context}", &context)
+            .replace("{question}", question);
+
+        let parser = RegexParser::new(&answer_regex(sources.len(), self.max_sentences))?;
+        let answer = self.model.stream_structured_text(&prompt, parser).await?;
+        let answer = answer.trim_end().to_string();
+
+        let citations = cite_sources(&answer, &sources);
+
+        Ok(RagAnswer {
+            answer,
+            citations,
+            sources,
+        })
+    }
+}
+
+/// Format a source as it is shown to the model.
+fn format_source(number: usize, source: &RagSource) -> String {
+    let provenance = source
+        .provenance
+        .iter()
+        .map(|provenance| provenance.to_string())
+        .filter(|provenance| !provenance.is_empty())
+        .collect::<Vec<_>>();
+    let title = if provenance.is_empty() {
+        source.title.clone()
+    } else {
+        format!("{} ({})", source.title, provenance.join("; "))
+    };
+    format!("[{number}] {title}\n{}\n\n", source.text.trim())
+}
+
+/// A regex for an answer where every sentence cites at least one of the sources.
+fn answer_regex(sources: usize, max_sentences: usize) -> String {
+    let max_sentences = max_sentences.clamp(1, MAX_ANSWER_CHARS / MIN_SENTENCE_CHARS);
+    let sentence_chars = (MAX_ANSWER_CHARS / max_sentences).min(MAX_SENTENCE_CHARS);
+    let numbers = (1..=sources)
+        .map(|number| number.to_string())
+        .collect::<Vec<_>>()
+        .join("|");
+    let sentence = format!(r"[^\[\]\n]{{1,{sentence_chars}}}( ?\[({numbers})\])+[.!?]?");
+    format!(
+        r"({}|{sentence}( {sentence}){{0,{}}})\n",
+        NO_ANSWER.replace('.', r"\."),
+        max_sentences - 1
+    )
+}
+
+/// Find the bracketed citations in the answer and map them back to the sources.
+fn cite_sources(answer: &str, sources: &[RagSource]) -> Vec<RagCitation> {
+    let mut citations: Vec<RagCitation> = Vec::new();
+    let mut search_start = 0;
+    while let Some(open) = answer[search_start..].find('[') {
+        let open = search_start + open;
+        let Some(close) = answer[open..].find(']').map(|close| open + close) else {
+            break;
+        };
+        search_start = open + 1;
+        let Ok(number) = answer[open + 1..close].parse::<usize>() else {
+            continue;
+        };
+        let Some(source) = number.checked_sub(1).and_then(|index| sources.get(index)) else {
+            continue;
+        };
+        let mention = open..close + 1;
+        match citations
+            .iter_mut()
+            .find(|citation| citation.number == number)
+        {
+            Some(citation) => citation.mentions.push(mention),
+            None => citations.push(RagCitation {
+                number,
+                source: source.clone(),
+                mentions: vec![mention],
+            }),
+        }
+    }
+    citations.sort_by_key(|citation| citation.number);
+    citations
+}
+
+#[cfg(test)]
+fn source(title: &str) -> RagSource {
+    RagSource {
+        record_id: Id::String(title.to_lowercase()),
+        title: title.to_string(),
+        text: format!("{title} is a project."),
+        byte_range: 0..title.len() + 12,
+        provenance: Vec::new(),
+        distance: 0.0,
+    }
+}
+
+#[test]
+fn answers_must_cite_the_sources() {
+    use kalosm_language::kalosm_sample::{ParseStatus, Parser};
+
+    let parses = |regex: &str, answer: &str| {
+        let parser = RegexParser::new(regex).unwrap();
+        matches!(
+            parser.parse(&parser.create_parser_state(), answer.as_bytes()),
+            Ok(ParseStatus::Finished { .. })
+        )
+    };
+
+    let regex = answer_regex(2, 2);
+    assert!(parses(&regex, "Kalosm is a library [1].\n"));
+    assert!(parses(
+        &regex,
+        "Kalosm is a library [1][2]. Floneum is an editor [2].\n"
+    ));
+    assert!(parses(&regex, "I don't know.\n"));
+    // Every sentence needs a citation of a source that exists
+    assert!(!parses(&regex, "Kalosm is a library.\n"));
+    assert!(!parses(&regex, "Kalosm is a library [3].\n"));
+    // The number of sentences is limited
+    assert!(!parses(&regex, "One [1]. Two [1]. Three [1].\n"));
+
+    // Sentences get shorter as the number of sentences grows, so the regex stays small
+    let long_sentence = format!("{} [1].\n", "word ".repeat(60));
+    assert!(parses(&answer_regex(2, 1), &long_sentence));
+    assert!(!parses(&answer_regex(2, 60), &long_sentence));
+    assert_eq!(answer_regex(2, 1000), answer_regex(2, 60));
+}
+
+#[test]
+fn citations_are_mapped_to_sources() {
+    let sources = [source("Kalosm"), source("Floneum")];
+    let answer =
+        "Kalosm is a library [1]. Floneum uses Kalosm [2][1]. Nothing cites [3] or [x] or [";
+    let citations = cite_sources(answer, &sources);
+
+    assert_eq!(citations.len(), 2);
+    assert_eq!(citations[0].number, 1);
+    assert_eq!(citations[0].source.title, "Kalosm");
+    let mentions = citations[0]
+        .mentions
+        .iter()
+        .map(|mention| &answer[mention.clone()])
+        .collect::<Vec<_>>();
+    assert_eq!(mentions, ["[1]", "[1]"]);
+    assert_eq!(citations[0].mentions[0], 20..23);
+    assert_eq!(citations[1].number, 2);
+    assert_eq!(citations[1].source.title, "Floneum");
+    assert_eq!(citations[1].mentions.len(), 1);
+
+    assert!(cite_sources("I don't know.", &sources).is_empty());
+}
@@ -0,0 +1,251 @@
+use std::path::Path;
+
+use kalosm_language::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::{Id, Thing};
+use surrealdb::Connection;
+
+use super::document_table::{DocumentTable, EmbeddingModelMetadata};
+use super::{DocumentLink, EmbeddingIndexedTable, ObjectWithEmbeddingIds};
+
+/// A record in an [`EmbeddingIndexedTable`] along with its id.
+#[derive(Deserialize)]
+struct StoredRecord<R> {
+    id: Thing,
+    #[serde(flatten)]
+    record: ObjectWithEmbeddingIds<R>,
+}
+
+/// A record with the embedding ids of its chunks in the new vector database.
+#[derive(Serialize)]
+struct MigratedRecord<R> {
+    id: Thing,
+    record: ObjectWithEmbeddingIds<R>,
+}
+
+/// Replace the links of the table with the links in the staging table, point the records at the new embeddings and store the new model in one transaction,
+/// so queries either see the old index or the new index.
+const SWAP_LINKS_QUERY: &str = "
+BEGIN TRANSACTION;
+DELETE type::table($links);
+FOR $link IN (SELECT * FROM type::table($staging)) {
+    CREATE type::thing($links, meta::id($link.id)) CONTENT {
+        document_id: $link.document_id,
+        byte_range: $link.byte_range
+    };
+};
+FOR $migrated IN $records {
+    UPDATE $migrated.id CONTENT $migrated.record;
+};
+DELETE type::table($staging);
+DELETE type::table($metadata_table);
+IF $model {
+    CREATE $metadata CONTENT $model;
+};
+COMMIT TRANSACTION;
+";
+
+impl<C: Connection, R, M: Embedder, K: Chunker> DocumentTable<C, R, M, K> {
+    /// Embed every chunk in the table again with a new embedding model and return a table that uses the new model.
+    ///
+    /// The documents are not chunked again. The text of each chunk is read from the stored record with the byte range of the chunk,
+    /// and embedded once with the new model. The new embeddings are stored in a new vector database at `location`, which must be empty.
+    ///
+    /// This table keeps serving queries from the old vector database while the new one is built, so the migration can run in the
+    /// background. Writes to this table wait until the migration is done. The links to the new embeddings are written to the `{table}-links-reembed` table first.
+    /// Once every chunk is embedded, they replace the links of the table in a single transaction. After that, this table returns an error for every query and write, and only the returned table can be used.
+    ///
+    /// # Example
+    /// ```rust, no_run
+    /// use kalosm::language::*;
+    /// use std::sync::Arc;
+    /// use surrealdb::{engine::local::RocksDb, Surreal};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let db = Surreal::new::<RocksDb>("./db/temp.db").await?;
+    ///     db.use_ns("test").use_db("test").await?;
+    ///     let document_table = Arc::new(
+    ///         db.document_table_builder("documents")
+    ///             .at("./db/embeddings.db")
+    ///             .build::<Document>()
+    ///             .await?,
+    ///     );
+    ///
+    ///     // Build the new index in the background
+    ///     let new_model = Bert::builder()
+    ///         .with_source(BertSource::bge_small_en())
+    ///         .build()
+    ///         .await?;
+    ///     let migration = tokio::spawn({
+    ///         let document_table = document_table.clone();
+    ///         async move {
+    ///             document_table
+    ///                 .reembed_all(new_model, "./db/embeddings-bge.db")
+    ///                 .await
+    ///         }
+    ///     });
+    ///
+    ///     // The old table keeps answering queries in the meantime
+    ///     let nearest = document_table.select_nearest("What is Kalosm?", 5).await?;
+    ///     println!("{:?}", nearest);
+    ///
+    ///     let document_table = migration.await??;
+    ///     let nearest = document_table.select_nearest("What is Kalosm?", 5).await?;
+    ///     println!("{:?}", nearest);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn reembed_all<M2: Embedder>(
+        &self,
+        new_model: M2,
+        location: impl AsRef<Path>,
+    ) -> anyhow::Result<DocumentTable<C, R, M2, K>>
+    where
+        R: AsRef<Document> + Serialize + DeserializeOwned,
+        K: Clone,
+    {
+        // Hold off writes until the records point at the new embeddings, so no record is left out of the migration
+        let _migrating = self.start_migration().await?;
+
+        let location = location.as_ref();
+        if location.exists() && std::fs::read_dir(location)?.next().is_some() {
+            anyhow::bail!(
+                "Cannot re-embed the table into {} because the folder is not empty",
+                location.display()
+            );
+        }
+        let vector_db = VectorDB::builder()
+            .at(location)
+            .with_distance(self.table().vector_db().distance())
+            .build()?;
+        let table = EmbeddingIndexedTable {
+            table: self.table().table().to_string(),
+            db: self.table().db().clone(),
+            vector_db,
+            phantom: std::marker::PhantomData,
+        };
+        let new_table = DocumentTable::new(new_model, table, self.chunker().clone())?;
+
+        // Links left behind by a migration that failed are not valid for the new vector database
+        let staging = format!("{}-reembed", self.table().table_links());
+        let _: Vec<DocumentLink> = self.table().db().delete(&staging).await?;
+
+        // Embed every chunk with the new model while the old vector database keeps serving queries
+        let records: Vec<StoredRecord<R>> = self.table().db().select(self.table().table()).await?;
+        let mut migrated = Vec::with_capacity(records.len());
+        let mut dimensions = None;
+        for StoredRecord { id, record } in records {
+            let body = record.object.as_ref().body();
+            let inputs = record.chunks.iter().map(|(byte_range, _)| {
+                EmbeddingInput::new(&body[byte_range.clone()], EmbeddingVariant::Document)
+            });
+            let embeddings = new_table.embedding_model().embed_batch_for(inputs).await?;
+            let mut chunks = Vec::with_capacity(embeddings.len());
+            for ((byte_range, _), embedding) in record.chunks.iter().zip(embeddings) {
+                dimensions.get_or_insert(embedding.vector().elem_count());
+                let embedding_id = new_table.table().vector_db().add_embedding(embedding)?;
+                self.table()
+                    .db()
+                    .create::<Option<DocumentLink>>(Thing {
+                        tb: staging.clone(),
+                        id: Id::Number(embedding_id.0 as i64),
+                    })
+                    .content(DocumentLink {
+                        document_id: id.id.clone(),
+                        byte_range: byte_range.clone(),
+                    })
+                    .await?;
+                chunks.push((byte_range.clone(), vec![embedding_id]));
+            }
+            new_table.index_keywords(body, &chunks)?;
+            migrated.push(MigratedRecord {
+                id,
+                record: ObjectWithEmbeddingIds {
+                    object: record.object,
+                    chunks,
+                },
+            });
+        }
+
+        // Then swap in the new links, point the records at the embeddings in the new vector database and store the new model
+        let model = dimensions.map(|dimensions| EmbeddingModelMetadata {
+            model_id: new_table.embedding_model().model_id(),
+            dimensions,
+        });
+        self.table()
+            .db()
+            .query(SWAP_LINKS_QUERY)
+            .bind(("links", self.table().table_links()))
+            .bind(("staging", staging))
+            .bind(("records", migrated))
+            .bind(("metadata_table", self.metadata_table()))
+            .bind(("metadata", new_table.model_metadata_record()))
+            .bind(("model", model))
+            .await?
+            .check()?;
+        self.retire();
+
+        Ok(new_table)
+    }
+}
+
+#[tokio::test]
+async fn reembedding_swaps_in_the_new_links() {
+    use super::testing::{test_db, test_documents, test_table, KeywordEmbedder};
+
+    let (dir, db) = test_db().await;
+    let table = test_table(&dir, &db).await;
+    table.extend(test_documents()).await.unwrap();
+    let links: Vec<DocumentLink> = db.select(table.table().table_links()).await.unwrap();
+
+    let new_table = table
+        .reembed_all(KeywordEmbedder, dir.path().join("reembedded.db"))
+        .await
+        .unwrap();
+    let results = new_table.select_nearest("borrow checker", 1).await.unwrap();
+    assert_eq!(results[0].text(), "The borrow checker prevents data races.");
+    let results = new_table
+        .select_hybrid("mail carrier", 1, 0.0)
+        .await
+        .unwrap();
+    assert_eq!(
+        results[0].text(),
+        "Dogs chase the mail carrier every morning."
+    );
+
+    // Every chunk is linked to one new embedding and the staging table is removed
+    let new_links: Vec<DocumentLink> = db.select(new_table.table().table_links()).await.unwrap();
+    assert_eq!(new_links.len(), links.len());
+    let staging: Vec<DocumentLink> = db.select("documents-links-reembed").await.unwrap();
+    assert!(staging.is_empty());
+}
+
+#[tokio::test]
+async fn writes_wait_for_reembedding_and_then_fail() {
+    use super::testing::{test_db, test_documents, test_table, KeywordEmbedder};
+
+    let (dir, db) = test_db().await;
+    let table = test_table(&dir, &db).await;
+    table.extend(test_documents()).await.unwrap();
+    let records = table.select_all().await.unwrap().len();
+
+    // The insert starts after the migration, so it waits for the migration to finish instead of writing to the old vector database
+    let (new_table, inserted) = tokio::join!(
+        table.reembed_all(KeywordEmbedder, dir.path().join("reembedded.db")),
+        table.insert(Document::from_parts("Late", "Cats sleep most of the day."))
+    );
+    let new_table = new_table.unwrap();
+    assert!(inserted.is_err());
+    assert_eq!(new_table.select_all().await.unwrap().len(), records);
+
+    // The old table is retired, but the new table accepts writes
+    assert!(table.select_nearest("borrow checker", 1).await.is_err());
+    new_table
+        .insert(Document::from_parts("Late", "Cats sleep most of the day."))
+        .await
+        .unwrap();
+    let results = new_table.select_nearest("cats sleep", 1).await.unwrap();
+    assert_eq!(results[0].text(), "Cats sleep most of the day.");
+}
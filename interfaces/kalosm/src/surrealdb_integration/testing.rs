@@ -0,0 +1,85 @@
+//! Helpers for testing document tables without downloading an embedding model.
+
+use super::document_table::DocumentTable;
+use super::VectorDbSurrealExt;
+use kalosm_common::BoxedFuture;
+use kalosm_language::prelude::*;
+use surrealdb::engine::local::{Db, RocksDb};
+use surrealdb::Surreal;
+
+/// The number of dimensions of the embeddings from [`KeywordEmbedder`].
+const DIMENSIONS: usize = 64;
+
+/// An embedder that hashes the lowercase words of the text into a bag of words vector. Texts that share words are close to each other.
+pub(crate) struct KeywordEmbedder;
+
+impl Embedder for KeywordEmbedder {
+    type VectorSpace = UnknownVectorSpace;
+
+    fn count_tokens(&self, text: &str) -> Option<usize> {
+        Some(text.split_whitespace().count())
+    }
+
+    fn embed_for(
+        &self,
+        input: EmbeddingInput,
+    ) -> BoxedFuture<'_, anyhow::Result<Embedding<Self::VectorSpace>>> {
+        Box::pin(async move {
+            let mut vector = vec![0.0f32; DIMENSIONS];
+            for word in input
+                .text
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|word| !word.is_empty())
+            {
+                let hash = word.to_lowercase().bytes().fold(0usize, |hash, byte| {
+                    hash.wrapping_mul(31).wrapping_add(byte as usize)
+                });
+                vector[hash % DIMENSIONS] += 1.0;
+            }
+            // Keep empty texts away from the origin so the vector can be normalized
+            vector[0] += 0.01;
+            Ok(Embedding::from(vector))
+        })
+    }
+}
+
+/// A surreal database in a temporary directory. The directory is deleted when the returned [`tempfile::TempDir`] is dropped.
+pub(crate) async fn test_db() -> (tempfile::TempDir, Surreal<Db>) {
+    let dir = tempfile::tempdir().unwrap();
+    let db = Surreal::new::<RocksDb>(dir.path().join("surreal.db"))
+        .await
+        .unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    (dir, db)
+}
+
+/// Open the `documents` table in a database with one sentence per chunk. The vector database is stored in the directory, so the table can be opened again.
+pub(crate) async fn test_table(
+    dir: &tempfile::TempDir,
+    db: &Surreal<Db>,
+) -> DocumentTable<Db, Document, KeywordEmbedder, ChunkStrategy> {
+    let table = db
+        .vector_indexed_table_builder("documents")
+        .at(dir.path().join("embeddings.db"))
+        .build()
+        .unwrap();
+    let chunker = ChunkStrategy::Sentence {
+        sentence_count: 1,
+        overlap: 0,
+    };
+    DocumentTable::new(KeywordEmbedder, table, chunker).unwrap()
+}
+
+/// A few short documents with one topic per sentence.
+pub(crate) fn test_documents() -> Vec<Document> {
+    vec![
+        Document::from_parts(
+            "Pets",
+            "The cat sat on the mat. Dogs chase the mail carrier every morning.",
+        ),
+        Document::from_parts(
+            "Programming",
+            "Rust is a systems programming language. The borrow checker prevents data races.",
+        ),
+    ]
+}
@@ -1,55 +1,313 @@
-// use kalosm_language_model::{Embedder, VectorSpace};
-
-// use crate::{
-//     prelude::{DocumentDatabase, SearchIndex},
-//     tool::Tool,
-// };
-
-// use super::OneLine;
-
-// /// A tool that can search the web
-// pub struct DocumentSearchTool<S: VectorSpace + Send + Sync + 'static, M: Embedder<S>> {
-//     database: DocumentDatabase<S, M>,
-//     top_n: usize,
-// }
-
-// impl<S: VectorSpace + Send + Sync + 'static, M: Embedder<S>> DocumentSearchTool<S, M> {
-//     /// Create a new web search tool
-//     pub fn new(database: DocumentDatabase<S, M>, top_n: usize) -> Self {
-//         Self { database, top_n }
-//     }
-// }
-
-// #[async_trait::async_trait]
-// impl<S: VectorSpace + Send + Sync + 'static, M: Embedder<S>> Tool for DocumentSearchTool<S, M> {
-//     type Constraint = OneLine;
-
-//     fn constraints(&self) -> Self::Constraint {
-//         OneLine
-//     }
-
-//     fn name(&self) -> String {
-//         "Local Search".to_string()
-//     }
-
-//     fn input_prompt(&self) -> String {
-//         "Search query: ".to_string()
-//     }
-
-//     fn description(&self) -> String {
-//         "Search local documents for a query.\nUse tool with:\nAction: Local Search\nSearch query: the search query\nExample:\n\nQuestion: What is Floneum?\nThought: I don't remember what Floneum is. I should search for it.\nAction: Local Search\nAction Input: What is Floneum?\nObservation: Floneum is a visual editor for AI workflows.\nThought: I now know that Floneum is a visual editor for AI workflows.\nFinal Answer: Floneum is a visual editor for AI workflows.".to_string()
-//     }
-
-//     async fn run(&mut self, query: String) -> String {
-//         let documents = self.database.search(&query, self.top_n).await;
-//         let mut text = String::new();
-//         for document in documents {
-//             for word in document.body().split(' ').take(300) {
-//                 text.push_str(word);
-//                 text.push(' ');
-//             }
-//             text.push('\n');
-//         }
-//         text
-//     }
-// }
+use std::future::Future;
+
+use kalosm_language::prelude::*;
+use serde::de::DeserializeOwned;
+use surrealdb::Connection;
+
+use crate::surrealdb_integration::document_table::DocumentTable;
+use crate::EmbeddingIndexedTable;
+
+/// A chunk of a document found by a [`DocumentSearchSource`].
+#[derive(Debug, Clone)]
+pub struct DocumentSearchChunk {
+    /// The title of the document the chunk is from.
+    pub title: String,
+    /// The text of the chunk.
+    pub text: String,
+    /// The distance between the chunk and the query.
+    pub distance: f32,
+}
+
+/// A collection of documents that a [`DocumentSearchTool`] can search.
+pub trait DocumentSearchSource: Send + Sync + 'static {
+    /// Find the `k` chunks closest to the query.
+    fn search_chunks(
+        &self,
+        query: &str,
+        k: usize,
+    ) -> impl Future<Output = anyhow::Result<Vec<DocumentSearchChunk>>> + Send;
+}
+
+impl<C, R, M, K> DocumentSearchSource for DocumentTable<C, R, M, K>
+where
+    C: Connection,
+    R: AsRef<Document> + DeserializeOwned + Send + Sync + 'static,
+    M: Embedder,
+    K: Chunker + Send + Sync + 'static,
+{
+    async fn search_chunks(
+        &self,
+        query: &str,
+        k: usize,
+    ) -> anyhow::Result<Vec<DocumentSearchChunk>> {
+        let embedding = self.embedding_model().embed_query(query).await?;
+        let results = self.select_nearest(embedding, k).await?;
+        Ok(results
+            .into_iter()
+            .map(|result| DocumentSearchChunk {
+                title: result.record.as_ref().title().to_string(),
+                text: result.text(),
+                distance: result.distance,
+            })
+            .collect())
+    }
+}
+
+/// An [`EmbeddingIndexedTable`] with the embedding model that was used to create the embeddings in the table.
+pub struct EmbeddingIndexedTableSource<C: Connection, R, M: Embedder> {
+    table: EmbeddingIndexedTable<C, R, M::VectorSpace>,
+    embedding_model: M,
+}
+
+impl<C: Connection, R, M: Embedder> EmbeddingIndexedTableSource<C, R, M> {
+    /// Create a new source from a table and the embedding model that was used to create the embeddings in the table.
+    pub fn new(table: EmbeddingIndexedTable<C, R, M::VectorSpace>, embedding_model: M) -> Self {
+        Self {
+            table,
+            embedding_model,
+        }
+    }
+}
+
+impl<C, R, M> DocumentSearchSource for EmbeddingIndexedTableSource<C, R, M>
+where
+    C: Connection,
+    R: AsRef<Document> + DeserializeOwned + Send + Sync + 'static,
+    M: Embedder,
+{
+    async fn search_chunks(
+        &self,
+        query: &str,
+        k: usize,
+    ) -> anyhow::Result<Vec<DocumentSearchChunk>> {
+        let embedding = self.embedding_model.embed_query(query).await?;
+        let results = self.table.select_nearest(embedding, k).await?;
+        Ok(results
+            .into_iter()
+            .map(|result| DocumentSearchChunk {
+                title: result.record.as_ref().title().to_string(),
+                text: result.text(),
+                distance: result.distance,
+            })
+            .collect())
+    }
+}
+
+/// A tool that can search local documents
+///
+/// The tool searches a [`DocumentTable`] (or an [`EmbeddingIndexedTable`]) for the chunks closest to the query and returns the text of
+/// those chunks along with the title of the document each chunk is from.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use kalosm::language::*;
+/// use surrealdb::{engine::local::RocksDb, Surreal};
+///
+/// #[tokio::main]
+/// async fn main() -> anyhow::Result<()> {
+///     let db = Surreal::new::<RocksDb>("./db/temp.db").await?;
+///     db.use_ns("test").use_db("test").await?;
+///     let document_table = db
+///         .document_table_builder("documents")
+///         .at("./db/embeddings.db")
+///         .build::<Document>()
+///         .await?;
+///
+///     let tools = ToolManager::new().with_tool(
+///         DocumentSearchTool::new(document_table)
+///             .with_top_k(3)
+///             .with_max_tokens(512),
+///     );
+///     Ok(())
+/// }
+/// ```
+pub struct DocumentSearchTool<T> {
+    source: T,
+    top_k: usize,
+    max_tokens: usize,
+    max_chunk_tokens: usize,
+    count_tokens: Box<dyn Fn(&str) -> usize + Send + Sync>,
+}
+
+impl<T: DocumentSearchSource> DocumentSearchTool<T> {
+    /// Create a new document search tool that searches the given documents
+    pub fn new(source: T) -> Self {
+        Self {
+            source,
+            top_k: 5,
+            max_tokens: 1024,
+            max_chunk_tokens: 300,
+            count_tokens: Box::new(|text| text.split_whitespace().count()),
+        }
+    }
+
+    /// Set the number of chunks the tool returns. Defaults to 5.
+    pub fn with_top_k(mut self, top_k: usize) -> Self {
+        self.top_k = top_k;
+        self
+    }
+
+    /// Set the maximum number of tokens of all chunks the tool returns. Defaults to 1024.
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Set the maximum number of tokens of a single chunk the tool returns. Longer chunks will be truncated. Defaults to 300.
+    pub fn with_max_chunk_tokens(mut self, max_chunk_tokens: usize) -> Self {
+        self.max_chunk_tokens = max_chunk_tokens;
+        self
+    }
+
+    /// Set the function used to count the tokens in some text. By default every word is counted as one token.
+    ///
+    /// You can use the tokenizer of your model to get exact token counts:
+    ///
+    /// ```rust, no_run
+    /// # use kalosm::language::*;
+    /// # fn tool(table: impl DocumentSearchSource, llm: &Llama) -> DocumentSearchTool<impl DocumentSearchSource> {
+    /// let tokenizer = llm.tokenizer();
+    /// DocumentSearchTool::new(table).with_token_counter(move |text| {
+    ///     tokenizer
+    ///         .encode(text, false)
+    ///         .map(|encoding| encoding.len())
+    ///         .unwrap_or_default()
+    /// })
+    /// # }
+    /// ```
+    pub fn with_token_counter(
+        mut self,
+        count_tokens: impl Fn(&str) -> usize + Send + Sync + 'static,
+    ) -> Self {
+        self.count_tokens = Box::new(count_tokens);
+        self
+    }
+
+    /// Get the source of the documents the tool searches.
+    pub fn source(&self) -> &T {
+        &self.source
+    }
+
+    /// Format the chunks into the text returned to the model, staying within the token budgets.
+    fn format_chunks(&self, chunks: Vec<DocumentSearchChunk>) -> String {
+        let mut text = String::new();
+        let mut remaining_tokens = self.max_tokens;
+        let chunks = chunks
+            .into_iter()
+            .filter(|chunk| !chunk.text.trim().is_empty());
+        for (index, chunk) in chunks.enumerate() {
+            let header = format!("[{}] {}\n", index + 1, chunk.title);
+            let header_tokens = (self.count_tokens)(&header);
+            if header_tokens >= remaining_tokens {
+                break;
+            }
+            remaining_tokens -= header_tokens;
+
+            let budget = self.max_chunk_tokens.min(remaining_tokens);
+            let body = truncate_to_tokens(chunk.text.trim(), budget, &self.count_tokens);
+            if body.is_empty() {
+                break;
+            }
+            remaining_tokens = remaining_tokens.saturating_sub((self.count_tokens)(body));
+
+            text.push_str(&header);
+            text.push_str(body);
+            text.push_str("\n\n");
+        }
+        text
+    }
+}
+
+impl<T: DocumentSearchSource> Tool for DocumentSearchTool<T> {
+    type Input = String;
+
+    fn input_parser(
+        &self,
+    ) -> impl CreateParserState<Output = Self::Input, PartialState: Send + Sync + 'static>
+           + Send
+           + Sync
+           + 'static {
+        OneLine
+    }
+
+    fn name(&self) -> String {
+        "Local Search".to_string()
+    }
+
+    fn input_prompt(&self) -> String {
+        "Search query: ".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Search local documents for a query.\nUse tool with:\nAction: Local Search\nSearch query: the search query\nExample:\n\nQuestion: What is Floneum?\nThought: I don't remember what Floneum is. I should search for it.\nAction: Local Search\nAction Input: What is Floneum?\nObservation: [1] Floneum\nFloneum is a visual editor for AI workflows.\nThought: I now know that Floneum is a visual editor for AI workflows.\nFinal Answer: Floneum is a visual editor for AI workflows.".to_string()
+    }
+
+    async fn run<'a>(&'a mut self, query: &'a Self::Input) -> String {
+        match self.source.search_chunks(query, self.top_k).await {
+            Ok(chunks) if chunks.is_empty() => "No matching documents found.".to_string(),
+            Ok(chunks) => self.format_chunks(chunks),
+            Err(err) => format!("Failed to search local documents: {err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+struct StaticChunks(Vec<DocumentSearchChunk>);
+
+#[cfg(test)]
+impl DocumentSearchSource for StaticChunks {
+    async fn search_chunks(
+        &self,
+        _query: &str,
+        k: usize,
+    ) -> anyhow::Result<Vec<DocumentSearchChunk>> {
+        Ok(self.0.iter().take(k).cloned().collect())
+    }
+}
+
+#[cfg(test)]
+fn chunk(title: &str, text: &str) -> DocumentSearchChunk {
+    DocumentSearchChunk {
+        title: title.to_string(),
+        text: text.to_string(),
+        distance: 0.0,
+    }
+}
+
+#[test]
+fn chunks_are_formatted_within_the_token_budgets() {
+    let tool = DocumentSearchTool::new(StaticChunks(Vec::new()))
+        .with_max_tokens(10)
+        .with_max_chunk_tokens(4);
+    let formatted = tool.format_chunks(vec![
+        chunk("Kalosm", "Kalosm is a library for local AI."),
+        chunk("Empty", "  \n "),
+        chunk("Floneum", "Floneum is a visual editor."),
+        chunk("Dropped", "This chunk does not fit in the budget."),
+    ]);
+    // Chunks are truncated to the chunk budget, empty chunks are skipped and the rest is cut off at the total budget
+    assert_eq!(
+        formatted,
+        "[1] Kalosm\nKalosm is a library\n\n[2] Floneum\nFloneum is\n\n"
+    );
+}
+
+#[tokio::test]
+async fn search_results_are_returned_to_the_model() {
+    let mut tool = DocumentSearchTool::new(StaticChunks(vec![
+        chunk("Kalosm", "Kalosm is a library for local AI."),
+        chunk("Floneum", "Floneum is a visual editor."),
+    ]))
+    .with_top_k(1);
+    assert_eq!(
+        tool.run(&"What is Kalosm?".to_string()).await,
+        "[1] Kalosm\nKalosm is a library for local AI.\n\n"
+    );
+
+    let mut tool = DocumentSearchTool::new(StaticChunks(Vec::new()));
+    assert_eq!(
+        tool.run(&"What is Kalosm?".to_string()).await,
+        "No matching documents found."
+    );
+}
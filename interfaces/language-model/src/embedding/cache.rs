@@ -187,6 +187,14 @@ impl<M: Embedder> Embedder for CachedEmbeddingModel<M> {
     /// The vector space that this embedder uses.
     type VectorSpace = M::VectorSpace;
 
+    fn model_id(&self) -> String {
+        self.model.model_id()
+    }
+
+    fn count_tokens(&self, text: &str) -> Option<usize> {
+        self.model.count_tokens(text)
+    }
+
     /// Embed a single string.
     fn embed_for(
         &self,
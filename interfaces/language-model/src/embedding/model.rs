@@ -31,6 +31,21 @@ pub trait Embedder: Send + Sync + 'static {
     /// The vector space that this embedder uses.
     type VectorSpace: VectorSpace + Send + Sync + 'static;
 
+    /// A name that identifies the model. Embeddings from models with different ids should not be compared.
+    ///
+    /// Databases store this id with their embeddings to catch queries with a different model. Defaults to the name of the type.
+    fn model_id(&self) -> String {
+        std::any::type_name::<Self>().to_string()
+    }
+
+    /// Count the number of tokens the model sees for some text, or `None` if the tokenizer of the model is not known.
+    ///
+    /// Chunkers use this to size chunks so they fit in the context of the model.
+    fn count_tokens(&self, text: &str) -> Option<usize> {
+        let _ = text;
+        None
+    }
+
     /// Embed some text into a vector space.
     fn embed_string(
         &self,
@@ -172,6 +187,14 @@ struct AnyEmbedder<E: Embedder + Send + Sync + 'static>(E);
 impl<E: Embedder + Send + Sync + 'static> Embedder for AnyEmbedder<E> {
     type VectorSpace = UnknownVectorSpace;
 
+    fn model_id(&self) -> String {
+        self.0.model_id()
+    }
+
+    fn count_tokens(&self, text: &str) -> Option<usize> {
+        self.0.count_tokens(text)
+    }
+
     fn embed_string(
         &self,
         input: String,
@@ -240,6 +240,10 @@ impl AdaEmbedder {
 impl Embedder for AdaEmbedder {
     type VectorSpace = AdaEmbedding;
 
+    fn model_id(&self) -> String {
+        Self::MODEL_ID.to_string()
+    }
+
     fn embed_for(
         &self,
         input: crate::EmbeddingInput,
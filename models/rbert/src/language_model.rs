@@ -57,6 +57,16 @@ impl Bert {
 impl Embedder for Bert {
     type VectorSpace = BertSpace;
 
+    fn model_id(&self) -> String {
+        self.model_id.to_string()
+    }
+
+    fn count_tokens(&self, text: &str) -> Option<usize> {
+        let tokenizer = self.tokenizer.read().unwrap();
+        let encoding = tokenizer.encode(text, true).ok()?;
+        Some(encoding.len())
+    }
+
     fn embed_for(
         &self,
         input: EmbeddingInput,
@@ -113,6 +113,7 @@ pub enum Pooling {
 /// A bert model
 #[derive(Clone)]
 pub struct Bert {
+    model_id: Arc<String>,
     embedding_search_prefix: Arc<Option<String>>,
     model: Arc<BertModel>,
     tokenizer: Arc<RwLock<Tokenizer>>,
@@ -163,6 +164,7 @@ impl Bert {
                 progress_handler(create_progress(progress))
             })
             .await?;
+        let model_id = model.to_string();
         let model_source = format!("Model ({})", model);
         let mut create_progress = ModelLoadingProgress::downloading_progress(model_source);
         let weights_filename = cache
@@ -183,6 +185,7 @@ impl Bert {
         tokenizer.with_padding(None);
 
         Ok(Bert {
+            model_id: Arc::new(model_id),
             tokenizer: Arc::new(RwLock::new(tokenizer)),
             model: Arc::new(model),
             embedding_search_prefix: Arc::new(search_embedding_prefix),